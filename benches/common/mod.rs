@@ -0,0 +1,31 @@
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const ACQUIRES_PER_THREAD: u64 = 10_000;
+
+/// Starts `threads` threads together, each calling `acquire(thread_id, i)` for
+/// `ACQUIRES_PER_THREAD` values of `i`, and returns the wall time of the slowest.
+pub fn contend<F: Fn(u64, u64) + Send + Sync + 'static>(threads: usize, acquire: F) -> Duration {
+    let acquire = Arc::new(acquire);
+    let barrier = Arc::new(Barrier::new(threads + 1));
+    let handles: Vec<_> = (0..threads as u64)
+        .map(|thread_id| {
+            let acquire = Arc::clone(&acquire);
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                barrier.wait();
+                for i in 0..ACQUIRES_PER_THREAD {
+                    acquire(thread_id, i);
+                }
+            })
+        })
+        .collect();
+
+    barrier.wait();
+    let start = Instant::now();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    start.elapsed()
+}
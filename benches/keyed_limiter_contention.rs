@@ -0,0 +1,63 @@
+// cargo bench --bench keyed_limiter_contention
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::ACQUIRES_PER_THREAD;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rate_limiters::core::KeyedRateLimiterShared;
+use rate_limiters::keyed::{KeyedLimiter, ShardedKeyedLimiter};
+use rate_limiters::token_bucket::TokenBucket;
+
+const KEYS: u64 = 1_024;
+
+/// Runs `threads` threads cycling through `KEYS` keys, each starting at its own offset so they
+/// hit different keys at once, and returns the wall time of the slowest.
+fn contend<L: KeyedRateLimiterShared<u64> + Send + Sync + 'static>(
+    limiter: Arc<L>,
+    threads: usize,
+) -> Duration {
+    common::contend(threads, move |thread_id, i| {
+        let key = (thread_id * ACQUIRES_PER_THREAD + i) % KEYS;
+        std::hint::black_box(limiter.try_acquire(&key, 1));
+    })
+}
+
+fn bucket(_: &u64) -> TokenBucket {
+    TokenBucket::new(1_000_000, 1_000_000)
+}
+
+fn keyed_limiter_contention(c: &mut Criterion) {
+    let mut group = c.benchmark_group("keyed_limiter_contention");
+    group.sample_size(10);
+
+    for threads in [1, 4, 16, 64] {
+        group.bench_with_input(
+            BenchmarkId::new("single_lock", threads),
+            &threads,
+            |b, &threads| {
+                b.iter_custom(|iters| {
+                    (0..iters)
+                        .map(|_| contend(Arc::new(KeyedLimiter::new(bucket)), threads))
+                        .sum()
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("sharded", threads),
+            &threads,
+            |b, &threads| {
+                b.iter_custom(|iters| {
+                    (0..iters)
+                        .map(|_| contend(Arc::new(ShardedKeyedLimiter::new(bucket)), threads))
+                        .sum()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, keyed_limiter_contention);
+criterion_main!(benches);
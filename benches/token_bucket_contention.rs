@@ -0,0 +1,63 @@
+// cargo bench --bench token_bucket_contention
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rate_limiters::core::RateLimiterShared;
+use rate_limiters::token_bucket::{AtomicTokenBucket, TokenBucketShared};
+
+/// Runs `threads` threads hammering `try_acquire(1)` and returns the wall time of the slowest.
+fn contend<L: RateLimiterShared + Send + Sync + 'static>(
+    limiter: Arc<L>,
+    threads: usize,
+) -> Duration {
+    common::contend(threads, move |_, _| {
+        std::hint::black_box(limiter.try_acquire(1));
+    })
+}
+
+fn token_bucket_contention(c: &mut Criterion) {
+    let mut group = c.benchmark_group("token_bucket_contention");
+    group.sample_size(10);
+
+    for threads in [1, 4, 16, 64] {
+        group.bench_with_input(
+            BenchmarkId::new("mutex", threads),
+            &threads,
+            |b, &threads| {
+                b.iter_custom(|iters| {
+                    (0..iters)
+                        .map(|_| {
+                            contend(
+                                Arc::new(TokenBucketShared::new(1_000_000, 1_000_000)),
+                                threads,
+                            )
+                        })
+                        .sum()
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("atomic", threads),
+            &threads,
+            |b, &threads| {
+                b.iter_custom(|iters| {
+                    (0..iters)
+                        .map(|_| {
+                            contend(
+                                Arc::new(AtomicTokenBucket::new(1_000_000, 1_000_000)),
+                                threads,
+                            )
+                        })
+                        .sum()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, token_bucket_contention);
+criterion_main!(benches);
@@ -0,0 +1,32 @@
+// cargo run --example async_limiter_usage --features tokio
+use std::sync::Arc;
+use std::time::Instant;
+
+use rate_limiters::asynchronous::AsyncLimiter;
+use rate_limiters::token_bucket::TokenBucketShared;
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let limiter = Arc::new(AsyncLimiter::new(TokenBucketShared::new(5, 2)));
+    let start = Instant::now();
+    let mut handles = vec![];
+
+    for client_id in 0..3 {
+        let limiter = Arc::clone(&limiter);
+        handles.push(tokio::spawn(async move {
+            for req_id in 0..5 {
+                let decision = limiter.acquire(1).await.unwrap();
+
+                let elapsed = start.elapsed().as_secs_f32();
+                println!(
+                    "[{elapsed:5.2}s] Client #{client_id} - Request #{req_id} - Allowed - Remaining {}",
+                    decision.remaining
+                );
+            }
+        }));
+    }
+
+    for handle in handles {
+        handle.await.unwrap();
+    }
+}
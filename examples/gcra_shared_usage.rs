@@ -0,0 +1,58 @@
+// cargo run --example gcra_shared_usage
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rate_limiters::core::Rate;
+use rate_limiters::core::RateLimiterShared;
+use rate_limiters::gcra::GcraShared;
+
+fn main() {
+    let gcra = Arc::new(GcraShared::with_rate(Rate::per_second(1), 10));
+
+    let start = Instant::now();
+    let mut handles = vec![];
+
+    for client_id in 0..5 {
+        let gcra_clone = Arc::clone(&gcra);
+        handles.push(thread::spawn(move || {
+            let mut allowed = 0;
+            let mut denied = 0;
+
+            for req_id in 0..20 {
+                let decision = gcra_clone.try_acquire_decision(1);
+
+                let elapsed = start.elapsed().as_secs_f32();
+                if decision.allowed {
+                    allowed += 1;
+                    println!(
+                        "[{elapsed:5.2}s] Client #{client_id} - Request #{req_id} - Allowed - Remaining {}",
+                        decision.remaining
+                    );
+                } else {
+                    denied += 1;
+                    println!(
+                        "[{elapsed:5.2}s] Client #{client_id} - Request #{req_id} - Rejected - Retry after {:.2}s - Reset UNIX {}",
+                        decision.retry_after.as_secs_f32(),
+                        decision.reset
+                    );
+                }
+
+                thread::sleep(Duration::from_millis(500));
+            }
+
+            println!("Client #{client_id} finished: allowed={allowed}, denied={denied}");
+        }));
+    }
+
+    for h in handles {
+        h.join().unwrap();
+    }
+
+    println!(
+        "\n[Final] Used: {}, Remaining: {} (limit={})",
+        gcra.get_used(),
+        gcra.get_remaining(),
+        gcra.get_limit()
+    );
+}
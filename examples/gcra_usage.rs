@@ -0,0 +1,37 @@
+// cargo run --example gcra_usage
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rate_limiters::core::RateLimiter;
+use rate_limiters::gcra::Gcra;
+
+fn main() {
+    let start = Instant::now();
+    let mut gcra = Gcra::new(Duration::from_millis(500), Duration::from_secs(2));
+
+    for i in 0..100 {
+        gcra.refresh();
+        let limit = gcra.get_limit();
+        let remaining = gcra.get_remaining();
+        let used = gcra.get_used();
+        let reset = gcra.get_reset();
+        let is_acquired = gcra.try_acquire(1);
+
+        let elapsed = start.elapsed().as_secs_f32();
+        println!(
+            "[{elapsed:5.2}s] Request #{:03} | {:<12} | Limit: {:2} | Remaining: {:2} | Used: {:2} | Reset: {}",
+            i + 1,
+            if is_acquired {
+                "Allowed"
+            } else {
+                "Rate limited"
+            },
+            limit,
+            remaining,
+            used,
+            reset
+        );
+
+        thread::sleep(Duration::from_millis(300));
+    }
+}
@@ -0,0 +1,36 @@
+// cargo run --example keyed_limiter_usage
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rate_limiters::core::KeyedRateLimiterShared;
+use rate_limiters::keyed::KeyedLimiter;
+use rate_limiters::token_bucket::TokenBucket;
+
+fn main() {
+    let start = Instant::now();
+    let limiter = KeyedLimiter::new(|_: &String| TokenBucket::new(3, 1));
+    let clients = ["10.0.0.1", "10.0.0.2", "10.0.0.3"];
+
+    for i in 0..30 {
+        let client = clients[i % clients.len()];
+        let decision = limiter.try_acquire_decision(client, 1);
+
+        let elapsed = start.elapsed().as_secs_f32();
+        println!(
+            "[{elapsed:5.2}s] Request #{:03} | {:<8} | {:<12} | Remaining: {} | Retry after: {:.2}s",
+            i + 1,
+            client,
+            if decision.allowed {
+                "Allowed"
+            } else {
+                "Rate limited"
+            },
+            decision.remaining,
+            decision.retry_after.as_secs_f32()
+        );
+
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    println!("\n[Final] Tracked keys: {}", limiter.len());
+}
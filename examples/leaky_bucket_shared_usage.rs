@@ -3,8 +3,8 @@ use std::sync::Arc;
 use std::thread;
 use std::time::{Duration, Instant};
 
+use rate_limiters::core::RateLimiterShared;
 use rate_limiters::leaky_bucket::LeakyBucketShared;
-use rate_limiters::token_bucket::r#impl::RateLimiterShared;
 
 fn main() {
     let bucket = Arc::new(LeakyBucketShared::new(5, 3.0));
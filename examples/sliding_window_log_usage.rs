@@ -2,8 +2,8 @@
 use std::thread;
 use std::time::{Duration, Instant};
 
+use rate_limiters::core::RateLimiter;
 use rate_limiters::sliding_window_log::SlidingWindowLog;
-use rate_limiters::token_bucket::r#impl::RateLimiter;
 
 fn main() {
     let start = Instant::now();
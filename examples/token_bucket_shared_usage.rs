@@ -3,7 +3,7 @@ use std::sync::Arc;
 use std::thread;
 use std::time::{Duration, Instant};
 
-use rate_limiters::token_bucket::r#impl::RateLimiterShared;
+use rate_limiters::core::RateLimiterShared;
 use rate_limiters::token_bucket::TokenBucketShared;
 
 fn main() {
@@ -19,21 +19,21 @@ fn main() {
             let mut denied = 0;
 
             for req_id in 0..20 {
-                let ok = bucket_clone.try_acquire(1);
+                let decision = bucket_clone.try_acquire_decision(1);
 
                 let elapsed = start.elapsed().as_secs_f32();
-                if ok {
+                if decision.allowed {
                     allowed += 1;
                     println!(
                         "[{elapsed:5.2}s] Client #{client_id} - Request #{req_id} - Allowed - Remaining {}",
-                        bucket_clone.get_remaining()
+                        decision.remaining
                     );
                 } else {
                     denied += 1;
-                    bucket_clone.refresh(); // <-- It's must to get reset value w/ "jumping"
                     println!(
-                        "[{elapsed:5.2}s] Client #{client_id} - Request #{req_id} - Rejected - Reset UNIX {}",
-                        bucket_clone.get_reset()
+                        "[{elapsed:5.2}s] Client #{client_id} - Request #{req_id} - Rejected - Retry after {:.2}s - Reset UNIX {}",
+                        decision.retry_after.as_secs_f32(),
+                        decision.reset
                     );
                 }
 
@@ -2,7 +2,7 @@
 use std::thread;
 use std::time::{Duration, Instant};
 
-use rate_limiters::token_bucket::r#impl::RateLimiter;
+use rate_limiters::core::RateLimiter;
 use rate_limiters::token_bucket::TokenBucket;
 
 fn main() {
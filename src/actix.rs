@@ -0,0 +1,6 @@
+pub mod r#impl;
+pub mod tests;
+
+pub use crate::core::{Decision, KeyedRateLimiterShared, RateLimiterShared};
+pub use crate::headers::RateLimitHeaders;
+pub use r#impl::{peer_ip, RateLimit, RateLimitInfo, RateLimitMiddleware};
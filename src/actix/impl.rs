@@ -0,0 +1,158 @@
+use std::future::{ready, Future, Ready};
+use std::net::IpAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::error::ErrorInternalServerError;
+use actix_web::{Error, FromRequest, HttpMessage, HttpRequest, HttpResponse};
+
+use crate::core::{Decision, KeyedRateLimiterShared, RateLimiterShared};
+use crate::headers::RateLimitHeaders;
+
+type Check = Arc<dyn Fn(&ServiceRequest) -> Decision + Send + Sync>;
+type Deny = Arc<dyn Fn(&ServiceRequest, &Decision) -> HttpResponse + Send + Sync>;
+
+/// Key function for limiting by the client IP.
+pub fn peer_ip(req: &ServiceRequest) -> Option<IpAddr> {
+    req.peer_addr().map(|addr| addr.ip())
+}
+
+// *** RATE LIMIT INFO ***
+/// The decision made for the current request, available to handlers as an extractor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitInfo(pub Decision);
+
+impl FromRequest for RateLimitInfo {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(
+            req.extensions()
+                .get::<RateLimitInfo>()
+                .copied()
+                .ok_or_else(|| ErrorInternalServerError("rate limit middleware is missing")),
+        )
+    }
+}
+
+// *** RATE LIMIT ***
+/// Middleware taking one token per request. A throttled request gets `429 Too Many Requests`
+/// with `Retry-After` unless `deny_with` replaces it, and every response gets the
+/// `RateLimit-*` and `X-RateLimit-*` headers.
+#[derive(Clone)]
+pub struct RateLimit {
+    check: Check,
+    deny: Deny,
+}
+
+impl RateLimit {
+    pub fn new<L>(limiter: L) -> Self
+    where
+        L: RateLimiterShared + Send + Sync + 'static,
+    {
+        Self::from_arc(Arc::new(limiter))
+    }
+
+    pub fn from_arc<L>(limiter: Arc<L>) -> Self
+    where
+        L: RateLimiterShared + Send + Sync + 'static,
+    {
+        Self::with_check(Arc::new(move |_| limiter.try_acquire_decision(1)))
+    }
+
+    /// Limits every key `key` returns for a request separately, e.g. by `peer_ip`.
+    pub fn keyed<L, K, F>(limiter: L, key: F) -> Self
+    where
+        L: KeyedRateLimiterShared<K> + Send + Sync + 'static,
+        F: Fn(&ServiceRequest) -> K + Send + Sync + 'static,
+    {
+        Self::keyed_from_arc(Arc::new(limiter), key)
+    }
+
+    pub fn keyed_from_arc<L, K, F>(limiter: Arc<L>, key: F) -> Self
+    where
+        L: KeyedRateLimiterShared<K> + Send + Sync + 'static,
+        F: Fn(&ServiceRequest) -> K + Send + Sync + 'static,
+    {
+        Self::with_check(Arc::new(move |req| {
+            limiter.try_acquire_decision(&key(req), 1)
+        }))
+    }
+
+    /// Builds the response for a throttled request, the rate limit headers are added to it.
+    pub fn deny_with<F>(mut self, deny: F) -> Self
+    where
+        F: Fn(&ServiceRequest, &Decision) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.deny = Arc::new(deny);
+        self
+    }
+
+    fn with_check(check: Check) -> Self {
+        Self {
+            check,
+            deny: Arc::new(|_, _| HttpResponse::TooManyRequests().body("Too Many Requests")),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RateLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service,
+            check: Arc::clone(&self.check),
+            deny: Arc::clone(&self.deny),
+        }))
+    }
+}
+
+// *** RATE LIMIT MIDDLEWARE ***
+pub struct RateLimitMiddleware<S> {
+    service: S,
+    check: Check,
+    deny: Deny,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let decision = (self.check)(&req);
+        let headers = RateLimitHeaders::from_decision(&decision);
+        if !decision.allowed {
+            let mut response = (self.deny)(&req, &decision);
+            headers.insert_into(response.headers_mut());
+            let response = req.into_response(response).map_into_right_body();
+            return Box::pin(ready(Ok(response)));
+        }
+
+        req.extensions_mut().insert(RateLimitInfo(decision));
+        let future = self.service.call(req);
+        Box::pin(async move {
+            let mut response = future.await?;
+            headers.insert_into(response.headers_mut());
+            Ok(response.map_into_left_body())
+        })
+    }
+}
@@ -0,0 +1,129 @@
+#[cfg(test)]
+mod sequential_tests {
+    use crate::actix::{peer_ip, RateLimit, RateLimitInfo};
+    use crate::clock::MockClock;
+    use crate::keyed::KeyedLimiter;
+    use crate::token_bucket::{TokenBucket, TokenBucketShared};
+    use actix_web::dev::ServiceResponse;
+    use actix_web::http::StatusCode;
+    use actix_web::rt::System;
+    use actix_web::test::{call_service, init_service, read_body, TestRequest};
+    use actix_web::{web, App, HttpResponse};
+    use std::net::{IpAddr, SocketAddr};
+    use std::time::Duration;
+
+    async fn handler(RateLimitInfo(decision): RateLimitInfo) -> String {
+        format!("remaining {}", decision.remaining)
+    }
+
+    fn header<B>(response: &ServiceResponse<B>, name: &str) -> Option<String> {
+        response
+            .headers()
+            .get(name)
+            .map(|value| value.to_str().unwrap().to_string())
+    }
+
+    #[test]
+    fn global_test() {
+        System::new().block_on(async {
+            let clock = MockClock::new();
+            let bucket = TokenBucketShared::with_clock(2, 1, clock.clone());
+            let app = init_service(
+                App::new()
+                    .wrap(RateLimit::new(bucket))
+                    .route("/", web::get().to(handler)),
+            )
+            .await;
+
+            let response = call_service(&app, TestRequest::get().to_request()).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(header(&response, "ratelimit-limit").as_deref(), Some("2"));
+            assert_eq!(
+                header(&response, "ratelimit-remaining").as_deref(),
+                Some("1")
+            );
+            assert_eq!(header(&response, "x-ratelimit-limit").as_deref(), Some("2"));
+            assert_eq!(header(&response, "retry-after"), None);
+            assert_eq!(read_body(response).await, "remaining 1");
+
+            call_service(&app, TestRequest::get().to_request()).await;
+            let response = call_service(&app, TestRequest::get().to_request()).await;
+            assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+            assert_eq!(
+                header(&response, "ratelimit-remaining").as_deref(),
+                Some("0")
+            );
+            assert_eq!(header(&response, "retry-after").as_deref(), Some("1"));
+            assert!(header(&response, "x-ratelimit-reset").is_some());
+
+            clock.advance(Duration::from_secs(1));
+            let response = call_service(&app, TestRequest::get().to_request()).await;
+            assert_eq!(response.status(), StatusCode::OK);
+        });
+    }
+
+    #[test]
+    fn keyed_test() {
+        System::new().block_on(async {
+            let limiter = KeyedLimiter::new(|_: &Option<IpAddr>| TokenBucket::new(1, 1));
+            let app = init_service(
+                App::new()
+                    .wrap(RateLimit::keyed(limiter, peer_ip))
+                    .route("/", web::get().to(handler)),
+            )
+            .await;
+
+            let from = |addr: &str| {
+                TestRequest::get()
+                    .peer_addr(addr.parse::<SocketAddr>().unwrap())
+                    .to_request()
+            };
+
+            let response = call_service(&app, from("10.0.0.1:5000")).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let response = call_service(&app, from("10.0.0.1:5001")).await;
+            assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+            let response = call_service(&app, from("10.0.0.2:5000")).await;
+            assert_eq!(response.status(), StatusCode::OK);
+        });
+    }
+
+    #[test]
+    fn deny_with_test() {
+        System::new().block_on(async {
+            let limiter = KeyedLimiter::new(|_: &String| TokenBucket::new(1, 1));
+            let by_api_key = |req: &actix_web::dev::ServiceRequest| {
+                req.headers()
+                    .get("x-api-key")
+                    .and_then(|value| value.to_str().ok())
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            let middleware = RateLimit::keyed(limiter, by_api_key).deny_with(|_, decision| {
+                HttpResponse::ServiceUnavailable()
+                    .json(format!("retry in {}ms", decision.retry_after.as_millis()))
+            });
+            let app = init_service(
+                App::new()
+                    .wrap(middleware)
+                    .route("/", web::get().to(handler)),
+            )
+            .await;
+
+            let with_key = |key: &str| {
+                TestRequest::get()
+                    .insert_header(("x-api-key", key))
+                    .to_request()
+            };
+
+            let response = call_service(&app, with_key("a")).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let response = call_service(&app, with_key("a")).await;
+            assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+            assert_eq!(header(&response, "ratelimit-limit").as_deref(), Some("1"));
+            assert!(header(&response, "retry-after").is_some());
+            let body = read_body(response).await;
+            assert!(String::from_utf8_lossy(&body).starts_with("\"retry in"));
+        });
+    }
+}
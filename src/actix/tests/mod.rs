@@ -0,0 +1 @@
+mod actix_tests;
@@ -0,0 +1,7 @@
+pub mod r#impl;
+pub mod tests;
+
+pub use crate::core::{Decision, RateLimitError, RateLimiterShared};
+#[cfg(feature = "tokio")]
+pub use r#impl::TokioSleeper;
+pub use r#impl::{AsyncLimiter, Sleeper};
@@ -0,0 +1,163 @@
+use std::collections::VecDeque;
+use std::future::{poll_fn, Future};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+use crate::core::{Decision, RateLimitError, RateLimiterShared, MIN_WAIT};
+
+// *** SLEEPER ***
+/// Timer of the async runtime in use, the only runtime-specific piece of `AsyncLimiter`.
+pub trait Sleeper {
+    type Sleep: Future<Output = ()>;
+
+    fn sleep(&self, duration: Duration) -> Self::Sleep;
+}
+
+/// `Sleeper` backed by `tokio::time::sleep`.
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioSleeper;
+
+#[cfg(feature = "tokio")]
+impl Sleeper for TokioSleeper {
+    type Sleep = tokio::time::Sleep;
+
+    fn sleep(&self, duration: Duration) -> Self::Sleep {
+        tokio::time::sleep(duration)
+    }
+}
+
+// *** WAITERS ***
+struct Waiter {
+    ticket: u64,
+    waker: Option<Waker>,
+}
+
+#[derive(Default)]
+struct Waiters {
+    next_ticket: u64,
+    queue: VecDeque<Waiter>,
+}
+
+/// A place in the waiter queue, given up when dropped.
+struct Turn<'a> {
+    waiters: &'a Mutex<Waiters>,
+    ticket: u64,
+}
+
+impl<'a> Turn<'a> {
+    fn new(waiters: &'a Mutex<Waiters>) -> Self {
+        let mut guard = lock(waiters);
+        let ticket = guard.next_ticket;
+        guard.next_ticket += 1;
+        guard.queue.push_back(Waiter {
+            ticket,
+            waker: None,
+        });
+
+        Self { waiters, ticket }
+    }
+
+    /// Ready once every earlier waiter has left the queue.
+    fn poll_front(&self, cx: &mut Context<'_>) -> Poll<()> {
+        let mut guard = lock(self.waiters);
+        let Some(position) = guard.queue.iter().position(|w| w.ticket == self.ticket) else {
+            return Poll::Ready(());
+        };
+        if position == 0 {
+            return Poll::Ready(());
+        }
+        guard.queue[position].waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for Turn<'_> {
+    fn drop(&mut self) {
+        let mut guard = lock(self.waiters);
+        let Some(position) = guard.queue.iter().position(|w| w.ticket == self.ticket) else {
+            return;
+        };
+        guard.queue.remove(position);
+        if position == 0 {
+            if let Some(waker) = guard.queue.front_mut().and_then(|w| w.waker.take()) {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// The queue stays consistent across panics, so a poisoned lock is still usable.
+fn lock(waiters: &Mutex<Waiters>) -> MutexGuard<'_, Waiters> {
+    waiters.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+// *** ASYNC LIMITER ***
+/// Wraps any shared limiter with an `acquire` that waits for permits instead of failing.
+/// Waiters are served in FIFO order: a request only tries the limiter once everyone who
+/// called `acquire` before it got their permits or gave up.
+pub struct AsyncLimiter<L, S> {
+    limiter: L,
+    sleeper: S,
+    waiters: Mutex<Waiters>,
+}
+
+#[cfg(feature = "tokio")]
+impl<L: RateLimiterShared> AsyncLimiter<L, TokioSleeper> {
+    pub fn new(limiter: L) -> Self {
+        Self::with_sleeper(limiter, TokioSleeper)
+    }
+}
+
+impl<L: RateLimiterShared, S: Sleeper> AsyncLimiter<L, S> {
+    pub fn with_sleeper(limiter: L, sleeper: S) -> Self {
+        Self {
+            limiter,
+            sleeper,
+            waiters: Mutex::new(Waiters::default()),
+        }
+    }
+
+    pub fn limiter(&self) -> &L {
+        &self.limiter
+    }
+
+    pub fn into_inner(self) -> L {
+        self.limiter
+    }
+
+    /// Number of `acquire` calls currently waiting, including the one at the front.
+    pub fn waiting(&self) -> usize {
+        lock(&self.waiters).queue.len()
+    }
+
+    /// Waits until `tokens` permits are taken, sleeping exactly until the limiter expects
+    /// them to be available. Fails right away with `InsufficientCapacity` for a request that
+    /// can never pass.
+    ///
+    /// Cancellation safe: dropping the future before it completes takes no permits and lets
+    /// the next waiter go ahead.
+    pub async fn acquire(&self, tokens: u32) -> Result<Decision, RateLimitError> {
+        let capacity = self.limiter.get_limit();
+        if tokens > capacity {
+            return Err(RateLimitError::InsufficientCapacity {
+                requested: tokens,
+                capacity,
+            });
+        }
+
+        let turn = Turn::new(&self.waiters);
+        poll_fn(|cx| turn.poll_front(cx)).await;
+
+        loop {
+            match self.limiter.try_acquire_checked(tokens) {
+                Ok(decision) => return Ok(decision),
+                Err(RateLimitError::Denied(decision)) => {
+                    self.sleeper.sleep(decision.retry_after.max(MIN_WAIT)).await
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
@@ -0,0 +1,162 @@
+#[cfg(test)]
+mod sequential_tests {
+    use crate::asynchronous::{AsyncLimiter, Sleeper};
+    use crate::clock::MockClock;
+    use crate::core::{Rate, RateLimitError, RateLimiterShared};
+    use crate::token_bucket::{TokenBucket, TokenBucketShared};
+    use std::future::Future;
+    use std::pin::{pin, Pin};
+    use std::sync::Mutex;
+    use std::task::{Context, Poll, Waker};
+    use std::time::Duration;
+
+    /// Advances the mock clock right away, then yields once so other futures get polled.
+    struct MockSleeper {
+        clock: MockClock,
+    }
+
+    struct YieldOnce(bool);
+
+    impl Future for YieldOnce {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                return Poll::Ready(());
+            }
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    impl Sleeper for MockSleeper {
+        type Sleep = YieldOnce;
+
+        fn sleep(&self, duration: Duration) -> YieldOnce {
+            self.clock.advance(duration);
+            YieldOnce(false)
+        }
+    }
+
+    fn limiter(
+        capacity: u32,
+        clock: &MockClock,
+    ) -> AsyncLimiter<TokenBucketShared<MockClock>, MockSleeper> {
+        let bucket = TokenBucket::builder()
+            .capacity(capacity)
+            .rate(Rate::per_second(1))
+            .clock(clock.clone())
+            .build_shared()
+            .unwrap();
+        AsyncLimiter::with_sleeper(
+            bucket,
+            MockSleeper {
+                clock: clock.clone(),
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn acquire_test() {
+        let clock = MockClock::new();
+        let limiter = limiter(2, &clock);
+
+        let decision = limiter.acquire(2).await.unwrap();
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 0);
+        assert_eq!(clock.elapsed(), Duration::ZERO);
+
+        // Sleeps exactly until the refill instead of polling
+        limiter.acquire(1).await.unwrap();
+        assert_eq!(clock.elapsed(), Duration::from_secs(1));
+        limiter.acquire(2).await.unwrap();
+        assert_eq!(clock.elapsed(), Duration::from_secs(3));
+
+        assert!(matches!(
+            limiter.acquire(3).await,
+            Err(RateLimitError::InsufficientCapacity {
+                requested: 3,
+                capacity: 2
+            })
+        ));
+        assert_eq!(limiter.waiting(), 0);
+    }
+
+    #[tokio::test]
+    async fn fifo_test() {
+        let clock = MockClock::new();
+        let limiter = limiter(2, &clock);
+        limiter.acquire(2).await.unwrap();
+
+        let order = Mutex::new(vec![]);
+        tokio::join!(
+            async {
+                limiter.acquire(2).await.unwrap();
+                order.lock().unwrap().push("large");
+            },
+            async {
+                limiter.acquire(1).await.unwrap();
+                order.lock().unwrap().push("small");
+            },
+        );
+
+        // The small request could have passed first, but it queued behind the large one
+        assert_eq!(*order.lock().unwrap(), vec!["large", "small"]);
+        assert_eq!(clock.elapsed(), Duration::from_secs(3));
+    }
+
+    #[tokio::test]
+    async fn cancellation_test() {
+        let clock = MockClock::new();
+        let limiter = limiter(2, &clock);
+        limiter.acquire(2).await.unwrap();
+
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut small = Box::pin(limiter.acquire(1));
+        {
+            let mut large = pin!(limiter.acquire(2));
+            assert!(large.as_mut().poll(&mut cx).is_pending());
+            assert!(small.as_mut().poll(&mut cx).is_pending());
+            assert_eq!(limiter.waiting(), 2);
+        }
+
+        // The large request gave up without taking anything
+        assert_eq!(limiter.waiting(), 1);
+        small.await.unwrap();
+        assert_eq!(limiter.waiting(), 0);
+        assert_eq!(limiter.limiter().get_remaining(), 1);
+    }
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tokio_tests {
+    use crate::asynchronous::AsyncLimiter;
+    use crate::gcra::GcraShared;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    #[tokio::test]
+    async fn acquire_test() {
+        let limiter = Arc::new(AsyncLimiter::new(GcraShared::new(
+            Duration::from_millis(50),
+            Duration::ZERO,
+        )));
+        let start = Instant::now();
+
+        let mut handles = vec![];
+        for _ in 0..4 {
+            let limiter = Arc::clone(&limiter);
+            handles.push(tokio::spawn(async move {
+                limiter.acquire(1).await.unwrap();
+            }));
+        }
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(150));
+        assert!(elapsed < Duration::from_millis(400));
+    }
+}
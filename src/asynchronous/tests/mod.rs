@@ -0,0 +1 @@
+mod async_limiter_tests;
@@ -0,0 +1,8 @@
+pub mod r#impl;
+pub mod tests;
+
+pub use crate::core::{Decision, KeyedRateLimiterShared, RateLimiterShared};
+pub use crate::headers::RateLimitHeaders;
+pub use r#impl::{
+    HeaderKey, KeyExtractor, PeerIp, RateLimitInfo, RateLimitLayer, RateLimitService,
+};
@@ -0,0 +1,185 @@
+use std::convert::Infallible;
+use std::future::{ready, Future};
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use ::axum::extract::{ConnectInfo, FromRequestParts, Request};
+use ::axum::http::request::Parts;
+use ::axum::http::{HeaderName, StatusCode};
+use ::axum::response::{IntoResponse, Response};
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::core::{Decision, KeyedRateLimiterShared, RateLimiterShared};
+use crate::headers::RateLimitHeaders;
+
+type Check = Arc<dyn Fn(&Request) -> Decision + Send + Sync>;
+
+// *** KEY EXTRACTOR ***
+/// Picks the key a request is limited under. Any `Fn(&Request) -> K` closure works, e.g. to
+/// key by a user id an auth middleware put in the request extensions.
+pub trait KeyExtractor: Clone + Send + Sync + 'static {
+    type Key;
+
+    fn extract(&self, req: &Request) -> Self::Key;
+}
+
+impl<F, K> KeyExtractor for F
+where
+    F: Fn(&Request) -> K + Clone + Send + Sync + 'static,
+{
+    type Key = K;
+
+    fn extract(&self, req: &Request) -> K {
+        self(req)
+    }
+}
+
+/// Keys by the client IP from `ConnectInfo<SocketAddr>`, so the app must be served with
+/// `into_make_service_with_connect_info::<SocketAddr>()`. Requests without it share `None`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PeerIp;
+
+impl KeyExtractor for PeerIp {
+    type Key = Option<IpAddr>;
+
+    fn extract(&self, req: &Request) -> Self::Key {
+        req.extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip())
+    }
+}
+
+/// Keys by the value of a request header, e.g. `x-api-key`. Requests without it share `None`.
+#[derive(Debug, Clone)]
+pub struct HeaderKey(pub HeaderName);
+
+impl HeaderKey {
+    pub fn new(name: HeaderName) -> Self {
+        Self(name)
+    }
+}
+
+impl KeyExtractor for HeaderKey {
+    type Key = Option<String>;
+
+    fn extract(&self, req: &Request) -> Self::Key {
+        req.headers()
+            .get(&self.0)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned)
+    }
+}
+
+// *** RATE LIMIT INFO ***
+/// The decision made for the current request, available to handlers as an extractor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitInfo(pub Decision);
+
+impl<S: Send + Sync> FromRequestParts<S> for RateLimitInfo {
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<RateLimitInfo>().copied().ok_or((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "rate limit layer is missing",
+        ))
+    }
+}
+
+// *** RATE LIMIT LAYER ***
+/// Takes one token per request, answers `429 Too Many Requests` with `Retry-After` when
+/// throttled and sets the `RateLimit-*` and `X-RateLimit-*` headers on every response.
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    check: Check,
+}
+
+impl RateLimitLayer {
+    pub fn new<L>(limiter: L) -> Self
+    where
+        L: RateLimiterShared + Send + Sync + 'static,
+    {
+        Self::from_arc(Arc::new(limiter))
+    }
+
+    pub fn from_arc<L>(limiter: Arc<L>) -> Self
+    where
+        L: RateLimiterShared + Send + Sync + 'static,
+    {
+        Self {
+            check: Arc::new(move |_| limiter.try_acquire_decision(1)),
+        }
+    }
+
+    pub fn keyed<L, E>(limiter: L, extractor: E) -> Self
+    where
+        L: KeyedRateLimiterShared<E::Key> + Send + Sync + 'static,
+        E: KeyExtractor,
+    {
+        Self::keyed_from_arc(Arc::new(limiter), extractor)
+    }
+
+    pub fn keyed_from_arc<L, E>(limiter: Arc<L>, extractor: E) -> Self
+    where
+        L: KeyedRateLimiterShared<E::Key> + Send + Sync + 'static,
+        E: KeyExtractor,
+    {
+        Self {
+            check: Arc::new(move |req| limiter.try_acquire_decision(&extractor.extract(req), 1)),
+        }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            check: Arc::clone(&self.check),
+        }
+    }
+}
+
+// *** RATE LIMIT SERVICE ***
+#[derive(Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    check: Check,
+}
+
+impl<S> Service<Request> for RateLimitService<S>
+where
+    S: Service<Request, Error = Infallible>,
+    S::Response: IntoResponse,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, Infallible>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request) -> Self::Future {
+        let decision = (self.check)(&req);
+        let headers = RateLimitHeaders::from_decision(&decision);
+        if !decision.allowed {
+            let mut response = (StatusCode::TOO_MANY_REQUESTS, "Too Many Requests").into_response();
+            headers.insert_into(response.headers_mut());
+            return Box::pin(ready(Ok(response)));
+        }
+
+        req.extensions_mut().insert(RateLimitInfo(decision));
+        let future = self.inner.call(req);
+        Box::pin(async move {
+            let mut response = future.await?.into_response();
+            headers.insert_into(response.headers_mut());
+            Ok(response)
+        })
+    }
+}
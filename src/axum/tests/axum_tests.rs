@@ -0,0 +1,176 @@
+#[cfg(test)]
+mod sequential_tests {
+    use crate::axum::{HeaderKey, PeerIp, RateLimitInfo, RateLimitLayer};
+    use crate::clock::MockClock;
+    use crate::keyed::KeyedLimiter;
+    use crate::token_bucket::{TokenBucket, TokenBucketShared};
+    use ::axum::body::Body;
+    use ::axum::extract::{ConnectInfo, Request};
+    use ::axum::http::{HeaderName, StatusCode};
+    use ::axum::response::Response;
+    use ::axum::routing::get;
+    use ::axum::Router;
+    use ::tower::ServiceExt;
+    use std::net::SocketAddr;
+    use std::time::Duration;
+
+    async fn handler(RateLimitInfo(decision): RateLimitInfo) -> String {
+        format!("remaining {}", decision.remaining)
+    }
+
+    fn header<'a>(response: &'a Response, name: &str) -> Option<&'a str> {
+        response
+            .headers()
+            .get(name)
+            .map(|value| value.to_str().unwrap())
+    }
+
+    async fn body(response: Response) -> String {
+        let bytes = ::axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn global_test() {
+        let clock = MockClock::new();
+        let bucket = TokenBucketShared::with_clock(2, 1, clock.clone());
+        let app = Router::new()
+            .route("/", get(handler))
+            .layer(RateLimitLayer::new(bucket));
+
+        let response = app
+            .clone()
+            .oneshot(Request::new(Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(header(&response, "ratelimit-limit"), Some("2"));
+        assert_eq!(header(&response, "ratelimit-remaining"), Some("1"));
+        assert_eq!(header(&response, "x-ratelimit-remaining"), Some("1"));
+        assert_eq!(header(&response, "retry-after"), None);
+        assert_eq!(body(response).await, "remaining 1");
+
+        app.clone()
+            .oneshot(Request::new(Body::empty()))
+            .await
+            .unwrap();
+        let response = app
+            .clone()
+            .oneshot(Request::new(Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(header(&response, "ratelimit-remaining"), Some("0"));
+        let reset_after: u64 = header(&response, "ratelimit-reset")
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((1..=2).contains(&reset_after));
+        assert_eq!(header(&response, "retry-after"), Some("1"));
+        let reset: u64 = header(&response, "x-ratelimit-reset")
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(reset > 1_000_000_000);
+
+        clock.advance(Duration::from_secs(1));
+        let response = app.oneshot(Request::new(Body::empty())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn peer_ip_test() {
+        let limiter = KeyedLimiter::new(|_: &Option<std::net::IpAddr>| TokenBucket::new(1, 1));
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(RateLimitLayer::keyed(limiter, PeerIp));
+
+        let from = |addr: &str| {
+            let mut req = Request::new(Body::empty());
+            req.extensions_mut()
+                .insert(ConnectInfo(addr.parse::<SocketAddr>().unwrap()));
+            req
+        };
+
+        let response = app.clone().oneshot(from("10.0.0.1:5000")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        // Same IP from another port shares the limit
+        let response = app.clone().oneshot(from("10.0.0.1:5001")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let response = app.oneshot(from("10.0.0.2:5000")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn header_key_test() {
+        let limiter = KeyedLimiter::new(|_: &Option<String>| TokenBucket::new(1, 1));
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(RateLimitLayer::keyed(
+                limiter,
+                HeaderKey::new(HeaderName::from_static("x-api-key")),
+            ));
+
+        let with_key = |key: &str| {
+            Request::builder()
+                .header("x-api-key", key)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        assert_eq!(
+            app.clone().oneshot(with_key("a")).await.unwrap().status(),
+            StatusCode::OK
+        );
+        assert_eq!(
+            app.clone().oneshot(with_key("a")).await.unwrap().status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+        assert_eq!(
+            app.clone().oneshot(with_key("b")).await.unwrap().status(),
+            StatusCode::OK
+        );
+        // Requests without the header share one limiter
+        assert_eq!(
+            app.clone()
+                .oneshot(Request::new(Body::empty()))
+                .await
+                .unwrap()
+                .status(),
+            StatusCode::OK
+        );
+        assert_eq!(
+            app.oneshot(Request::new(Body::empty()))
+                .await
+                .unwrap()
+                .status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+
+    #[tokio::test]
+    async fn custom_key_test() {
+        let limiter = KeyedLimiter::new(|_: &String| TokenBucket::new(1, 1));
+        let by_path = |req: &Request| req.uri().path().to_string();
+        let app = Router::new()
+            .route("/a", get(|| async { "a" }))
+            .route("/b", get(|| async { "b" }))
+            .layer(RateLimitLayer::keyed(limiter, by_path));
+
+        let get_path = |path: &str| Request::builder().uri(path).body(Body::empty()).unwrap();
+        assert_eq!(
+            app.clone().oneshot(get_path("/a")).await.unwrap().status(),
+            StatusCode::OK
+        );
+        assert_eq!(
+            app.clone().oneshot(get_path("/b")).await.unwrap().status(),
+            StatusCode::OK
+        );
+        assert_eq!(
+            app.oneshot(get_path("/a")).await.unwrap().status(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+    }
+}
@@ -0,0 +1 @@
+mod axum_tests;
@@ -0,0 +1,6 @@
+pub mod r#impl;
+pub mod tests;
+
+pub use crate::core::{RateLimitError, RateLimiterShared};
+pub use crate::tower::BoxError;
+pub use r#impl::{ResponseFuture, Throttle, ThrottleLayer};
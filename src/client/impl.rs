@@ -0,0 +1,304 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{ready, Context, Poll};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use http::{HeaderMap, Request, Response, StatusCode};
+use pin_project_lite::pin_project;
+use tokio::time::{Instant, Sleep};
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::core::{RateLimitError, RateLimiterShared, MIN_WAIT};
+use crate::headers::{RATELIMIT_REMAINING, RATELIMIT_RESET, RETRY_AFTER};
+use crate::headers::{X_RATELIMIT_REMAINING, X_RATELIMIT_RESET};
+use crate::tower::BoxError;
+
+// *** SHARED ***
+/// Local limiter and the server imposed pause, shared by every clone of the client.
+struct Shared<L> {
+    limiter: Arc<L>,
+    paused_until: Mutex<Option<Instant>>,
+}
+
+impl<L: RateLimiterShared> Shared<L> {
+    fn paused_until(&self, now: Instant) -> Option<Instant> {
+        let paused_until = *self
+            .paused_until
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        paused_until.filter(|until| *until > now)
+    }
+
+    fn pause(&self, until: Instant) {
+        let mut paused_until = self
+            .paused_until
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if paused_until.is_none_or(|current| current < until) {
+            *paused_until = Some(until);
+        }
+    }
+
+    /// Tokens the local limiter would grant right now. `get_remaining` may be rounded, e.g. for a
+    /// leaky bucket, so the largest amount that needs no wait is searched for instead.
+    fn grantable(&self) -> u32 {
+        let (mut low, mut high) = (0, self.limiter.get_limit());
+        while low < high {
+            let mid = high - (high - low) / 2;
+            if self.limiter.get_retry_after(mid).is_zero() {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        low
+    }
+
+    /// Takes tokens from the local limiter so it never has more left than the server.
+    fn shrink_to(&self, remaining: u32) {
+        self.limiter.refresh();
+        let mut local = self.grantable();
+        while local > remaining && !self.limiter.try_acquire(local - remaining) {
+            // Another caller took tokens in between, shrink by what is left instead
+            let left = self.grantable();
+            if left >= local {
+                break;
+            }
+            local = left;
+        }
+    }
+
+    fn apply_hints(&self, status: StatusCode, headers: &HeaderMap) {
+        let throttled = matches!(
+            status,
+            StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+        );
+        let remaining = header_u64(headers, RATELIMIT_REMAINING)
+            .or_else(|| header_u64(headers, X_RATELIMIT_REMAINING))
+            .map(|remaining| u32::try_from(remaining).unwrap_or(u32::MAX));
+
+        let remaining = if throttled { Some(0) } else { remaining };
+        let Some(remaining) = remaining else {
+            return;
+        };
+        self.shrink_to(remaining);
+
+        if remaining == 0 {
+            let wait = throttled
+                .then(|| header_u64(headers, RETRY_AFTER))
+                .flatten()
+                .or_else(|| reset_after(headers));
+            if let Some(wait) = wait {
+                self.pause(Instant::now() + Duration::from_secs(wait));
+            }
+        }
+    }
+}
+
+fn header_u64(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+/// Seconds until the server quota resets, `X-RateLimit-Reset` is a UNIX timestamp.
+fn reset_after(headers: &HeaderMap) -> Option<u64> {
+    header_u64(headers, RATELIMIT_RESET).or_else(|| {
+        let reset = header_u64(headers, X_RATELIMIT_RESET)?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        Some(reset.saturating_sub(now))
+    })
+}
+
+// *** THROTTLE LAYER ***
+/// Client side throttling for outbound HTTP requests. Each request waits for a token of the
+/// local limiter before it is sent. With `server_hints` the `RateLimit-*`, `X-RateLimit-*` and
+/// `Retry-After` (in seconds) response headers shrink the local limiter and pause the client
+/// until the server quota resets.
+pub struct ThrottleLayer<L> {
+    shared: Arc<Shared<L>>,
+    server_hints: bool,
+}
+
+impl<L: RateLimiterShared> ThrottleLayer<L> {
+    pub fn new(limiter: L) -> Self {
+        Self::from_arc(Arc::new(limiter))
+    }
+
+    /// Shares `limiter` with other layers or code outside the service stack.
+    pub fn from_arc(limiter: Arc<L>) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                limiter,
+                paused_until: Mutex::new(None),
+            }),
+            server_hints: false,
+        }
+    }
+
+    pub fn with_server_hints(mut self, server_hints: bool) -> Self {
+        self.server_hints = server_hints;
+        self
+    }
+}
+
+impl<L> Clone for ThrottleLayer<L> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: Arc::clone(&self.shared),
+            server_hints: self.server_hints,
+        }
+    }
+}
+
+impl<S, L> Layer<S> for ThrottleLayer<L> {
+    type Service = Throttle<S, L>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Throttle {
+            inner,
+            shared: Arc::clone(&self.shared),
+            server_hints: self.server_hints,
+            state: State::Idle,
+        }
+    }
+}
+
+// *** THROTTLE ***
+enum State {
+    Idle,
+    Waiting(Pin<Box<Sleep>>),
+    /// A token was taken in `poll_ready` for the next `call`.
+    Reserved,
+}
+
+pub struct Throttle<S, L> {
+    inner: S,
+    shared: Arc<Shared<L>>,
+    server_hints: bool,
+    state: State,
+}
+
+impl<S: Clone, L> Clone for Throttle<S, L> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            shared: Arc::clone(&self.shared),
+            server_hints: self.server_hints,
+            state: State::Idle,
+        }
+    }
+}
+
+impl<S, L, ReqBody, ResBody> Service<Request<ReqBody>> for Throttle<S, L>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Error: Into<BoxError>,
+    L: RateLimiterShared,
+{
+    type Response = Response<ResBody>;
+    type Error = BoxError;
+    type Future = ResponseFuture<S::Future, L>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        loop {
+            match &mut self.state {
+                State::Reserved => break,
+                State::Waiting(sleep) => {
+                    ready!(sleep.as_mut().poll(cx));
+                    self.state = State::Idle;
+                }
+                State::Idle => {
+                    if let Some(until) = self.shared.paused_until(Instant::now()) {
+                        self.state = State::Waiting(Box::pin(tokio::time::sleep_until(until)));
+                        continue;
+                    }
+                    match self.shared.limiter.try_acquire_checked(1) {
+                        Ok(_) => self.state = State::Reserved,
+                        Err(RateLimitError::Denied(decision)) => {
+                            let wait = decision.retry_after.max(MIN_WAIT);
+                            self.state = State::Waiting(Box::pin(tokio::time::sleep(wait)));
+                        }
+                        Err(err) => return Poll::Ready(Err(err.into())),
+                    }
+                }
+            }
+        }
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        // The token reserved by `poll_ready` pays for this request, without one it is taken
+        // here so skipping `poll_ready` can't bypass the limit
+        let state = std::mem::replace(&mut self.state, State::Idle);
+        if !matches!(state, State::Reserved) {
+            if let Err(err) = self.shared.limiter.try_acquire_checked(1) {
+                return ResponseFuture::rejected(err);
+            }
+        }
+        ResponseFuture::inner(
+            self.inner.call(req),
+            self.server_hints.then(|| Arc::clone(&self.shared)),
+        )
+    }
+}
+
+// *** RESPONSE FUTURE ***
+pin_project! {
+    pub struct ResponseFuture<F, L> {
+        #[pin]
+        kind: Kind<F>,
+        shared: Option<Arc<Shared<L>>>,
+    }
+}
+
+pin_project! {
+    #[project = KindProj]
+    enum Kind<F> {
+        Inner { #[pin] future: F },
+        Rejected { error: Option<RateLimitError> },
+    }
+}
+
+impl<F, L> ResponseFuture<F, L> {
+    fn inner(future: F, shared: Option<Arc<Shared<L>>>) -> Self {
+        Self {
+            kind: Kind::Inner { future },
+            shared,
+        }
+    }
+
+    fn rejected(error: RateLimitError) -> Self {
+        Self {
+            kind: Kind::Rejected { error: Some(error) },
+            shared: None,
+        }
+    }
+}
+
+impl<F, L, B, E> Future for ResponseFuture<F, L>
+where
+    F: Future<Output = Result<Response<B>, E>>,
+    E: Into<BoxError>,
+    L: RateLimiterShared,
+{
+    type Output = Result<Response<B>, BoxError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let future = match this.kind.project() {
+            KindProj::Inner { future } => future,
+            KindProj::Rejected { error } => {
+                let error = error.take().expect("polled after completion");
+                return Poll::Ready(Err(error.into()));
+            }
+        };
+        let response = ready!(future.poll(cx)).map_err(Into::into)?;
+        if let Some(shared) = this.shared.take() {
+            shared.apply_hints(response.status(), response.headers());
+        }
+        Poll::Ready(Ok(response))
+    }
+}
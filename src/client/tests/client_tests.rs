@@ -0,0 +1,255 @@
+#[cfg(test)]
+mod sequential_tests {
+    use crate::client::ThrottleLayer;
+    use crate::clock::MockClock;
+    use crate::core::{Decision, RateLimitError, RateLimiterShared};
+    use crate::gcra::GcraShared;
+    use crate::leaky_bucket::LeakyBucketShared;
+    use crate::token_bucket::TokenBucketShared;
+    use ::tower::{service_fn, Layer, Service, ServiceExt};
+    use http::{Request, Response, StatusCode};
+    use std::convert::Infallible;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    fn request() -> Request<()> {
+        Request::new(())
+    }
+
+    fn response(status: StatusCode, headers: &[(&'static str, &str)]) -> Response<()> {
+        let mut builder = Response::builder().status(status);
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(()).unwrap()
+    }
+
+    /// Lets another caller take `steal` tokens right before the next acquire of more than one.
+    struct Racing {
+        inner: TokenBucketShared<MockClock>,
+        steal: AtomicU32,
+    }
+
+    impl Racing {
+        fn before_acquire(&self, tokens: u32) {
+            if tokens > 1 {
+                let steal = self.steal.swap(0, Ordering::SeqCst);
+                assert!(self.inner.try_acquire(steal));
+            }
+        }
+    }
+
+    impl RateLimiterShared for Racing {
+        fn refresh(&self) {
+            self.inner.refresh()
+        }
+
+        fn try_acquire(&self, tokens: u32) -> bool {
+            self.before_acquire(tokens);
+            self.inner.try_acquire(tokens)
+        }
+
+        fn try_acquire_decision(&self, tokens: u32) -> Decision {
+            self.before_acquire(tokens);
+            self.inner.try_acquire_decision(tokens)
+        }
+
+        fn try_acquire_checked(&self, tokens: u32) -> Result<Decision, RateLimitError> {
+            self.before_acquire(tokens);
+            self.inner.try_acquire_checked(tokens)
+        }
+
+        fn get_limit(&self) -> u32 {
+            self.inner.get_limit()
+        }
+
+        fn get_remaining(&self) -> u32 {
+            self.inner.get_remaining()
+        }
+
+        fn get_used(&self) -> u32 {
+            self.inner.get_used()
+        }
+
+        fn get_reset(&self) -> u64 {
+            self.inner.get_reset()
+        }
+
+        fn get_reset_ms(&self) -> u64 {
+            self.inner.get_reset_ms()
+        }
+
+        fn get_retry_after(&self, tokens: u32) -> Duration {
+            self.inner.get_retry_after(tokens)
+        }
+    }
+
+    #[tokio::test]
+    async fn throttle_test() {
+        let limiter = GcraShared::new(Duration::from_millis(50), Duration::ZERO);
+        let mut client = ThrottleLayer::new(limiter).layer(service_fn(|_| async {
+            Ok::<_, Infallible>(Response::new(()))
+        }));
+        let start = std::time::Instant::now();
+
+        for _ in 0..4 {
+            client.ready().await.unwrap().call(request()).await.unwrap();
+        }
+
+        // Waited for a token before each request
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(150));
+        assert!(elapsed < Duration::from_millis(400));
+    }
+
+    #[tokio::test]
+    async fn call_without_poll_ready_test() {
+        let clock = MockClock::new();
+        let calls = Arc::new(AtomicU32::new(0));
+        let counted = Arc::clone(&calls);
+        let mut client = ThrottleLayer::new(TokenBucketShared::with_clock(1, 1, clock)).layer(
+            service_fn(move |_| {
+                counted.fetch_add(1, Ordering::SeqCst);
+                async { Ok::<_, Infallible>(Response::new(())) }
+            }),
+        );
+
+        // Each call pays for itself when `poll_ready` reserved nothing
+        client.call(request()).await.unwrap();
+        let err = client.call(request()).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<RateLimitError>(),
+            Some(RateLimitError::Denied(_))
+        ));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn ignores_hints_by_default_test() {
+        let clock = MockClock::new();
+        let bucket = Arc::new(TokenBucketShared::with_clock(10, 1, clock));
+        let client = ThrottleLayer::from_arc(Arc::clone(&bucket)).layer(service_fn(|_| async {
+            let headers = [("ratelimit-remaining", "0"), ("retry-after", "30")];
+            Ok::<_, Infallible>(response(StatusCode::TOO_MANY_REQUESTS, &headers))
+        }));
+
+        let response = client.oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(bucket.get_remaining(), 9);
+    }
+
+    #[tokio::test]
+    async fn shrinks_to_server_remaining_test() {
+        let clock = MockClock::new();
+        let bucket = Arc::new(TokenBucketShared::with_clock(10, 1, clock));
+        let layer = ThrottleLayer::from_arc(Arc::clone(&bucket)).with_server_hints(true);
+
+        let client = layer.layer(service_fn(|_| async {
+            Ok::<_, Infallible>(response(StatusCode::OK, &[("ratelimit-remaining", "3")]))
+        }));
+        client.oneshot(request()).await.unwrap();
+        assert_eq!(bucket.get_remaining(), 3);
+
+        // Legacy headers are read too, a server with more left never grows the limiter
+        let client = layer.layer(service_fn(|_| async {
+            Ok::<_, Infallible>(response(StatusCode::OK, &[("x-ratelimit-remaining", "8")]))
+        }));
+        client.oneshot(request()).await.unwrap();
+        assert_eq!(bucket.get_remaining(), 2);
+
+        // Unparsable values are ignored
+        let client = layer.layer(service_fn(|_| async {
+            Ok::<_, Infallible>(response(StatusCode::OK, &[("ratelimit-remaining", "many")]))
+        }));
+        client.oneshot(request()).await.unwrap();
+        assert_eq!(bucket.get_remaining(), 1);
+    }
+
+    #[tokio::test]
+    async fn shrinks_despite_concurrent_acquire_test() {
+        let racing = Arc::new(Racing {
+            inner: TokenBucketShared::with_clock(10, 1, MockClock::new()),
+            steal: AtomicU32::new(0),
+        });
+        let layer = ThrottleLayer::from_arc(Arc::clone(&racing)).with_server_hints(true);
+        let client = layer.layer(service_fn(|_| async {
+            Ok::<_, Infallible>(response(StatusCode::OK, &[("ratelimit-remaining", "3")]))
+        }));
+
+        // 9 left after the request, 5 of them go elsewhere before the 6 excess are taken
+        racing.steal.store(5, Ordering::SeqCst);
+        client.oneshot(request()).await.unwrap();
+        assert_eq!(racing.get_remaining(), 3);
+    }
+
+    #[tokio::test]
+    async fn shrinks_leaky_bucket_test() {
+        let clock = MockClock::new();
+        let bucket = Arc::new(LeakyBucketShared::with_clock(10, 1.0, clock.clone()));
+        let layer = ThrottleLayer::from_arc(Arc::clone(&bucket)).with_server_hints(true);
+
+        // 3.4 water after the request, `get_remaining` rounds it to 7 left but only 6 fit
+        assert!(bucket.try_acquire(4));
+        clock.advance(Duration::from_millis(1600));
+        let client = layer.layer(service_fn(|_| async {
+            Ok::<_, Infallible>(response(StatusCode::OK, &[("ratelimit-remaining", "0")]))
+        }));
+        client.oneshot(request()).await.unwrap();
+        assert!(!bucket.try_acquire(1));
+
+        clock.advance(Duration::from_millis(6600));
+        let client = layer.layer(service_fn(|_| async {
+            Ok::<_, Infallible>(response(StatusCode::OK, &[("ratelimit-remaining", "3")]))
+        }));
+        client.oneshot(request()).await.unwrap();
+        assert!(bucket.try_acquire(3));
+        assert!(!bucket.try_acquire(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retry_after_pauses_client_test() {
+        let clock = MockClock::new();
+        let bucket = Arc::new(TokenBucketShared::with_clock(10, 1, clock.clone()));
+        let layer = ThrottleLayer::from_arc(Arc::clone(&bucket)).with_server_hints(true);
+        let client = layer.layer(service_fn(|_| async {
+            Ok::<_, Infallible>(response(
+                StatusCode::TOO_MANY_REQUESTS,
+                &[("retry-after", "30")],
+            ))
+        }));
+
+        let start = Instant::now();
+        client.clone().oneshot(request()).await.unwrap();
+        assert_eq!(bucket.get_remaining(), 0);
+
+        // The limiter refills locally, but the server asked to wait 30 seconds
+        clock.advance(Duration::from_secs(5));
+        client.oneshot(request()).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_secs(30));
+        assert!(start.elapsed() < Duration::from_secs(31));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reset_pauses_exhausted_client_test() {
+        let clock = MockClock::new();
+        let bucket = Arc::new(TokenBucketShared::with_clock(10, 1, clock.clone()));
+        let layer = ThrottleLayer::from_arc(Arc::clone(&bucket)).with_server_hints(true);
+        let exhausted = layer.layer(service_fn(|_| async {
+            let headers = [("ratelimit-remaining", "0"), ("ratelimit-reset", "12")];
+            Ok::<_, Infallible>(response(StatusCode::OK, &headers))
+        }));
+
+        let start = Instant::now();
+        exhausted.oneshot(request()).await.unwrap();
+
+        // Every service of the layer waits for the reset
+        let other = layer.layer(service_fn(|_| async {
+            Ok::<_, Infallible>(Response::new(()))
+        }));
+        clock.advance(Duration::from_secs(5));
+        other.oneshot(request()).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_secs(12));
+    }
+}
@@ -0,0 +1,5 @@
+pub mod r#impl;
+pub mod tests;
+
+pub(crate) use r#impl::{since_unix, Epoch};
+pub use r#impl::{Clock, MockClock, SystemClock};
@@ -0,0 +1,102 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// *** CLOCK ***
+/// Source of time for the limiters.
+///
+/// `now` drives the limiter state, `now_system` is used to express reset times as UNIX timestamps.
+pub trait Clock {
+    fn now(&self) -> Instant;
+    fn now_system(&self) -> SystemTime;
+}
+
+// *** SYSTEM CLOCK ***
+/// Real time, backed by `Instant::now` and `SystemTime::now`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn now_system(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+// *** MOCK CLOCK ***
+/// Clock that only moves when `advance` is called.
+///
+/// Clones share the same time, so a test can keep one copy and hand another to a limiter.
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    start: Instant,
+    start_system: SystemTime,
+    offset_nanos: Arc<AtomicU64>,
+}
+
+impl MockClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            start_system: SystemTime::now(),
+            offset_nanos: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.offset_nanos
+            .fetch_add(duration.as_nanos() as u64, Ordering::SeqCst);
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_nanos(self.offset_nanos.load(Ordering::SeqCst))
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn now_system(&self) -> SystemTime {
+        self.start_system + self.elapsed()
+    }
+}
+
+// *** EPOCH ***
+/// Origin of the time passed to an `Algorithm`: the UNIX time when it was created, moved
+/// forward by the monotonic `Clock::now`. States hold wall-clock like timestamps that don't jump
+/// with the system clock.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Epoch {
+    instant: Instant,
+    since_unix: Duration,
+}
+
+impl Epoch {
+    pub(crate) fn new(clock: &impl Clock) -> Self {
+        Self {
+            instant: clock.now(),
+            since_unix: since_unix(clock.now_system()),
+        }
+    }
+
+    pub(crate) fn now(&self, clock: &impl Clock) -> Duration {
+        let elapsed = clock.now().saturating_duration_since(self.instant);
+        self.since_unix.saturating_add(elapsed)
+    }
+}
+
+/// Time since the UNIX epoch, zero for earlier times.
+pub(crate) fn since_unix(time: SystemTime) -> Duration {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default()
+}
@@ -0,0 +1,23 @@
+#[cfg(test)]
+mod sequential_tests {
+    use crate::clock::{Clock, MockClock};
+    use std::time::Duration;
+
+    #[test]
+    fn mock_clock_test() {
+        let clock = MockClock::new();
+        let start = clock.now();
+        let start_system = clock.now_system();
+        assert_eq!(clock.now(), start);
+        assert_eq!(clock.elapsed(), Duration::ZERO);
+
+        let clock_clone = clock.clone();
+        clock_clone.advance(Duration::from_millis(1500));
+        assert_eq!(clock.elapsed(), Duration::from_millis(1500));
+        assert_eq!(clock.now() - start, Duration::from_millis(1500));
+        assert_eq!(
+            clock.now_system().duration_since(start_system).unwrap(),
+            Duration::from_millis(1500)
+        );
+    }
+}
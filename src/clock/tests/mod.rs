@@ -0,0 +1 @@
+mod clock_tests;
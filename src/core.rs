@@ -0,0 +1,7 @@
+pub mod r#impl;
+pub mod tests;
+
+pub(crate) use r#impl::{as_millis, unix_time_after, MIN_WAIT};
+pub use r#impl::{
+    Decision, KeyedRateLimiterShared, Rate, RateLimitError, RateLimiter, RateLimiterShared,
+};
@@ -0,0 +1,269 @@
+use std::fmt;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Shortest wait between acquire attempts, guards against spinning on a zero `retry_after`.
+pub(crate) const MIN_WAIT: Duration = Duration::from_millis(1);
+
+// *** DECISION ***
+/// Outcome of an acquire attempt, computed together with the attempt itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decision {
+    /// `true` if the requested tokens were acquired.
+    pub allowed: bool,
+    /// Same as `get_limit`.
+    pub limit: u32,
+    /// Same as `get_remaining`, observed right after the attempt.
+    pub remaining: u32,
+    /// How long to wait before the same request can succeed.
+    /// Zero when allowed, `Duration::MAX` when the request can never succeed.
+    pub retry_after: Duration,
+    /// Same as `get_reset`, observed right after the attempt.
+    pub reset: u64,
+}
+
+impl Decision {
+    /// `true` if the request was denied but may pass later, `false` if it was allowed or
+    /// can never pass, e.g. because it asks for more tokens than the limiter holds.
+    pub fn can_retry(&self) -> bool {
+        !self.allowed && self.retry_after != Duration::MAX
+    }
+}
+
+// *** RATE ***
+/// `tokens` per `period`, e.g. `Rate::per(1, Duration::from_secs(5))` for one token every
+/// five seconds or `Rate::per(5, Duration::from_secs(2))` for 2.5 tokens per second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rate {
+    tokens: u32,
+    period: Duration,
+}
+
+impl Rate {
+    pub const fn per(tokens: u32, period: Duration) -> Self {
+        Self { tokens, period }
+    }
+
+    pub const fn per_second(tokens: u32) -> Self {
+        Self::per(tokens, Duration::from_secs(1))
+    }
+
+    pub const fn per_minute(tokens: u32) -> Self {
+        Self::per(tokens, Duration::from_secs(60))
+    }
+
+    pub const fn per_hour(tokens: u32) -> Self {
+        Self::per(tokens, Duration::from_secs(3600))
+    }
+
+    pub fn tokens(&self) -> u32 {
+        self.tokens
+    }
+
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Time it takes to produce a single token.
+    pub fn interval(&self) -> Duration {
+        self.time_for(1)
+    }
+
+    pub(crate) fn is_valid(&self) -> bool {
+        self.tokens > 0 && !self.period.is_zero()
+    }
+
+    /// Time it takes to produce `tokens`, rounded up to the next nanosecond.
+    pub(crate) fn time_for(&self, tokens: u32) -> Duration {
+        if self.tokens == 0 {
+            return Duration::MAX;
+        }
+        let nanos = (tokens as u128 * self.period.as_nanos()).div_ceil(self.tokens as u128);
+        Duration::from_nanos(nanos.min(u64::MAX as u128) as u64)
+    }
+}
+
+// *** RATE LIMIT ERROR ***
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RateLimitError {
+    /// The request was throttled, it may succeed after `retry_after`.
+    Denied(Decision),
+    /// The request asks for more tokens than the limiter can ever hold.
+    InsufficientCapacity { requested: u32, capacity: u32 },
+    /// The permits would not become available before the deadline.
+    Timeout(Decision),
+    /// The limiter parameters are invalid.
+    InvalidConfig(String),
+    /// A thread panicked while holding the limiter lock.
+    PoisonedLock,
+    /// The store holding the limiter state failed, e.g. a Redis server is unreachable.
+    Backend(String),
+}
+
+impl RateLimitError {
+    /// How long to wait before trying again, `None` if retrying the same request is pointless.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::Denied(decision) | Self::Timeout(decision) if decision.can_retry() => {
+                Some(decision.retry_after)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        self.retry_after().is_some()
+    }
+}
+
+impl fmt::Display for RateLimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Denied(decision) => write!(
+                f,
+                "rate limited, retry after {:.3}s",
+                decision.retry_after.as_secs_f64()
+            ),
+            Self::InsufficientCapacity {
+                requested,
+                capacity,
+            } => write!(
+                f,
+                "request of {requested} tokens exceeds limiter capacity of {capacity}"
+            ),
+            Self::Timeout(decision) => write!(
+                f,
+                "timed out waiting for permits, retry after {:.3}s",
+                decision.retry_after.as_secs_f64()
+            ),
+            Self::InvalidConfig(reason) => write!(f, "invalid limiter configuration: {reason}"),
+            Self::PoisonedLock => write!(f, "limiter lock is poisoned"),
+            Self::Backend(reason) => write!(f, "limiter backend error: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for RateLimitError {}
+
+// *** RATE LIMITER ***
+pub trait RateLimiter {
+    fn refresh(&mut self);
+    fn try_acquire(&mut self, tokens: u32) -> bool;
+
+    fn try_acquire_decision(&mut self, tokens: u32) -> Decision {
+        let allowed = self.try_acquire(tokens);
+        Decision {
+            allowed,
+            limit: self.get_limit(),
+            remaining: self.get_remaining(),
+            retry_after: if allowed {
+                Duration::ZERO
+            } else {
+                self.get_retry_after(tokens)
+            },
+            reset: self.get_reset(),
+        }
+    }
+
+    fn try_acquire_checked(&mut self, tokens: u32) -> Result<Decision, RateLimitError> {
+        let capacity = self.get_limit();
+        if tokens > capacity {
+            return Err(RateLimitError::InsufficientCapacity {
+                requested: tokens,
+                capacity,
+            });
+        }
+
+        let decision = self.try_acquire_decision(tokens);
+        if decision.allowed {
+            Ok(decision)
+        } else {
+            Err(RateLimitError::Denied(decision))
+        }
+    }
+
+    fn get_limit(&self) -> u32;
+    fn get_remaining(&self) -> u32;
+    fn get_used(&self) -> u32;
+    fn get_reset(&self) -> u64;
+    fn get_reset_ms(&self) -> u64;
+    fn get_retry_after(&self, tokens: u32) -> Duration;
+
+    /// Whether the limiter is back at the state a freshly created one starts in, so it can be
+    /// dropped and recreated later without changing any decision. Call `refresh` first.
+    fn is_idle(&self) -> bool {
+        self.get_used() == 0
+    }
+}
+
+// *** RATE LIMITER SHARED ***
+/// A limiter shared between threads. When a thread panics while holding the lock of a
+/// `*Shared` limiter, `try_acquire_checked` fails with `PoisonedLock` and every other method
+/// carries on with the state the thread left behind.
+pub trait RateLimiterShared {
+    fn refresh(&self);
+    fn try_acquire(&self, tokens: u32) -> bool;
+    fn try_acquire_decision(&self, tokens: u32) -> Decision;
+    fn try_acquire_checked(&self, tokens: u32) -> Result<Decision, RateLimitError>;
+
+    /// Parks the thread until `tokens` are acquired, see `acquire_timeout`.
+    fn acquire_blocking(&self, tokens: u32) -> Result<Decision, RateLimitError> {
+        self.acquire_timeout(tokens, Duration::MAX)
+    }
+
+    /// Parks the thread until `tokens` are acquired, sleeping exactly until the limiter
+    /// expects them to be available. Fails with `InsufficientCapacity` if the request can
+    /// never pass and with `Timeout` as soon as it is clear the permits won't be available
+    /// within `timeout`.
+    fn acquire_timeout(&self, tokens: u32, timeout: Duration) -> Result<Decision, RateLimitError> {
+        let deadline = Instant::now().checked_add(timeout);
+        loop {
+            let decision = match self.try_acquire_checked(tokens) {
+                Err(RateLimitError::Denied(decision)) => decision,
+                result => return result,
+            };
+
+            let wait = decision.retry_after.max(MIN_WAIT);
+            if let Some(deadline) = deadline {
+                if wait > deadline.saturating_duration_since(Instant::now()) {
+                    return Err(RateLimitError::Timeout(decision));
+                }
+            }
+            thread::sleep(wait);
+        }
+    }
+
+    fn get_limit(&self) -> u32;
+    fn get_remaining(&self) -> u32;
+    fn get_used(&self) -> u32;
+    fn get_reset(&self) -> u64;
+    fn get_reset_ms(&self) -> u64;
+    fn get_retry_after(&self, tokens: u32) -> Duration;
+}
+
+// *** KEYED RATE LIMITER SHARED ***
+/// A thread-safe set of limiters, one per key. A poisoned lock is handled like in
+/// `RateLimiterShared`.
+pub trait KeyedRateLimiterShared<K: ?Sized> {
+    fn try_acquire(&self, key: &K, tokens: u32) -> bool;
+    fn try_acquire_decision(&self, key: &K, tokens: u32) -> Decision;
+    fn try_acquire_checked(&self, key: &K, tokens: u32) -> Result<Decision, RateLimitError>;
+
+    fn get_limit(&self, key: &K) -> u32;
+    fn get_remaining(&self, key: &K) -> u32;
+    fn get_used(&self, key: &K) -> u32;
+    fn get_reset(&self, key: &K) -> u64;
+    fn get_reset_ms(&self, key: &K) -> u64;
+    fn get_retry_after(&self, key: &K, tokens: u32) -> Duration;
+}
+
+/// Time since UNIX epoch at `now + after`, saturating instead of overflowing.
+pub(crate) fn unix_time_after(now: SystemTime, after: Duration) -> Duration {
+    now.checked_add(after)
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .unwrap_or(Duration::MAX)
+}
+
+pub(crate) fn as_millis(time: Duration) -> u64 {
+    time.as_millis().min(u64::MAX as u128) as u64
+}
@@ -0,0 +1,226 @@
+#[cfg(test)]
+mod sequential_tests {
+    use crate::core::{Rate, RateLimitError, RateLimiter, RateLimiterShared};
+    use crate::leaky_bucket::LeakyBucketShared;
+    use crate::token_bucket::TokenBucket;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn try_acquire_checked_test() {
+        let mut bucket = TokenBucket::new(10, 1);
+
+        let decision = bucket.try_acquire_checked(10).unwrap();
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 0);
+
+        match bucket.try_acquire_checked(1) {
+            Err(RateLimitError::Denied(decision)) => {
+                assert!(!decision.allowed);
+                assert_eq!(decision.remaining, 0);
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+
+        assert_eq!(
+            bucket.try_acquire_checked(11),
+            Err(RateLimitError::InsufficientCapacity {
+                requested: 11,
+                capacity: 10
+            })
+        );
+    }
+
+    #[test]
+    fn shared_try_acquire_checked_test() {
+        let bucket = LeakyBucketShared::new(3, 1.0);
+
+        assert!(bucket.try_acquire_checked(3).is_ok());
+        assert!(matches!(
+            bucket.try_acquire_checked(1),
+            Err(RateLimitError::Denied(_))
+        ));
+
+        let err = bucket.try_acquire_checked(4).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "request of 4 tokens exceeds limiter capacity of 3"
+        );
+    }
+
+    #[test]
+    fn rate_test() {
+        let rate = Rate::per(5, Duration::from_secs(2));
+        assert_eq!(rate.interval(), Duration::from_millis(400));
+        assert_eq!(rate.time_for(3), Duration::from_millis(1200));
+
+        assert_eq!(Rate::per_minute(60), Rate::per(60, Duration::from_secs(60)));
+        assert_eq!(
+            Rate::per(1, Duration::from_secs(3)).time_for(1),
+            Duration::from_secs(3)
+        );
+        assert_eq!(
+            Rate::per(3, Duration::from_secs(1)).interval(),
+            Duration::from_nanos(333_333_334)
+        );
+    }
+
+    #[test]
+    fn acquire_timeout_test() {
+        let bucket = TokenBucket::builder()
+            .capacity(1)
+            .rate(Rate::per(1, Duration::from_millis(50)))
+            .build_shared()
+            .unwrap();
+        assert!(bucket.acquire_blocking(1).is_ok());
+
+        let start = Instant::now();
+        let decision = bucket.acquire_blocking(1).unwrap();
+        assert!(decision.allowed);
+        assert!((40..150).contains(&start.elapsed().as_millis()));
+
+        // Gives up right away instead of sleeping past the deadline
+        let start = Instant::now();
+        match bucket.acquire_timeout(1, Duration::from_millis(10)) {
+            Err(RateLimitError::Timeout(decision)) => {
+                assert!(!decision.allowed);
+                assert!(decision.retry_after > Duration::from_millis(10));
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+        assert!(start.elapsed() < Duration::from_millis(10));
+
+        assert!(bucket
+            .acquire_timeout(1, Duration::from_millis(200))
+            .is_ok());
+        assert_eq!(
+            bucket.acquire_blocking(2),
+            Err(RateLimitError::InsufficientCapacity {
+                requested: 2,
+                capacity: 1
+            })
+        );
+    }
+}
+
+#[cfg(test)]
+mod capacity_tests {
+    use crate::core::{Rate, RateLimitError, RateLimiterShared};
+    use crate::fixed_window_counter::FixedWindowCounterShared;
+    use crate::gcra::GcraShared;
+    use crate::leaky_bucket::LeakyBucketShared;
+    use crate::sliding_window_counter::{
+        SlidingWindowCounterExactShared, SlidingWindowCounterShared,
+    };
+    use crate::sliding_window_log::SlidingWindowLogShared;
+    use crate::token_bucket::{AtomicTokenBucket, TokenBucketShared};
+    use std::time::Duration;
+
+    fn limiters() -> Vec<(&'static str, Box<dyn RateLimiterShared>)> {
+        vec![
+            ("token_bucket", Box::new(TokenBucketShared::new(4, 1))),
+            (
+                "atomic_token_bucket",
+                Box::new(AtomicTokenBucket::new(4, 1)),
+            ),
+            ("leaky_bucket", Box::new(LeakyBucketShared::new(4, 1.0))),
+            (
+                "fixed_window_counter",
+                Box::new(FixedWindowCounterShared::new(4, 1)),
+            ),
+            (
+                "sliding_window_log",
+                Box::new(SlidingWindowLogShared::new(4, 1)),
+            ),
+            (
+                "sliding_window_counter",
+                Box::new(SlidingWindowCounterShared::new(4, 1)),
+            ),
+            (
+                "sliding_window_counter_exact",
+                Box::new(SlidingWindowCounterExactShared::new(4, 1)),
+            ),
+            (
+                "gcra",
+                Box::new(GcraShared::with_rate(Rate::per_second(4), 4)),
+            ),
+        ]
+    }
+
+    #[test]
+    fn insufficient_capacity_test() {
+        for (name, limiter) in limiters() {
+            assert!(limiter.try_acquire(1), "{name}");
+
+            // Too large: reported as never passing and nothing is taken
+            assert!(!limiter.try_acquire(5), "{name}");
+            let decision = limiter.try_acquire_decision(5);
+            assert!(!decision.allowed, "{name}");
+            assert!(!decision.can_retry(), "{name}");
+            assert_eq!(decision.retry_after, Duration::MAX, "{name}");
+            assert_eq!(limiter.get_retry_after(5), Duration::MAX, "{name}");
+            let err = limiter.try_acquire_checked(5).unwrap_err();
+            assert_eq!(
+                err,
+                RateLimitError::InsufficientCapacity {
+                    requested: 5,
+                    capacity: 4
+                },
+                "{name}"
+            );
+            assert!(!err.is_retryable(), "{name}");
+            assert_eq!(
+                limiter.acquire_timeout(5, Duration::from_secs(10)),
+                Err(err),
+                "{name}"
+            );
+            assert_eq!(limiter.get_used(), 1, "{name}");
+
+            // Throttled: reported as worth retrying
+            assert!(limiter.try_acquire(3), "{name}");
+            let decision = limiter.try_acquire_decision(4);
+            assert!(decision.can_retry(), "{name}");
+            let err = limiter.try_acquire_checked(4).unwrap_err();
+            assert!(matches!(err, RateLimitError::Denied(_)), "{name}");
+            assert!(err.is_retryable(), "{name}");
+            assert!(
+                err.retry_after().unwrap() <= Duration::from_secs(4),
+                "{name}"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod parallel_tests {
+    use crate::core::RateLimiterShared;
+    use crate::leaky_bucket::LeakyBucketShared;
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn acquire_blocking_test() {
+        let bucket = Arc::new(LeakyBucketShared::new(1, 20.0));
+        let barrier = Arc::new(Barrier::new(4));
+        let start = Instant::now();
+
+        let mut handles = vec![];
+        for _ in 0..4 {
+            let bucket_clone = Arc::clone(&bucket);
+            let barrier_clone = Arc::clone(&barrier);
+            handles.push(thread::spawn(move || {
+                barrier_clone.wait();
+                bucket_clone.acquire_blocking(1).unwrap();
+            }));
+        }
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        // One token leaks every 50 ms, the first one passes right away
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(140));
+        assert!(elapsed < Duration::from_millis(500));
+    }
+}
@@ -0,0 +1 @@
+mod core_tests;
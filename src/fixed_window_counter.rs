@@ -1,4 +1,7 @@
 pub mod r#impl;
+pub mod state;
 pub mod tests;
 
-pub use r#impl::{FixedWindowCounter, FixedWindowCounterShared};
+pub use crate::core::{Decision, RateLimitError, RateLimiter, RateLimiterShared};
+pub use r#impl::{FixedWindowCounter, FixedWindowCounterBuilder, FixedWindowCounterShared};
+pub use state::{FixedWindowCounterAlgorithm, FixedWindowCounterState};
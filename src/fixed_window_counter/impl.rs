@@ -1,114 +1,260 @@
-use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant, UNIX_EPOCH};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
 
-use crate::token_bucket::r#impl::{RateLimiter, RateLimiterShared};
+use crate::clock::{Clock, Epoch, SystemClock};
+use crate::core::{
+    as_millis, unix_time_after, Decision, RateLimitError, RateLimiter, RateLimiterShared,
+};
+use crate::fixed_window_counter::{FixedWindowCounterAlgorithm, FixedWindowCounterState};
+use crate::store::Algorithm;
 
 // *** FIXED WINDOW COUNTER ***
-pub struct FixedWindowCounter {
-    limit: u32,
-    remaining: u32,
-    window: Duration,
-    last_reset: Instant,
+pub struct FixedWindowCounter<C = SystemClock> {
+    pub(crate) algorithm: FixedWindowCounterAlgorithm,
+    pub(crate) state: FixedWindowCounterState,
+    epoch: Epoch,
+    pub(crate) clock: C,
 }
 
 impl FixedWindowCounter {
     pub fn new(limit: u32, window_secs: u64) -> Self {
+        Self::with_clock(limit, window_secs, SystemClock)
+    }
+
+    pub fn try_new(limit: u32, window_secs: u64) -> Result<Self, RateLimitError> {
+        Self::builder()
+            .limit(limit)
+            .window(Duration::from_secs(window_secs))
+            .build()
+    }
+
+    pub fn with_window(limit: u32, window: Duration) -> Self {
+        Self::with_window_and_clock(limit, window, SystemClock)
+    }
+
+    pub fn builder() -> FixedWindowCounterBuilder {
+        FixedWindowCounterBuilder::new()
+    }
+}
+
+impl<C: Clock> FixedWindowCounter<C> {
+    pub fn with_clock(limit: u32, window_secs: u64, clock: C) -> Self {
+        Self::with_window_and_clock(limit, Duration::from_secs(window_secs), clock)
+    }
+
+    pub fn with_window_and_clock(limit: u32, window: Duration, clock: C) -> Self {
+        let algorithm = FixedWindowCounterAlgorithm::new(limit, window);
+        let epoch = Epoch::new(&clock);
         Self {
-            limit,
-            remaining: limit,
-            window: Duration::from_secs(window_secs),
-            last_reset: Instant::now(),
+            algorithm,
+            state: algorithm.initial_state(epoch.now(&clock)),
+            epoch,
+            clock,
         }
     }
+
+    pub(crate) fn now(&self) -> Duration {
+        self.epoch.now(&self.clock)
+    }
+
+    /// Time until the current window ends.
+    fn reset_after(&self) -> Duration {
+        self.algorithm.reset_after(&self.state, self.now())
+    }
 }
 
-impl RateLimiter for FixedWindowCounter {
-    fn refresh(&mut self) {
-        let now = Instant::now();
-        if now.duration_since(self.last_reset) >= self.window {
-            self.remaining = self.limit;
-            self.last_reset = now;
+// *** FIXED WINDOW COUNTER BUILDER ***
+pub struct FixedWindowCounterBuilder<C = SystemClock> {
+    limit: u32,
+    window: Duration,
+    clock: C,
+}
+
+impl FixedWindowCounterBuilder {
+    pub fn new() -> Self {
+        Self {
+            limit: 0,
+            window: Duration::ZERO,
+            clock: SystemClock,
         }
     }
+}
 
-    fn try_acquire(&mut self, tokens: u32) -> bool {
-        self.refresh();
-        if self.remaining >= tokens {
-            self.remaining -= tokens;
-            true
-        } else {
-            false
+impl Default for FixedWindowCounterBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Clock> FixedWindowCounterBuilder<C> {
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    pub fn clock<D: Clock>(self, clock: D) -> FixedWindowCounterBuilder<D> {
+        FixedWindowCounterBuilder {
+            limit: self.limit,
+            window: self.window,
+            clock,
+        }
+    }
+
+    pub fn build(self) -> Result<FixedWindowCounter<C>, RateLimitError> {
+        if self.limit == 0 {
+            return Err(RateLimitError::InvalidConfig(
+                "fixed window counter limit must be greater than zero".to_string(),
+            ));
         }
+        if self.window.is_zero() {
+            return Err(RateLimitError::InvalidConfig(
+                "fixed window counter window must be greater than zero".to_string(),
+            ));
+        }
+
+        Ok(FixedWindowCounter::with_window_and_clock(
+            self.limit,
+            self.window,
+            self.clock,
+        ))
+    }
+
+    pub fn build_shared(self) -> Result<FixedWindowCounterShared<C>, RateLimitError> {
+        self.build().map(FixedWindowCounterShared::from)
+    }
+}
+
+impl<C: Clock> RateLimiter for FixedWindowCounter<C> {
+    fn refresh(&mut self) {
+        let now = self.now();
+        self.algorithm.refresh(&mut self.state, now);
+    }
+
+    fn try_acquire(&mut self, tokens: u32) -> bool {
+        let now = self.now();
+        self.algorithm.try_acquire(&mut self.state, now, tokens)
     }
 
     fn get_limit(&self) -> u32 {
-        self.limit
+        self.algorithm.limit()
     }
 
     fn get_remaining(&self) -> u32 {
-        self.remaining
+        self.algorithm.remaining(&self.state, self.now())
     }
 
     fn get_used(&self) -> u32 {
-        self.limit - self.remaining
+        self.algorithm.used(&self.state, self.now())
     }
 
     fn get_reset(&self) -> u64 {
-        let now = std::time::SystemTime::now();
-        let elapsed = Instant::now().duration_since(self.last_reset);
-        let remaining = if elapsed < self.window {
-            self.window - elapsed
-        } else {
-            Duration::from_secs(0)
-        };
-        (now + remaining)
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
+        unix_time_after(self.clock.now_system(), self.reset_after()).as_secs()
+    }
+
+    fn get_reset_ms(&self) -> u64 {
+        as_millis(unix_time_after(self.clock.now_system(), self.reset_after()))
+    }
+
+    fn get_retry_after(&self, tokens: u32) -> Duration {
+        self.algorithm.retry_after(&self.state, self.now(), tokens)
     }
 }
 
 // *** FIXED RATE LIMITER SHARED ***
-pub struct FixedWindowCounterShared {
-    inner: Arc<Mutex<FixedWindowCounter>>,
+pub struct FixedWindowCounterShared<C = SystemClock> {
+    pub(crate) inner: Arc<Mutex<FixedWindowCounter<C>>>,
 }
 
 impl FixedWindowCounterShared {
     pub fn new(limit: u32, window_secs: u64) -> Self {
+        Self::with_clock(limit, window_secs, SystemClock)
+    }
+
+    pub fn try_new(limit: u32, window_secs: u64) -> Result<Self, RateLimitError> {
+        FixedWindowCounter::try_new(limit, window_secs).map(Self::from)
+    }
+
+    pub fn with_window(limit: u32, window: Duration) -> Self {
+        Self::from(FixedWindowCounter::with_window(limit, window))
+    }
+}
+
+impl<C: Clock> FixedWindowCounterShared<C> {
+    pub fn with_clock(limit: u32, window_secs: u64, clock: C) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(FixedWindowCounter::with_clock(
+                limit,
+                window_secs,
+                clock,
+            ))),
+        }
+    }
+}
+
+impl<C> From<FixedWindowCounter<C>> for FixedWindowCounterShared<C> {
+    fn from(limiter: FixedWindowCounter<C>) -> Self {
         Self {
-            inner: Arc::new(Mutex::new(FixedWindowCounter::new(limit, window_secs))),
+            inner: Arc::new(Mutex::new(limiter)),
         }
     }
 }
 
-impl RateLimiterShared for FixedWindowCounterShared {
+impl<C: Clock> RateLimiterShared for FixedWindowCounterShared<C> {
     fn refresh(&self) {
-        let mut limiter = self.inner.lock().unwrap();
+        let mut limiter = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         limiter.refresh()
     }
 
     fn try_acquire(&self, tokens: u32) -> bool {
-        let mut limiter = self.inner.lock().unwrap();
+        let mut limiter = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         limiter.try_acquire(tokens)
     }
 
+    fn try_acquire_decision(&self, tokens: u32) -> Decision {
+        let mut limiter = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        limiter.try_acquire_decision(tokens)
+    }
+
+    fn try_acquire_checked(&self, tokens: u32) -> Result<Decision, RateLimitError> {
+        let mut limiter = self
+            .inner
+            .lock()
+            .map_err(|_| RateLimitError::PoisonedLock)?;
+        limiter.try_acquire_checked(tokens)
+    }
+
     fn get_limit(&self) -> u32 {
-        let limiter = self.inner.lock().unwrap();
+        let limiter = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         limiter.get_limit()
     }
 
     fn get_remaining(&self) -> u32 {
-        let limiter = self.inner.lock().unwrap();
+        let limiter = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         limiter.get_remaining()
     }
 
     fn get_used(&self) -> u32 {
-        let limiter = self.inner.lock().unwrap();
+        let limiter = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         limiter.get_used()
     }
 
     fn get_reset(&self) -> u64 {
-        let limiter = self.inner.lock().unwrap();
+        let limiter = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         limiter.get_reset()
     }
+
+    fn get_reset_ms(&self) -> u64 {
+        let limiter = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        limiter.get_reset_ms()
+    }
+
+    fn get_retry_after(&self, tokens: u32) -> Duration {
+        let limiter = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        limiter.get_retry_after(tokens)
+    }
 }
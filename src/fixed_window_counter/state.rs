@@ -0,0 +1,104 @@
+use std::time::Duration;
+
+use crate::store::r#impl::{Algorithm, StateReader, StateWriter};
+
+const TAG: u8 = 3;
+
+// *** FIXED WINDOW COUNTER STATE ***
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct FixedWindowCounterState {
+    pub remaining: u32,
+    pub last_reset: Duration,
+}
+
+// *** FIXED WINDOW COUNTER ALGORITHM ***
+/// Fixed window transitions, see `FixedWindowCounter` for a limiter holding its own state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedWindowCounterAlgorithm {
+    limit: u32,
+    window: Duration,
+}
+
+impl FixedWindowCounterAlgorithm {
+    pub fn new(limit: u32, window: Duration) -> Self {
+        Self { limit, window }
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+}
+
+impl Algorithm for FixedWindowCounterAlgorithm {
+    type State = FixedWindowCounterState;
+
+    fn limit(&self) -> u32 {
+        self.limit
+    }
+
+    fn initial_state(&self, now: Duration) -> FixedWindowCounterState {
+        FixedWindowCounterState {
+            remaining: self.limit,
+            last_reset: now,
+        }
+    }
+
+    fn refresh(&self, state: &mut FixedWindowCounterState, now: Duration) {
+        if now.saturating_sub(state.last_reset) >= self.window {
+            state.remaining = self.limit;
+            state.last_reset = now;
+        }
+    }
+
+    fn try_acquire(&self, state: &mut FixedWindowCounterState, now: Duration, tokens: u32) -> bool {
+        self.refresh(state, now);
+        if state.remaining >= tokens {
+            state.remaining -= tokens;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn remaining(&self, state: &FixedWindowCounterState, _now: Duration) -> u32 {
+        state.remaining
+    }
+
+    fn used(&self, state: &FixedWindowCounterState, _now: Duration) -> u32 {
+        self.limit - state.remaining
+    }
+
+    /// Time until the current window ends.
+    fn reset_after(&self, state: &FixedWindowCounterState, now: Duration) -> Duration {
+        let elapsed = now.saturating_sub(state.last_reset);
+        self.window.saturating_sub(elapsed)
+    }
+
+    fn retry_after(&self, state: &FixedWindowCounterState, now: Duration, tokens: u32) -> Duration {
+        if tokens > self.limit {
+            return Duration::MAX;
+        }
+        if state.remaining >= tokens {
+            return Duration::ZERO;
+        }
+
+        self.reset_after(state, now)
+    }
+
+    fn encode(&self, state: &FixedWindowCounterState) -> Vec<u8> {
+        StateWriter::new(TAG)
+            .u32(state.remaining)
+            .duration(state.last_reset)
+            .finish()
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Option<FixedWindowCounterState> {
+        let mut reader = StateReader::new(bytes, TAG)?;
+        let state = FixedWindowCounterState {
+            remaining: reader.u32()?.min(self.limit),
+            last_reset: reader.duration()?,
+        };
+        reader.is_empty().then_some(state)
+    }
+}
@@ -1,7 +1,7 @@
 #[cfg(test)]
 mod sequential_tests {
+    use crate::core::RateLimiterShared;
     use crate::fixed_window_counter::FixedWindowCounterShared;
-    use crate::token_bucket::r#impl::RateLimiterShared;
     use std::thread;
     use std::time::Duration;
 
@@ -51,7 +51,7 @@ mod sequential_tests {
 
 #[cfg(test)]
 mod parallel_tests {
-    use crate::token_bucket::r#impl::RateLimiterShared;
+    use crate::core::RateLimiterShared;
     use crate::token_bucket::TokenBucketShared;
     use std::sync::atomic::{AtomicU32, Ordering};
     use std::sync::{Arc, Barrier};
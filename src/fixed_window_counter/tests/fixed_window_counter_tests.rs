@@ -3,8 +3,9 @@ mod sequential_tests {
     use std::thread;
     use std::time::Duration;
 
+    use crate::clock::{Clock, MockClock};
+    use crate::core::{RateLimitError, RateLimiter};
     use crate::fixed_window_counter::FixedWindowCounter;
-    use crate::token_bucket::r#impl::RateLimiter;
 
     #[test]
     fn basic_test() {
@@ -48,4 +49,114 @@ mod sequential_tests {
         let diff = bucket.get_reset() - now_unix;
         assert_eq!(diff, 4);
     }
+
+    #[test]
+    fn decision_test() {
+        let mut bucket = FixedWindowCounter::new(10, 2);
+
+        let decision = bucket.try_acquire_decision(8);
+        assert!(decision.allowed);
+        assert_eq!(decision.limit, 10);
+        assert_eq!(decision.remaining, 2);
+        assert_eq!(decision.retry_after, Duration::ZERO);
+
+        let decision = bucket.try_acquire_decision(4);
+        assert!(!decision.allowed);
+        assert_eq!(decision.remaining, 2);
+        assert!(decision.retry_after >= Duration::from_millis(1900));
+        assert!(decision.retry_after <= Duration::from_secs(2));
+
+        let decision = bucket.try_acquire_decision(11);
+        assert!(!decision.allowed);
+        assert_eq!(decision.retry_after, Duration::MAX);
+    }
+
+    #[test]
+    fn mock_clock_test() {
+        let clock = MockClock::new();
+        let mut bucket = FixedWindowCounter::with_clock(10, 2, clock.clone());
+
+        assert!(bucket.try_acquire(10));
+        assert!(!bucket.try_acquire(1));
+
+        clock.advance(Duration::from_secs(1));
+        assert!(!bucket.try_acquire(1));
+
+        clock.advance(Duration::from_secs(1));
+        assert!(bucket.try_acquire(1));
+        assert_eq!(bucket.get_remaining(), 9);
+        assert_eq!(bucket.get_used(), 1);
+    }
+
+    #[test]
+    fn builder_test() {
+        assert!(matches!(
+            FixedWindowCounter::try_new(10, 0),
+            Err(RateLimitError::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            FixedWindowCounter::try_new(0, 1),
+            Err(RateLimitError::InvalidConfig(_))
+        ));
+
+        let clock = MockClock::new();
+        let mut bucket = FixedWindowCounter::builder()
+            .limit(10)
+            .window(Duration::from_millis(500))
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        assert!(bucket.try_acquire(10));
+        assert!(!bucket.try_acquire(1));
+
+        clock.advance(Duration::from_millis(500));
+        assert!(bucket.try_acquire(10));
+    }
+
+    #[test]
+    fn sub_second_window_test() {
+        let clock = MockClock::new();
+        let mut bucket = FixedWindowCounter::builder()
+            .limit(20)
+            .window(Duration::from_millis(100))
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        let start_ms = clock
+            .now_system()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+
+        assert!(bucket.try_acquire(20));
+        assert!(!bucket.try_acquire(1));
+        assert_eq!(bucket.get_reset_ms() - start_ms, 100);
+
+        clock.advance(Duration::from_millis(60));
+        assert!(!bucket.try_acquire(1));
+        assert_eq!(bucket.get_retry_after(1), Duration::from_millis(40));
+        assert_eq!(bucket.get_reset_ms() - start_ms, 100);
+
+        clock.advance(Duration::from_millis(40));
+        assert!(bucket.try_acquire(20));
+    }
+
+    #[test]
+    fn huge_window_test() {
+        let clock = MockClock::new();
+        let mut bucket = FixedWindowCounter::builder()
+            .limit(2)
+            .window(Duration::MAX)
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+
+        assert!(bucket.try_acquire(2));
+        clock.advance(Duration::from_secs(1));
+        let decision = bucket.try_acquire_decision(1);
+        assert!(!decision.allowed);
+        assert!(decision.retry_after > Duration::from_secs(u64::from(u32::MAX)));
+        assert!(bucket.get_retry_after(2) > Duration::from_secs(u64::from(u32::MAX)));
+        assert!(bucket.get_reset() > u64::from(u32::MAX));
+    }
 }
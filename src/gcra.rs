@@ -0,0 +1,7 @@
+pub mod r#impl;
+pub mod state;
+pub mod tests;
+
+pub use crate::core::{Decision, Rate, RateLimitError, RateLimiter, RateLimiterShared};
+pub use r#impl::{Gcra, GcraBuilder, GcraShared};
+pub use state::{GcraAlgorithm, GcraState};
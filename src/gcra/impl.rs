@@ -0,0 +1,287 @@
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use crate::clock::{Clock, Epoch, SystemClock};
+use crate::core::{
+    as_millis, unix_time_after, Decision, Rate, RateLimitError, RateLimiter, RateLimiterShared,
+};
+use crate::gcra::{GcraAlgorithm, GcraState};
+use crate::store::Algorithm;
+
+// *** GCRA ***
+/// Generic Cell Rate Algorithm: one token is emitted every `emission_interval` and up to
+/// `burst_tolerance` worth of tokens may be taken ahead of schedule. The whole state is a
+/// single timestamp, the theoretical arrival time of the next token.
+pub struct Gcra<C = SystemClock> {
+    pub(crate) algorithm: GcraAlgorithm,
+    pub(crate) state: GcraState,
+    epoch: Epoch,
+    pub(crate) clock: C,
+}
+
+impl Gcra {
+    pub fn new(emission_interval: Duration, burst_tolerance: Duration) -> Self {
+        Self::with_clock(emission_interval, burst_tolerance, SystemClock)
+    }
+
+    pub fn try_new(
+        emission_interval: Duration,
+        burst_tolerance: Duration,
+    ) -> Result<Self, RateLimitError> {
+        Self::builder()
+            .emission_interval(emission_interval)
+            .burst_tolerance(burst_tolerance)
+            .build()
+    }
+
+    /// `rate` tokens on average, with up to `burst` tokens at once.
+    pub fn with_rate(rate: Rate, burst: u32) -> Self {
+        let emission_interval = rate.interval();
+        Self::new(
+            emission_interval,
+            emission_interval.saturating_mul(burst.saturating_sub(1)),
+        )
+    }
+
+    pub fn builder() -> GcraBuilder {
+        GcraBuilder::new()
+    }
+}
+
+impl<C: Clock> Gcra<C> {
+    pub fn with_clock(emission_interval: Duration, burst_tolerance: Duration, clock: C) -> Self {
+        let algorithm = GcraAlgorithm::new(emission_interval, burst_tolerance);
+        let epoch = Epoch::new(&clock);
+        Self {
+            algorithm,
+            state: algorithm.initial_state(epoch.now(&clock)),
+            epoch,
+            clock,
+        }
+    }
+
+    pub(crate) fn now(&self) -> Duration {
+        self.epoch.now(&self.clock)
+    }
+
+    /// Time the theoretical arrival time is ahead of now.
+    fn reset_after(&self) -> Duration {
+        self.algorithm.reset_after(&self.state, self.now())
+    }
+}
+
+// *** GCRA BUILDER ***
+pub struct GcraBuilder<C = SystemClock> {
+    emission_interval: Duration,
+    burst_tolerance: Duration,
+    burst: Option<u32>,
+    clock: C,
+}
+
+impl GcraBuilder {
+    pub fn new() -> Self {
+        Self {
+            emission_interval: Duration::ZERO,
+            burst_tolerance: Duration::ZERO,
+            burst: None,
+            clock: SystemClock,
+        }
+    }
+}
+
+impl Default for GcraBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Clock> GcraBuilder<C> {
+    pub fn emission_interval(mut self, emission_interval: Duration) -> Self {
+        self.emission_interval = emission_interval;
+        self
+    }
+
+    /// Same as `emission_interval(rate.interval())`.
+    pub fn rate(self, rate: Rate) -> Self {
+        self.emission_interval(rate.interval())
+    }
+
+    pub fn burst_tolerance(mut self, burst_tolerance: Duration) -> Self {
+        self.burst_tolerance = burst_tolerance;
+        self.burst = None;
+        self
+    }
+
+    /// Tokens that may be taken at once, overrides `burst_tolerance`.
+    pub fn burst(mut self, burst: u32) -> Self {
+        self.burst = Some(burst);
+        self
+    }
+
+    pub fn clock<D: Clock>(self, clock: D) -> GcraBuilder<D> {
+        GcraBuilder {
+            emission_interval: self.emission_interval,
+            burst_tolerance: self.burst_tolerance,
+            burst: self.burst,
+            clock,
+        }
+    }
+
+    pub fn build(self) -> Result<Gcra<C>, RateLimitError> {
+        if self.emission_interval.is_zero() {
+            return Err(RateLimitError::InvalidConfig(
+                "gcra emission interval must be greater than zero".to_string(),
+            ));
+        }
+        let burst_tolerance = match self.burst {
+            Some(0) => {
+                return Err(RateLimitError::InvalidConfig(
+                    "gcra burst must be greater than zero".to_string(),
+                ))
+            }
+            Some(burst) => self.emission_interval.saturating_mul(burst - 1),
+            None => self.burst_tolerance,
+        };
+
+        Ok(Gcra::with_clock(
+            self.emission_interval,
+            burst_tolerance,
+            self.clock,
+        ))
+    }
+
+    pub fn build_shared(self) -> Result<GcraShared<C>, RateLimitError> {
+        self.build().map(GcraShared::from)
+    }
+}
+
+impl<C: Clock> RateLimiter for Gcra<C> {
+    fn refresh(&mut self) {
+        let now = self.now();
+        self.algorithm.refresh(&mut self.state, now);
+    }
+
+    fn try_acquire(&mut self, tokens: u32) -> bool {
+        let now = self.now();
+        self.algorithm.try_acquire(&mut self.state, now, tokens)
+    }
+
+    fn get_limit(&self) -> u32 {
+        self.algorithm.limit()
+    }
+
+    fn get_remaining(&self) -> u32 {
+        self.algorithm.remaining(&self.state, self.now())
+    }
+
+    fn get_used(&self) -> u32 {
+        self.algorithm.used(&self.state, self.now())
+    }
+
+    fn get_reset(&self) -> u64 {
+        unix_time_after(self.clock.now_system(), self.reset_after()).as_secs()
+    }
+
+    fn get_reset_ms(&self) -> u64 {
+        as_millis(unix_time_after(self.clock.now_system(), self.reset_after()))
+    }
+
+    fn get_retry_after(&self, tokens: u32) -> Duration {
+        self.algorithm.retry_after(&self.state, self.now(), tokens)
+    }
+
+    fn is_idle(&self) -> bool {
+        self.algorithm.is_idle(&self.state, self.now())
+    }
+}
+
+// *** GCRA SHARED ***
+pub struct GcraShared<C = SystemClock> {
+    pub(crate) inner: Arc<Mutex<Gcra<C>>>,
+}
+
+impl GcraShared {
+    pub fn new(emission_interval: Duration, burst_tolerance: Duration) -> Self {
+        Self::with_clock(emission_interval, burst_tolerance, SystemClock)
+    }
+
+    pub fn try_new(
+        emission_interval: Duration,
+        burst_tolerance: Duration,
+    ) -> Result<Self, RateLimitError> {
+        Gcra::try_new(emission_interval, burst_tolerance).map(Self::from)
+    }
+
+    pub fn with_rate(rate: Rate, burst: u32) -> Self {
+        Self::from(Gcra::with_rate(rate, burst))
+    }
+}
+
+impl<C: Clock> GcraShared<C> {
+    pub fn with_clock(emission_interval: Duration, burst_tolerance: Duration, clock: C) -> Self {
+        Self::from(Gcra::with_clock(emission_interval, burst_tolerance, clock))
+    }
+}
+
+impl<C> From<Gcra<C>> for GcraShared<C> {
+    fn from(limiter: Gcra<C>) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(limiter)),
+        }
+    }
+}
+
+impl<C: Clock> RateLimiterShared for GcraShared<C> {
+    fn refresh(&self) {
+        let mut limiter = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        limiter.refresh()
+    }
+
+    fn try_acquire(&self, tokens: u32) -> bool {
+        let mut limiter = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        limiter.try_acquire(tokens)
+    }
+
+    fn try_acquire_decision(&self, tokens: u32) -> Decision {
+        let mut limiter = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        limiter.try_acquire_decision(tokens)
+    }
+
+    fn try_acquire_checked(&self, tokens: u32) -> Result<Decision, RateLimitError> {
+        let mut limiter = self
+            .inner
+            .lock()
+            .map_err(|_| RateLimitError::PoisonedLock)?;
+        limiter.try_acquire_checked(tokens)
+    }
+
+    fn get_limit(&self) -> u32 {
+        let limiter = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        limiter.get_limit()
+    }
+
+    fn get_remaining(&self) -> u32 {
+        let limiter = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        limiter.get_remaining()
+    }
+
+    fn get_used(&self) -> u32 {
+        let limiter = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        limiter.get_used()
+    }
+
+    fn get_reset(&self) -> u64 {
+        let limiter = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        limiter.get_reset()
+    }
+
+    fn get_reset_ms(&self) -> u64 {
+        let limiter = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        limiter.get_reset_ms()
+    }
+
+    fn get_retry_after(&self, tokens: u32) -> Duration {
+        let limiter = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        limiter.get_retry_after(tokens)
+    }
+}
@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use crate::store::r#impl::{Algorithm, StateReader, StateWriter};
+
+const TAG: u8 = 6;
+
+// *** GCRA STATE ***
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct GcraState {
+    /// Theoretical arrival time of the next token.
+    pub tat: Duration,
+}
+
+// *** GCRA ALGORITHM ***
+/// GCRA transitions, see `Gcra` for a limiter holding its own state. Reads are always up to
+/// date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GcraAlgorithm {
+    emission_interval: Duration,
+    burst_tolerance: Duration,
+}
+
+impl GcraAlgorithm {
+    pub fn new(emission_interval: Duration, burst_tolerance: Duration) -> Self {
+        Self {
+            emission_interval,
+            burst_tolerance,
+        }
+    }
+
+    pub fn emission_interval(&self) -> Duration {
+        self.emission_interval
+    }
+
+    pub fn burst_tolerance(&self) -> Duration {
+        self.burst_tolerance
+    }
+
+    /// How far ahead of `now` the theoretical arrival time may be pushed.
+    fn max_delay(&self) -> Duration {
+        self.burst_tolerance.saturating_add(self.emission_interval)
+    }
+}
+
+impl Algorithm for GcraAlgorithm {
+    type State = GcraState;
+
+    fn limit(&self) -> u32 {
+        if self.emission_interval.is_zero() {
+            return u32::MAX;
+        }
+        let limit = self.max_delay().as_nanos() / self.emission_interval.as_nanos();
+        limit.min(u32::MAX as u128) as u32
+    }
+
+    fn initial_state(&self, now: Duration) -> GcraState {
+        GcraState { tat: now }
+    }
+
+    fn refresh(&self, _state: &mut GcraState, _now: Duration) {
+        // The state is a single timestamp compared against the clock, nothing to refresh
+    }
+
+    fn try_acquire(&self, state: &mut GcraState, now: Duration, tokens: u32) -> bool {
+        let cost = self.emission_interval.saturating_mul(tokens);
+        let tat = state.tat.max(now).saturating_add(cost);
+        if tat.saturating_sub(now) <= self.max_delay() {
+            state.tat = tat;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn remaining(&self, state: &GcraState, now: Duration) -> u32 {
+        if self.emission_interval.is_zero() {
+            return u32::MAX;
+        }
+        let free = self
+            .max_delay()
+            .saturating_sub(self.reset_after(state, now));
+        let remaining = free.as_nanos() / self.emission_interval.as_nanos();
+        remaining.min(u32::MAX as u128) as u32
+    }
+
+    fn used(&self, state: &GcraState, now: Duration) -> u32 {
+        self.limit().saturating_sub(self.remaining(state, now))
+    }
+
+    fn is_idle(&self, state: &GcraState, now: Duration) -> bool {
+        state.tat <= now
+    }
+
+    /// Time the theoretical arrival time is ahead of `now`.
+    fn reset_after(&self, state: &GcraState, now: Duration) -> Duration {
+        state.tat.saturating_sub(now)
+    }
+
+    fn retry_after(&self, state: &GcraState, now: Duration, tokens: u32) -> Duration {
+        if tokens > self.limit() {
+            return Duration::MAX;
+        }
+        let cost = self.emission_interval.saturating_mul(tokens);
+        let delay = self.reset_after(state, now).saturating_add(cost);
+        delay.saturating_sub(self.max_delay())
+    }
+
+    fn encode(&self, state: &GcraState) -> Vec<u8> {
+        StateWriter::new(TAG).duration(state.tat).finish()
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Option<GcraState> {
+        let mut reader = StateReader::new(bytes, TAG)?;
+        let state = GcraState {
+            tat: reader.duration()?,
+        };
+        reader.is_empty().then_some(state)
+    }
+}
@@ -0,0 +1,82 @@
+#[cfg(test)]
+mod sequential_tests {
+    use crate::clock::MockClock;
+    use crate::core::RateLimiterShared;
+    use crate::gcra::{Gcra, GcraShared};
+    use std::time::Duration;
+
+    #[test]
+    fn basic_test() {
+        let clock = MockClock::new();
+        let limiter = GcraShared::with_clock(
+            Duration::from_millis(100),
+            Duration::from_millis(900),
+            clock.clone(),
+        );
+        assert_eq!(limiter.get_limit(), 10);
+        assert_eq!(limiter.get_remaining(), 10);
+
+        assert!(limiter.try_acquire(10));
+        assert_eq!(limiter.get_used(), 10);
+        assert!(!limiter.try_acquire(1));
+        assert_eq!(limiter.get_retry_after(1), Duration::from_millis(100));
+
+        clock.advance(Duration::from_millis(500));
+        assert_eq!(limiter.get_remaining(), 5);
+        assert!(limiter.try_acquire(5));
+    }
+
+    #[test]
+    fn from_test() {
+        let clock = MockClock::new();
+        let limiter: GcraShared<MockClock> = Gcra::builder()
+            .emission_interval(Duration::from_secs(1))
+            .burst(3)
+            .clock(clock.clone())
+            .build_shared()
+            .unwrap();
+        assert_eq!(limiter.get_limit(), 3);
+        assert!(limiter.try_acquire_checked(3).is_ok());
+        assert!(limiter.try_acquire_checked(1).is_err());
+    }
+}
+
+#[cfg(test)]
+mod parallel_tests {
+    use crate::core::RateLimiterShared;
+    use crate::gcra::GcraShared;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn race_condition_test() {
+        let limiter = Arc::new(GcraShared::new(
+            Duration::from_secs(1),
+            Duration::from_secs(9),
+        ));
+        let success_count = Arc::new(AtomicU32::new(0));
+        let barrier = Arc::new(Barrier::new(20));
+
+        let mut handles = vec![];
+        for _ in 0..20 {
+            let limiter_clone = Arc::clone(&limiter);
+            let success_count_clone = Arc::clone(&success_count);
+            let barrier_clone = Arc::clone(&barrier);
+
+            handles.push(thread::spawn(move || {
+                barrier_clone.wait();
+                if limiter_clone.try_acquire(1) {
+                    success_count_clone.fetch_add(1, Ordering::SeqCst);
+                }
+            }));
+        }
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(success_count.load(Ordering::SeqCst), 10);
+    }
+}
@@ -0,0 +1,144 @@
+#[cfg(test)]
+mod sequential_tests {
+    use crate::clock::{Clock, MockClock};
+    use crate::core::{Rate, RateLimitError, RateLimiter};
+    use crate::gcra::Gcra;
+    use std::time::Duration;
+
+    #[test]
+    fn basic_test() {
+        let clock = MockClock::new();
+        let mut limiter = Gcra::with_clock(
+            Duration::from_millis(100),
+            Duration::from_millis(400),
+            clock.clone(),
+        );
+        assert_eq!(limiter.get_limit(), 5);
+        assert_eq!(limiter.get_remaining(), 5);
+        assert_eq!(limiter.get_used(), 0);
+        assert_eq!(limiter.get_retry_after(1), Duration::ZERO);
+
+        assert!(limiter.try_acquire(3));
+        assert_eq!(limiter.get_remaining(), 2);
+        assert_eq!(limiter.get_used(), 3);
+
+        assert!(limiter.try_acquire(2));
+        assert_eq!(limiter.get_remaining(), 0);
+        assert!(!limiter.try_acquire(1));
+        assert_eq!(limiter.get_retry_after(1), Duration::from_millis(100));
+        assert_eq!(limiter.get_retry_after(3), Duration::from_millis(300));
+        assert_eq!(limiter.get_retry_after(6), Duration::MAX);
+
+        clock.advance(Duration::from_millis(150));
+        assert_eq!(limiter.get_remaining(), 1);
+        assert_eq!(limiter.get_retry_after(2), Duration::from_millis(50));
+        assert!(!limiter.try_acquire(2));
+        assert!(limiter.try_acquire(1));
+
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(limiter.get_remaining(), 5);
+        assert_eq!(limiter.get_used(), 0);
+    }
+
+    #[test]
+    fn reset_test() {
+        let clock = MockClock::new();
+        let mut limiter = Gcra::with_clock(
+            Duration::from_millis(250),
+            Duration::from_millis(750),
+            clock.clone(),
+        );
+        let now_ms = clock
+            .now_system()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        assert_eq!(limiter.get_reset_ms(), now_ms);
+
+        assert!(limiter.try_acquire(3));
+        assert_eq!(limiter.get_reset_ms(), now_ms + 750);
+
+        clock.advance(Duration::from_millis(500));
+        assert_eq!(limiter.get_reset_ms(), now_ms + 750);
+
+        clock.advance(Duration::from_millis(250));
+        assert_eq!(limiter.get_reset_ms(), now_ms + 750);
+        assert_eq!(limiter.get_remaining(), 4);
+    }
+
+    #[test]
+    fn steady_rate_test() {
+        let clock = MockClock::new();
+        let mut limiter =
+            Gcra::with_clock(Duration::from_millis(100), Duration::ZERO, clock.clone());
+        assert_eq!(limiter.get_limit(), 1);
+
+        let mut acquired = 0;
+        for _ in 0..120 {
+            if limiter.try_acquire(1) {
+                acquired += 1;
+            }
+            clock.advance(Duration::from_millis(25));
+        }
+        // 3 seconds at one token per 100 ms, plus the first one
+        assert_eq!(acquired, 30);
+    }
+
+    #[test]
+    fn builder_test() {
+        let clock = MockClock::new();
+        let limiter = Gcra::builder()
+            .rate(Rate::per_second(4))
+            .burst(8)
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        assert_eq!(limiter.get_limit(), 8);
+        assert_eq!(limiter.get_retry_after(8), Duration::ZERO);
+
+        let mut limiter = Gcra::with_rate(Rate::per_second(4), 8);
+        assert_eq!(limiter.get_limit(), 8);
+        assert!(limiter.try_acquire(8));
+        assert!(!limiter.try_acquire(1));
+
+        assert!(matches!(
+            Gcra::try_new(Duration::ZERO, Duration::from_secs(1)),
+            Err(RateLimitError::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            Gcra::builder()
+                .emission_interval(Duration::from_millis(10))
+                .burst(0)
+                .build(),
+            Err(RateLimitError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn decision_test() {
+        let clock = MockClock::new();
+        let mut limiter = Gcra::with_clock(
+            Duration::from_millis(200),
+            Duration::from_millis(200),
+            clock.clone(),
+        );
+
+        let decision = limiter.try_acquire_decision(2);
+        assert!(decision.allowed);
+        assert_eq!(decision.limit, 2);
+        assert_eq!(decision.remaining, 0);
+        assert_eq!(decision.retry_after, Duration::ZERO);
+
+        let decision = limiter.try_acquire_decision(1);
+        assert!(!decision.allowed);
+        assert_eq!(decision.retry_after, Duration::from_millis(200));
+
+        assert!(matches!(
+            limiter.try_acquire_checked(3),
+            Err(RateLimitError::InsufficientCapacity {
+                requested: 3,
+                capacity: 2
+            })
+        ));
+    }
+}
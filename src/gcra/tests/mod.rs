@@ -0,0 +1,2 @@
+mod gcra_shared_tests;
+mod gcra_tests;
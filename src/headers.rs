@@ -0,0 +1,8 @@
+pub mod r#impl;
+pub mod tests;
+
+pub use crate::core::Decision;
+pub use r#impl::{
+    RateLimitHeaders, RATELIMIT_LIMIT, RATELIMIT_REMAINING, RATELIMIT_RESET, RETRY_AFTER,
+    X_RATELIMIT_LIMIT, X_RATELIMIT_REMAINING, X_RATELIMIT_RESET,
+};
@@ -0,0 +1,115 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::core::Decision;
+
+// *** HEADER NAMES ***
+/// IETF `RateLimit` header fields draft, reset is in seconds from now.
+pub const RATELIMIT_LIMIT: &str = "ratelimit-limit";
+pub const RATELIMIT_REMAINING: &str = "ratelimit-remaining";
+pub const RATELIMIT_RESET: &str = "ratelimit-reset";
+
+/// Legacy `X-RateLimit-*` headers, reset is a UNIX timestamp in seconds.
+pub const X_RATELIMIT_LIMIT: &str = "x-ratelimit-limit";
+pub const X_RATELIMIT_REMAINING: &str = "x-ratelimit-remaining";
+pub const X_RATELIMIT_RESET: &str = "x-ratelimit-reset";
+
+pub const RETRY_AFTER: &str = "retry-after";
+
+// *** RATE LIMIT HEADERS ***
+/// Rate limit response headers derived from a `Decision`, independent of any HTTP library.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitHeaders {
+    pub limit: u32,
+    pub remaining: u32,
+    /// Seconds until the limiter is fully reset.
+    pub reset_after: u64,
+    /// UNIX time in seconds at which the limiter is fully reset.
+    pub reset: u64,
+    /// Whole seconds to wait before retrying, only set for a denied request that may pass.
+    pub retry_after: Option<u64>,
+}
+
+impl RateLimitHeaders {
+    pub fn from_decision(decision: &Decision) -> Self {
+        Self::from_decision_at(decision, SystemTime::now())
+    }
+
+    pub fn from_decision_at(decision: &Decision, now: SystemTime) -> Self {
+        let now = now
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        let retry_after = decision.can_retry().then(|| {
+            let retry_after = decision.retry_after;
+            // Rounded up so a client retrying on time is not denied again
+            retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0)
+        });
+
+        Self {
+            limit: decision.limit,
+            remaining: decision.remaining,
+            reset_after: decision.reset.saturating_sub(now),
+            reset: decision.reset,
+            retry_after,
+        }
+    }
+
+    /// Header names and values, `Retry-After` last when present.
+    pub fn to_pairs(&self) -> Vec<(&'static str, String)> {
+        let mut pairs = vec![
+            (RATELIMIT_LIMIT, self.limit.to_string()),
+            (RATELIMIT_REMAINING, self.remaining.to_string()),
+            (RATELIMIT_RESET, self.reset_after.to_string()),
+            (X_RATELIMIT_LIMIT, self.limit.to_string()),
+            (X_RATELIMIT_REMAINING, self.remaining.to_string()),
+            (X_RATELIMIT_RESET, self.reset.to_string()),
+        ];
+        if let Some(retry_after) = self.retry_after {
+            pairs.push((RETRY_AFTER, retry_after.to_string()));
+        }
+        pairs
+    }
+}
+
+// *** HEADER MAP ***
+/// Header maps of the HTTP libraries the integrations write into.
+#[cfg(any(feature = "axum", feature = "tonic", feature = "actix"))]
+pub(crate) trait InsertHeader {
+    /// Inserts `value` under `name`, skipping values that aren't valid header values.
+    fn insert_header(&mut self, name: &'static str, value: &str);
+}
+
+#[cfg(any(feature = "axum", feature = "tonic"))]
+impl InsertHeader for http::HeaderMap {
+    fn insert_header(&mut self, name: &'static str, value: &str) {
+        if let Ok(value) = http::HeaderValue::from_str(value) {
+            self.insert(http::HeaderName::from_static(name), value);
+        }
+    }
+}
+
+#[cfg(feature = "actix")]
+impl InsertHeader for actix_web::http::header::HeaderMap {
+    fn insert_header(&mut self, name: &'static str, value: &str) {
+        use actix_web::http::header::{HeaderName, HeaderValue};
+
+        if let Ok(value) = HeaderValue::from_str(value) {
+            self.insert(HeaderName::from_static(name), value);
+        }
+    }
+}
+
+impl RateLimitHeaders {
+    /// Writes every header into `map`, replacing values already there.
+    #[cfg(any(feature = "axum", feature = "tonic", feature = "actix"))]
+    pub(crate) fn insert_into(&self, map: &mut impl InsertHeader) {
+        for (name, value) in self.to_pairs() {
+            map.insert_header(name, &value);
+        }
+    }
+}
+
+impl From<Decision> for RateLimitHeaders {
+    fn from(decision: Decision) -> Self {
+        Self::from_decision(&decision)
+    }
+}
@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod sequential_tests {
+    use crate::core::Decision;
+    use crate::headers::RateLimitHeaders;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn from_decision_test() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_000);
+        let decision = Decision {
+            allowed: false,
+            limit: 10,
+            remaining: 0,
+            retry_after: Duration::from_millis(1_500),
+            reset: 1_030,
+        };
+
+        let headers = RateLimitHeaders::from_decision_at(&decision, now);
+        assert_eq!(headers.reset_after, 30);
+        assert_eq!(headers.retry_after, Some(2));
+        assert_eq!(
+            headers.to_pairs(),
+            vec![
+                ("ratelimit-limit", "10".to_string()),
+                ("ratelimit-remaining", "0".to_string()),
+                ("ratelimit-reset", "30".to_string()),
+                ("x-ratelimit-limit", "10".to_string()),
+                ("x-ratelimit-remaining", "0".to_string()),
+                ("x-ratelimit-reset", "1030".to_string()),
+                ("retry-after", "2".to_string()),
+            ]
+        );
+
+        let allowed = Decision {
+            allowed: true,
+            remaining: 9,
+            retry_after: Duration::ZERO,
+            ..decision
+        };
+        assert_eq!(
+            RateLimitHeaders::from_decision_at(&allowed, now).retry_after,
+            None
+        );
+
+        // Never passes, so there is no point telling the client when to retry
+        let never = Decision {
+            retry_after: Duration::MAX,
+            ..decision
+        };
+        let headers = RateLimitHeaders::from_decision_at(&never, now);
+        assert_eq!(headers.retry_after, None);
+        assert_eq!(headers.to_pairs().len(), 6);
+    }
+}
@@ -0,0 +1 @@
+mod headers_tests;
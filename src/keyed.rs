@@ -0,0 +1,7 @@
+pub mod r#impl;
+pub mod sharded;
+pub mod tests;
+
+pub use crate::core::{Decision, KeyedRateLimiterShared, RateLimitError, RateLimiter};
+pub use r#impl::{KeyedLimiter, KeyedLimiterBuilder};
+pub use sharded::ShardedKeyedLimiter;
@@ -0,0 +1,403 @@
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+use crate::core::{Decision, KeyedRateLimiterShared, RateLimitError, RateLimiter};
+use crate::keyed::sharded::ShardedKeyedLimiter;
+
+pub(super) type Factory<K, L> = Box<dyn Fn(&K) -> L + Send + Sync>;
+
+/// Inserts between automatic idle sweeps never drop below this.
+const MIN_SWEEP_INTERVAL: usize = 64;
+
+/// How keys leave a `State`.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Eviction {
+    pub(super) max_keys: Option<usize>,
+    pub(super) evict_idle: bool,
+}
+
+struct Entry<L> {
+    limiter: L,
+    last_used: u64,
+}
+
+/// Limiters behind one lock, along with what is needed to evict them.
+pub(super) struct State<K, L> {
+    entries: HashMap<K, Entry<L>>,
+    /// Keys ordered by last use, oldest first.
+    recency: BTreeMap<u64, K>,
+    tick: u64,
+    inserts_since_sweep: usize,
+    sweep_interval: usize,
+}
+
+impl<K, L> State<K, L>
+where
+    K: Eq + Hash + Clone,
+    L: RateLimiter,
+{
+    pub(super) fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+            inserts_since_sweep: 0,
+            sweep_interval: MIN_SWEEP_INTERVAL,
+        }
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub(super) fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries.contains_key(key)
+    }
+
+    pub(super) fn remove<Q>(&mut self, key: &Q) -> Option<L>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let entry = self.entries.remove(key)?;
+        self.recency.remove(&entry.last_used);
+        Some(entry.limiter)
+    }
+
+    pub(super) fn evict_idle(&mut self) -> usize {
+        let before = self.entries.len();
+        let recency = &mut self.recency;
+        self.entries.retain(|_, entry| {
+            entry.limiter.refresh();
+            let idle = entry.limiter.is_idle();
+            if idle {
+                recency.remove(&entry.last_used);
+            }
+            !idle
+        });
+        // Sweeping again once the key count could have doubled keeps the cost amortized O(1)
+        self.inserts_since_sweep = 0;
+        self.sweep_interval = self.entries.len().max(MIN_SWEEP_INTERVAL);
+        before - self.entries.len()
+    }
+
+    pub(super) fn shrink_to_fit(&mut self) {
+        self.entries.shrink_to_fit()
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+        self.inserts_since_sweep = 0;
+        self.sweep_interval = MIN_SWEEP_INTERVAL;
+    }
+
+    /// The limiter for `key`, created by `factory` if the key isn't tracked yet.
+    pub(super) fn limiter_mut<Q>(
+        &mut self,
+        key: &Q,
+        factory: &Factory<K, L>,
+        eviction: Eviction,
+    ) -> &mut L
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = K> + ?Sized,
+    {
+        self.tick += 1;
+        let tick = self.tick;
+        // Look up by reference first so hits don't allocate an owned key
+        if let Some(entry) = self.entries.get_mut(key) {
+            let key = self.recency.remove(&entry.last_used).unwrap();
+            self.recency.insert(tick, key);
+            entry.last_used = tick;
+        } else {
+            self.make_room(eviction);
+            let key = key.to_owned();
+            let limiter = factory(&key);
+            self.recency.insert(tick, key.clone());
+            self.entries.insert(
+                key,
+                Entry {
+                    limiter,
+                    last_used: tick,
+                },
+            );
+            self.inserts_since_sweep += 1;
+        }
+        &mut self.entries.get_mut(key).unwrap().limiter
+    }
+
+    /// Runs `query` on the refreshed limiter for `key`, or on a fresh one if it isn't tracked.
+    pub(super) fn inspect<Q, R>(
+        &mut self,
+        key: &Q,
+        factory: &Factory<K, L>,
+        query: impl FnOnce(&L) -> R,
+    ) -> R
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = K> + ?Sized,
+    {
+        match self.entries.get_mut(key) {
+            Some(entry) => {
+                entry.limiter.refresh();
+                query(&entry.limiter)
+            }
+            None => query(&factory(&key.to_owned())),
+        }
+    }
+
+    fn make_room(&mut self, eviction: Eviction) {
+        if eviction.evict_idle && self.inserts_since_sweep >= self.sweep_interval {
+            self.evict_idle();
+        }
+
+        if let Some(max_keys) = eviction.max_keys {
+            while self.entries.len() >= max_keys {
+                let Some((_, key)) = self.recency.pop_first() else {
+                    break;
+                };
+                self.entries.remove(&key);
+            }
+        }
+    }
+}
+
+// *** KEYED LIMITER ***
+/// One limiter per key, created on first use by `factory`. Queries on a key that was never
+/// acquired report what a fresh limiter would, without tracking the key.
+///
+/// Keys whose limiter is idle are dropped automatically every so often, and with `max_keys`
+/// the least recently used key makes room for a new one.
+pub struct KeyedLimiter<K, L> {
+    state: Mutex<State<K, L>>,
+    factory: Factory<K, L>,
+    eviction: Eviction,
+}
+
+impl<K, L> KeyedLimiter<K, L>
+where
+    K: Eq + Hash + Clone,
+    L: RateLimiter,
+{
+    pub fn new<F>(factory: F) -> Self
+    where
+        F: Fn(&K) -> L + Send + Sync + 'static,
+    {
+        Self::with_factory(
+            Box::new(factory),
+            Eviction {
+                max_keys: None,
+                evict_idle: true,
+            },
+        )
+    }
+
+    pub fn builder<F>(factory: F) -> KeyedLimiterBuilder<K, L>
+    where
+        F: Fn(&K) -> L + Send + Sync + 'static,
+    {
+        KeyedLimiterBuilder::new(factory)
+    }
+
+    fn with_factory(factory: Factory<K, L>, eviction: Eviction) -> Self {
+        Self {
+            state: Mutex::new(State::new()),
+            factory,
+            eviction,
+        }
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.contains_key(key)
+    }
+
+    /// Stops tracking `key`, its next request starts from a fresh limiter.
+    pub fn remove<Q>(&self, key: &Q) -> Option<L>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.remove(key)
+    }
+
+    /// Drops every key whose limiter is back at its idle state and returns how many were
+    /// dropped. Meant to be called periodically, e.g. from a background task.
+    pub fn retain_recent(&self) -> usize {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.evict_idle()
+    }
+
+    /// Releases memory left over from evicted keys.
+    pub fn shrink_to_fit(&self) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.shrink_to_fit()
+    }
+
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.clear()
+    }
+
+    pub fn len(&self) -> usize {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<K, L, Q> KeyedRateLimiterShared<Q> for KeyedLimiter<K, L>
+where
+    K: Eq + Hash + Clone + Borrow<Q>,
+    L: RateLimiter,
+    Q: Hash + Eq + ToOwned<Owned = K> + ?Sized,
+{
+    fn try_acquire(&self, key: &Q, tokens: u32) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state
+            .limiter_mut(key, &self.factory, self.eviction)
+            .try_acquire(tokens)
+    }
+
+    fn try_acquire_decision(&self, key: &Q, tokens: u32) -> Decision {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state
+            .limiter_mut(key, &self.factory, self.eviction)
+            .try_acquire_decision(tokens)
+    }
+
+    fn try_acquire_checked(&self, key: &Q, tokens: u32) -> Result<Decision, RateLimitError> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| RateLimitError::PoisonedLock)?;
+        state
+            .limiter_mut(key, &self.factory, self.eviction)
+            .try_acquire_checked(tokens)
+    }
+
+    fn get_limit(&self, key: &Q) -> u32 {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.inspect(key, &self.factory, |limiter| limiter.get_limit())
+    }
+
+    fn get_remaining(&self, key: &Q) -> u32 {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.inspect(key, &self.factory, |limiter| limiter.get_remaining())
+    }
+
+    fn get_used(&self, key: &Q) -> u32 {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.inspect(key, &self.factory, |limiter| limiter.get_used())
+    }
+
+    fn get_reset(&self, key: &Q) -> u64 {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.inspect(key, &self.factory, |limiter| limiter.get_reset())
+    }
+
+    fn get_reset_ms(&self, key: &Q) -> u64 {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.inspect(key, &self.factory, |limiter| limiter.get_reset_ms())
+    }
+
+    fn get_retry_after(&self, key: &Q, tokens: u32) -> Duration {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.inspect(key, &self.factory, |limiter| {
+            limiter.get_retry_after(tokens)
+        })
+    }
+}
+
+// *** KEYED LIMITER BUILDER ***
+pub struct KeyedLimiterBuilder<K, L> {
+    factory: Factory<K, L>,
+    max_keys: Option<usize>,
+    evict_idle: bool,
+    shards: Option<usize>,
+}
+
+impl<K, L> KeyedLimiterBuilder<K, L>
+where
+    K: Eq + Hash + Clone,
+    L: RateLimiter,
+{
+    pub fn new<F>(factory: F) -> Self
+    where
+        F: Fn(&K) -> L + Send + Sync + 'static,
+    {
+        Self {
+            factory: Box::new(factory),
+            max_keys: None,
+            evict_idle: true,
+            shards: None,
+        }
+    }
+
+    /// Hard cap on tracked keys, the least recently used key is dropped to make room.
+    /// A sharded limiter splits the cap between its shards and uses at most `max_keys` shards.
+    pub fn max_keys(mut self, max_keys: usize) -> Self {
+        self.max_keys = Some(max_keys);
+        self
+    }
+
+    /// Whether idle keys are dropped automatically, enabled by default.
+    pub fn evict_idle(mut self, evict_idle: bool) -> Self {
+        self.evict_idle = evict_idle;
+        self
+    }
+
+    /// Number of independently locked shards used by `build_sharded`.
+    pub fn shards(mut self, shards: usize) -> Self {
+        self.shards = Some(shards);
+        self
+    }
+
+    fn validate(&self) -> Result<Eviction, RateLimitError> {
+        if self.max_keys == Some(0) {
+            return Err(RateLimitError::InvalidConfig(
+                "keyed limiter max keys must be greater than zero".to_string(),
+            ));
+        }
+        if self.shards == Some(0) {
+            return Err(RateLimitError::InvalidConfig(
+                "keyed limiter shards must be greater than zero".to_string(),
+            ));
+        }
+
+        Ok(Eviction {
+            max_keys: self.max_keys,
+            evict_idle: self.evict_idle,
+        })
+    }
+
+    pub fn build(self) -> Result<KeyedLimiter<K, L>, RateLimitError> {
+        let eviction = self.validate()?;
+        Ok(KeyedLimiter::with_factory(self.factory, eviction))
+    }
+
+    pub fn build_sharded(self) -> Result<ShardedKeyedLimiter<K, L>, RateLimitError> {
+        let eviction = self.validate()?;
+        Ok(ShardedKeyedLimiter::with_factory(
+            self.factory,
+            eviction,
+            self.shards,
+        ))
+    }
+}
@@ -0,0 +1,246 @@
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::num::NonZeroUsize;
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+use crate::core::{Decision, KeyedRateLimiterShared, RateLimitError, RateLimiter};
+use crate::keyed::r#impl::{Eviction, Factory, KeyedLimiterBuilder, State};
+
+/// Shards per available CPU when the count isn't configured.
+const SHARDS_PER_CPU: usize = 4;
+
+// *** SHARDED KEYED LIMITER ***
+/// Same as `KeyedLimiter`, but keys are spread by hash over independently locked shards so
+/// requests for different keys rarely wait on each other.
+pub struct ShardedKeyedLimiter<K, L> {
+    shards: Box<[Mutex<State<K, L>>]>,
+    hasher: RandomState,
+    factory: Factory<K, L>,
+    eviction: Eviction,
+}
+
+impl<K, L> ShardedKeyedLimiter<K, L>
+where
+    K: Eq + Hash + Clone,
+    L: RateLimiter,
+{
+    pub fn new<F>(factory: F) -> Self
+    where
+        F: Fn(&K) -> L + Send + Sync + 'static,
+    {
+        Self::with_factory(
+            Box::new(factory),
+            Eviction {
+                max_keys: None,
+                evict_idle: true,
+            },
+            None,
+        )
+    }
+
+    pub fn builder<F>(factory: F) -> KeyedLimiterBuilder<K, L>
+    where
+        F: Fn(&K) -> L + Send + Sync + 'static,
+    {
+        KeyedLimiterBuilder::new(factory)
+    }
+
+    pub(super) fn with_factory(
+        factory: Factory<K, L>,
+        eviction: Eviction,
+        shards: Option<usize>,
+    ) -> Self {
+        let shards = shards.unwrap_or_else(|| {
+            thread::available_parallelism().map_or(1, NonZeroUsize::get) * SHARDS_PER_CPU
+        });
+        // Every shard needs room for at least one key
+        let shards = eviction
+            .max_keys
+            .map_or(shards, |max_keys| shards.min(max_keys));
+
+        Self {
+            shards: (0..shards).map(|_| Mutex::new(State::new())).collect(),
+            hasher: RandomState::new(),
+            factory,
+            eviction,
+        }
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let state = self
+            .shard(key)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        state.contains_key(key)
+    }
+
+    /// Stops tracking `key`, its next request starts from a fresh limiter.
+    pub fn remove<Q>(&self, key: &Q) -> Option<L>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let mut state = self
+            .shard(key)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        state.remove(key)
+    }
+
+    /// Drops every key whose limiter is back at its idle state, one shard at a time.
+    pub fn retain_recent(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| {
+                shard
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .evict_idle()
+            })
+            .sum()
+    }
+
+    pub fn shrink_to_fit(&self) {
+        for shard in self.shards.iter() {
+            shard
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .shrink_to_fit();
+        }
+    }
+
+    pub fn clear(&self) {
+        for shard in self.shards.iter() {
+            shard.lock().unwrap_or_else(PoisonError::into_inner).clear();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().unwrap_or_else(PoisonError::into_inner).len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn shard_index<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        self.hasher.hash_one(key) as usize % self.shards.len()
+    }
+
+    fn shard<Q: Hash + ?Sized>(&self, key: &Q) -> &Mutex<State<K, L>> {
+        &self.shards[self.shard_index(key)]
+    }
+
+    /// Eviction for the shard at `index`, `max_keys` is split so the shares add up to it.
+    fn shard_eviction(&self, index: usize) -> Eviction {
+        let shards = self.shards.len();
+        Eviction {
+            max_keys: self
+                .eviction
+                .max_keys
+                .map(|max_keys| max_keys / shards + usize::from(index < max_keys % shards)),
+            ..self.eviction
+        }
+    }
+}
+
+impl<K, L, Q> KeyedRateLimiterShared<Q> for ShardedKeyedLimiter<K, L>
+where
+    K: Eq + Hash + Clone + Borrow<Q>,
+    L: RateLimiter,
+    Q: Hash + Eq + ToOwned<Owned = K> + ?Sized,
+{
+    fn try_acquire(&self, key: &Q, tokens: u32) -> bool {
+        let index = self.shard_index(key);
+        let mut state = self.shards[index]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        state
+            .limiter_mut(key, &self.factory, self.shard_eviction(index))
+            .try_acquire(tokens)
+    }
+
+    fn try_acquire_decision(&self, key: &Q, tokens: u32) -> Decision {
+        let index = self.shard_index(key);
+        let mut state = self.shards[index]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        state
+            .limiter_mut(key, &self.factory, self.shard_eviction(index))
+            .try_acquire_decision(tokens)
+    }
+
+    fn try_acquire_checked(&self, key: &Q, tokens: u32) -> Result<Decision, RateLimitError> {
+        let index = self.shard_index(key);
+        let mut state = self.shards[index]
+            .lock()
+            .map_err(|_| RateLimitError::PoisonedLock)?;
+        state
+            .limiter_mut(key, &self.factory, self.shard_eviction(index))
+            .try_acquire_checked(tokens)
+    }
+
+    fn get_limit(&self, key: &Q) -> u32 {
+        let mut state = self
+            .shard(key)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        state.inspect(key, &self.factory, |limiter| limiter.get_limit())
+    }
+
+    fn get_remaining(&self, key: &Q) -> u32 {
+        let mut state = self
+            .shard(key)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        state.inspect(key, &self.factory, |limiter| limiter.get_remaining())
+    }
+
+    fn get_used(&self, key: &Q) -> u32 {
+        let mut state = self
+            .shard(key)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        state.inspect(key, &self.factory, |limiter| limiter.get_used())
+    }
+
+    fn get_reset(&self, key: &Q) -> u64 {
+        let mut state = self
+            .shard(key)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        state.inspect(key, &self.factory, |limiter| limiter.get_reset())
+    }
+
+    fn get_reset_ms(&self, key: &Q) -> u64 {
+        let mut state = self
+            .shard(key)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        state.inspect(key, &self.factory, |limiter| limiter.get_reset_ms())
+    }
+
+    fn get_retry_after(&self, key: &Q, tokens: u32) -> Duration {
+        let mut state = self
+            .shard(key)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        state.inspect(key, &self.factory, |limiter| {
+            limiter.get_retry_after(tokens)
+        })
+    }
+}
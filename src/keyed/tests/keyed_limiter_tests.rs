@@ -0,0 +1,274 @@
+#[cfg(test)]
+mod sequential_tests {
+    use crate::clock::MockClock;
+    use crate::core::{KeyedRateLimiterShared, Rate, RateLimitError};
+    use crate::fixed_window_counter::FixedWindowCounter;
+    use crate::keyed::KeyedLimiter;
+    use crate::leaky_bucket::LeakyBucket;
+    use crate::token_bucket::TokenBucket;
+    use std::time::Duration;
+
+    #[test]
+    fn per_key_test() {
+        let clock = MockClock::new();
+        let factory_clock = clock.clone();
+        let limiter = KeyedLimiter::new(move |_: &String| {
+            TokenBucket::with_clock(3, 1, factory_clock.clone())
+        });
+        assert!(limiter.is_empty());
+
+        assert!(limiter.try_acquire("alice", 3));
+        assert!(!limiter.try_acquire("alice", 1));
+        assert!(limiter.try_acquire("bob", 1));
+        assert_eq!(limiter.len(), 2);
+
+        assert_eq!(limiter.get_limit("alice"), 3);
+        assert_eq!(limiter.get_remaining("alice"), 0);
+        assert_eq!(limiter.get_used("alice"), 3);
+        assert_eq!(limiter.get_remaining("bob"), 2);
+        assert_eq!(limiter.get_retry_after("alice", 2), Duration::from_secs(2));
+
+        // Queries don't start tracking unknown keys
+        assert_eq!(limiter.get_remaining("carol"), 3);
+        assert_eq!(limiter.get_retry_after("carol", 3), Duration::ZERO);
+        assert!(!limiter.contains_key("carol"));
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(limiter.get_remaining("alice"), 1);
+        assert!(limiter.try_acquire("alice", 1));
+
+        assert!(limiter.remove("alice").is_some());
+        assert_eq!(limiter.get_remaining("alice"), 3);
+        limiter.clear();
+        assert!(limiter.is_empty());
+    }
+
+    #[test]
+    fn factory_key_test() {
+        let limiter = KeyedLimiter::new(|tier: &u32| {
+            TokenBucket::builder()
+                .capacity(*tier * 10)
+                .rate(Rate::per_second(1))
+                .build()
+                .unwrap()
+        });
+        assert_eq!(limiter.get_limit(&1), 10);
+        assert_eq!(limiter.get_limit(&5), 50);
+        assert!(limiter.try_acquire(&5, 50));
+        assert!(!limiter.try_acquire(&1, 11));
+    }
+
+    #[test]
+    fn limiter_kinds_test() {
+        let clock = MockClock::new();
+        let leaky_clock = clock.clone();
+        let leaky = KeyedLimiter::new(move |_: &String| {
+            LeakyBucket::with_clock(2, 1.0, leaky_clock.clone())
+        });
+        assert!(leaky.try_acquire("key", 2));
+        assert!(!leaky.try_acquire("key", 1));
+        clock.advance(Duration::from_secs(1));
+        assert!(leaky.try_acquire("key", 1));
+
+        let window_clock = clock.clone();
+        let window = KeyedLimiter::new(move |_: &String| {
+            FixedWindowCounter::with_clock(2, 1, window_clock.clone())
+        });
+        assert!(window.try_acquire("key", 2));
+        assert!(!window.try_acquire("key", 1));
+        assert_eq!(window.get_used("key"), 2);
+    }
+
+    #[test]
+    fn checked_test() {
+        let limiter = KeyedLimiter::new(|_: &String| TokenBucket::new(2, 1));
+
+        let decision = limiter.try_acquire_decision("key", 2);
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 0);
+
+        assert!(matches!(
+            limiter.try_acquire_checked("key", 1),
+            Err(RateLimitError::Denied(_))
+        ));
+        assert!(matches!(
+            limiter.try_acquire_checked("other", 3),
+            Err(RateLimitError::InsufficientCapacity {
+                requested: 3,
+                capacity: 2
+            })
+        ));
+    }
+}
+
+#[cfg(test)]
+mod parallel_tests {
+    use crate::core::KeyedRateLimiterShared;
+    use crate::keyed::KeyedLimiter;
+    use crate::token_bucket::TokenBucket;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+
+    #[test]
+    fn race_condition_test() {
+        let limiter = Arc::new(KeyedLimiter::new(|_: &u32| TokenBucket::new(5, 1)));
+        let success_count = Arc::new(AtomicU32::new(0));
+        let barrier = Arc::new(Barrier::new(40));
+
+        let mut handles = vec![];
+        for i in 0..40 {
+            let limiter_clone = Arc::clone(&limiter);
+            let success_count_clone = Arc::clone(&success_count);
+            let barrier_clone = Arc::clone(&barrier);
+
+            handles.push(thread::spawn(move || {
+                barrier_clone.wait();
+                if limiter_clone.try_acquire(&(i % 4), 1) {
+                    success_count_clone.fetch_add(1, Ordering::SeqCst);
+                }
+            }));
+        }
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(limiter.len(), 4);
+        assert_eq!(success_count.load(Ordering::SeqCst), 4 * 5);
+    }
+}
+
+#[cfg(test)]
+mod eviction_tests {
+    use crate::clock::MockClock;
+    use crate::core::{KeyedRateLimiterShared, RateLimitError, RateLimiter};
+    use crate::gcra::Gcra;
+    use crate::keyed::KeyedLimiter;
+    use crate::leaky_bucket::LeakyBucket;
+    use crate::sliding_window_log::SlidingWindowLog;
+    use crate::token_bucket::TokenBucket;
+    use std::time::Duration;
+
+    #[test]
+    fn is_idle_test() {
+        let clock = MockClock::new();
+
+        let mut bucket = TokenBucket::with_clock(2, 1, clock.clone());
+        assert!(bucket.is_idle());
+        assert!(bucket.try_acquire(1));
+        assert!(!bucket.is_idle());
+
+        let mut leaky = LeakyBucket::with_clock(2, 1.0, clock.clone());
+        assert!(leaky.is_idle());
+        assert!(leaky.try_acquire(1));
+
+        let mut log = SlidingWindowLog::with_clock(2, 1, clock.clone());
+        assert!(log.is_idle());
+        assert!(log.try_acquire(1));
+
+        let mut gcra = Gcra::with_clock(
+            Duration::from_millis(300),
+            Duration::from_millis(600),
+            clock.clone(),
+        );
+        assert!(gcra.is_idle());
+        assert!(gcra.try_acquire(1));
+
+        clock.advance(Duration::from_millis(900));
+        bucket.refresh();
+        leaky.refresh();
+        log.refresh();
+        assert!(!bucket.is_idle());
+        // Less than one unit of water left still rounds to zero used
+        assert_eq!(leaky.get_used(), 0);
+        assert!(!leaky.is_idle());
+        assert!(!log.is_idle());
+        assert!(gcra.is_idle());
+
+        clock.advance(Duration::from_millis(100));
+        bucket.refresh();
+        leaky.refresh();
+        log.refresh();
+        assert!(bucket.is_idle());
+        assert!(leaky.is_idle());
+        assert!(log.is_idle());
+    }
+
+    #[test]
+    fn retain_recent_test() {
+        let clock = MockClock::new();
+        let factory_clock = clock.clone();
+        let limiter = KeyedLimiter::new(move |key: &u32| {
+            TokenBucket::with_clock(*key, 1, factory_clock.clone())
+        });
+        for key in 1..=10 {
+            assert!(limiter.try_acquire(&key, 1));
+        }
+        assert_eq!(limiter.retain_recent(), 0);
+
+        clock.advance(Duration::from_secs(1));
+        // Every bucket refilled its single token
+        assert_eq!(limiter.retain_recent(), 10);
+        assert!(limiter.is_empty());
+
+        assert!(limiter.try_acquire(&3, 3));
+        assert!(limiter.try_acquire(&5, 1));
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(limiter.retain_recent(), 1);
+        assert!(limiter.contains_key(&3));
+        assert_eq!(limiter.get_remaining(&3), 1);
+        limiter.shrink_to_fit();
+    }
+
+    #[test]
+    fn automatic_eviction_test() {
+        let clock = MockClock::new();
+        let factory_clock = clock.clone();
+        let limiter = KeyedLimiter::new(move |_: &u32| {
+            LeakyBucket::with_clock(1, 1.0, factory_clock.clone())
+        });
+        for key in 0..1000 {
+            assert!(limiter.try_acquire(&key, 1));
+            clock.advance(Duration::from_millis(100));
+        }
+        // Only keys touched within the last second can still hold water
+        assert!(limiter.len() < 200);
+        assert!(limiter.contains_key(&999));
+
+        let limiter = KeyedLimiter::builder(move |_: &u32| TokenBucket::new(1, 1))
+            .evict_idle(false)
+            .build()
+            .unwrap();
+        for key in 0..1000 {
+            limiter.try_acquire(&key, 0);
+        }
+        assert_eq!(limiter.len(), 1000);
+    }
+
+    #[test]
+    fn max_keys_test() {
+        let limiter = KeyedLimiter::builder(|_: &String| TokenBucket::new(5, 1))
+            .max_keys(2)
+            .build()
+            .unwrap();
+        assert!(limiter.try_acquire("a", 5));
+        assert!(limiter.try_acquire("b", 5));
+        assert!(!limiter.try_acquire("a", 1));
+
+        // "b" is the least recently used key now
+        assert!(limiter.try_acquire("c", 1));
+        assert_eq!(limiter.len(), 2);
+        assert!(limiter.contains_key("a"));
+        assert!(!limiter.contains_key("b"));
+        assert!(limiter.try_acquire("b", 5));
+        assert!(!limiter.contains_key("a"));
+
+        assert!(matches!(
+            KeyedLimiter::builder(|_: &String| TokenBucket::new(5, 1))
+                .max_keys(0)
+                .build(),
+            Err(RateLimitError::InvalidConfig(_))
+        ));
+    }
+}
@@ -0,0 +1,2 @@
+mod keyed_limiter_tests;
+mod sharded_keyed_limiter_tests;
@@ -0,0 +1,148 @@
+#[cfg(test)]
+mod sequential_tests {
+    use crate::clock::MockClock;
+    use crate::core::{KeyedRateLimiterShared, RateLimitError};
+    use crate::fixed_window_counter::FixedWindowCounter;
+    use crate::keyed::{KeyedLimiter, ShardedKeyedLimiter};
+    use crate::token_bucket::TokenBucket;
+    use std::time::Duration;
+
+    #[test]
+    fn per_key_test() {
+        let clock = MockClock::new();
+        let factory_clock = clock.clone();
+        let limiter = ShardedKeyedLimiter::builder(move |_: &String| {
+            TokenBucket::with_clock(3, 1, factory_clock.clone())
+        })
+        .shards(8)
+        .build_sharded()
+        .unwrap();
+        assert_eq!(limiter.shard_count(), 8);
+
+        for key in 0..100 {
+            assert!(limiter.try_acquire(format!("client-{key}").as_str(), 3));
+        }
+        assert_eq!(limiter.len(), 100);
+        assert!(!limiter.try_acquire("client-7", 1));
+        assert_eq!(limiter.get_remaining("client-7"), 0);
+        assert_eq!(
+            limiter.get_retry_after("client-7", 1),
+            Duration::from_secs(1)
+        );
+        assert_eq!(limiter.get_remaining("unknown"), 3);
+        assert!(!limiter.contains_key("unknown"));
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(limiter.retain_recent(), 0);
+        clock.advance(Duration::from_secs(2));
+        assert_eq!(limiter.retain_recent(), 100);
+        assert!(limiter.is_empty());
+    }
+
+    #[test]
+    fn max_keys_test() {
+        let limiter = ShardedKeyedLimiter::builder(|_: &u32| TokenBucket::new(1, 1))
+            .shards(4)
+            .max_keys(40)
+            .build_sharded()
+            .unwrap();
+        for key in 0..1000 {
+            assert!(limiter.try_acquire(&key, 1));
+        }
+        // Every shard holds at most its share of the cap
+        assert!(limiter.len() <= 40);
+        assert!(limiter.contains_key(&999));
+
+        // The shares add up to the cap even when it doesn't divide evenly or is below the
+        // shard count
+        for (shards, max_keys) in [(64, 10), (64, 100), (3, 10)] {
+            let limiter = ShardedKeyedLimiter::builder(|_: &u32| TokenBucket::new(1, 1))
+                .shards(shards)
+                .max_keys(max_keys)
+                .build_sharded()
+                .unwrap();
+            for key in 0..1000 {
+                assert!(limiter.try_acquire(&key, 1));
+            }
+            assert!(
+                limiter.len() <= max_keys,
+                "{shards} shards, {max_keys} keys"
+            );
+            assert!(limiter.shard_count() <= max_keys);
+        }
+
+        assert!(matches!(
+            ShardedKeyedLimiter::builder(|_: &u32| TokenBucket::new(1, 1))
+                .shards(0)
+                .build_sharded(),
+            Err(RateLimitError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn trait_object_test() {
+        let clock = MockClock::new();
+        let first_clock = clock.clone();
+        let second_clock = clock.clone();
+        let limiters: Vec<Box<dyn KeyedRateLimiterShared<str>>> = vec![
+            Box::new(KeyedLimiter::new(move |_: &String| {
+                FixedWindowCounter::with_clock(2, 1, first_clock.clone())
+            })),
+            Box::new(ShardedKeyedLimiter::new(move |_: &String| {
+                FixedWindowCounter::with_clock(2, 1, second_clock.clone())
+            })),
+        ];
+
+        for limiter in &limiters {
+            assert!(limiter.try_acquire("key", 2));
+            let decision = limiter.try_acquire_decision("key", 1);
+            assert!(!decision.allowed);
+            assert_eq!(decision.limit, 2);
+            assert!(matches!(
+                limiter.try_acquire_checked("key", 3),
+                Err(RateLimitError::InsufficientCapacity { .. })
+            ));
+            assert!(limiter.try_acquire("other", 1));
+        }
+    }
+}
+
+#[cfg(test)]
+mod parallel_tests {
+    use crate::core::KeyedRateLimiterShared;
+    use crate::keyed::ShardedKeyedLimiter;
+    use crate::token_bucket::TokenBucket;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+
+    #[test]
+    fn race_condition_test() {
+        let limiter = Arc::new(ShardedKeyedLimiter::new(|_: &u32| TokenBucket::new(5, 1)));
+        let success_count = Arc::new(AtomicU32::new(0));
+        let barrier = Arc::new(Barrier::new(16));
+
+        let mut handles = vec![];
+        for _ in 0..16 {
+            let limiter_clone = Arc::clone(&limiter);
+            let success_count_clone = Arc::clone(&success_count);
+            let barrier_clone = Arc::clone(&barrier);
+
+            handles.push(thread::spawn(move || {
+                barrier_clone.wait();
+                for key in 0..50 {
+                    if limiter_clone.try_acquire(&key, 1) {
+                        success_count_clone.fetch_add(1, Ordering::SeqCst);
+                    }
+                }
+            }));
+        }
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(limiter.len(), 50);
+        assert_eq!(success_count.load(Ordering::SeqCst), 50 * 5);
+    }
+}
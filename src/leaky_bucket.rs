@@ -1,4 +1,7 @@
 pub mod r#impl;
+pub mod state;
 pub mod tests;
 
-pub use r#impl::{LeakyBucket, LeakyBucketShared};
+pub use crate::core::{Decision, RateLimitError, RateLimiter, RateLimiterShared};
+pub use r#impl::{LeakyBucket, LeakyBucketBuilder, LeakyBucketShared};
+pub use state::{LeakyBucketAlgorithm, LeakyBucketState};
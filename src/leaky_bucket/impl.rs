@@ -1,110 +1,269 @@
-use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant, UNIX_EPOCH};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
 
-use crate::token_bucket::r#impl::{RateLimiter, RateLimiterShared};
+use crate::clock::{Clock, Epoch, SystemClock};
+use crate::core::{
+    as_millis, unix_time_after, Decision, RateLimitError, RateLimiter, RateLimiterShared,
+};
+use crate::leaky_bucket::{LeakyBucketAlgorithm, LeakyBucketState};
+use crate::store::Algorithm;
 
 // *** LEAKY BUCKET ***
-pub struct LeakyBucket {
-    capacity: u32,
-    leak_rate: f64,
-    water: f64,
-    last_check: Instant,
+pub struct LeakyBucket<C = SystemClock> {
+    pub(crate) algorithm: LeakyBucketAlgorithm,
+    pub(crate) state: LeakyBucketState,
+    epoch: Epoch,
+    pub(crate) clock: C,
 }
 
 impl LeakyBucket {
     pub fn new(capacity: u32, leak_rate: f64) -> Self {
+        Self::with_clock(capacity, leak_rate, SystemClock)
+    }
+
+    pub fn try_new(capacity: u32, leak_rate: f64) -> Result<Self, RateLimitError> {
+        Self::builder()
+            .capacity(capacity)
+            .leak_rate(leak_rate)
+            .build()
+    }
+
+    pub fn builder() -> LeakyBucketBuilder {
+        LeakyBucketBuilder::new()
+    }
+}
+
+impl<C: Clock> LeakyBucket<C> {
+    pub fn with_clock(capacity: u32, leak_rate: f64, clock: C) -> Self {
+        let algorithm = LeakyBucketAlgorithm::new(capacity, leak_rate);
+        let epoch = Epoch::new(&clock);
         Self {
-            capacity,
-            leak_rate,
-            water: 0.0,
-            last_check: Instant::now(),
+            algorithm,
+            state: algorithm.initial_state(epoch.now(&clock)),
+            epoch,
+            clock,
         }
     }
+
+    pub(crate) fn now(&self) -> Duration {
+        self.epoch.now(&self.clock)
+    }
+
+    /// Time until the bucket is empty again.
+    fn reset_after(&self) -> Duration {
+        self.algorithm.reset_after(&self.state, self.now())
+    }
 }
 
-impl RateLimiter for LeakyBucket {
-    fn refresh(&mut self) {
-        let now = Instant::now();
-        let elapsed = now.duration_since(self.last_check).as_secs_f64();
-        let leaked = elapsed * self.leak_rate;
+// *** LEAKY BUCKET BUILDER ***
+pub struct LeakyBucketBuilder<C = SystemClock> {
+    capacity: u32,
+    leak_rate: f64,
+    initial_level: f64,
+    clock: C,
+}
 
-        if leaked > 0.0 {
-            self.water = (self.water - leaked).max(0.0);
-            self.last_check = now;
+impl LeakyBucketBuilder {
+    pub fn new() -> Self {
+        Self {
+            capacity: 0,
+            leak_rate: 0.0,
+            initial_level: 0.0,
+            clock: SystemClock,
         }
     }
+}
 
-    fn try_acquire(&mut self, amount: u32) -> bool {
-        self.refresh();
-        if self.water + amount as f64 <= self.capacity as f64 {
-            self.water += amount as f64;
-            true
-        } else {
-            false
+impl Default for LeakyBucketBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Clock> LeakyBucketBuilder<C> {
+    pub fn capacity(mut self, capacity: u32) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Same as `capacity`: the largest amount that can be poured in at once.
+    pub fn burst(self, burst: u32) -> Self {
+        self.capacity(burst)
+    }
+
+    pub fn leak_rate(mut self, leak_rate: f64) -> Self {
+        self.leak_rate = leak_rate;
+        self
+    }
+
+    /// Water in the bucket right after creation. Defaults to an empty bucket.
+    pub fn initial_level(mut self, level: f64) -> Self {
+        self.initial_level = level;
+        self
+    }
+
+    pub fn clock<D: Clock>(self, clock: D) -> LeakyBucketBuilder<D> {
+        LeakyBucketBuilder {
+            capacity: self.capacity,
+            leak_rate: self.leak_rate,
+            initial_level: self.initial_level,
+            clock,
+        }
+    }
+
+    pub fn build(self) -> Result<LeakyBucket<C>, RateLimitError> {
+        if self.capacity == 0 {
+            return Err(RateLimitError::InvalidConfig(
+                "leaky bucket capacity must be greater than zero".to_string(),
+            ));
+        }
+        if !self.leak_rate.is_finite() || self.leak_rate <= 0.0 {
+            return Err(RateLimitError::InvalidConfig(format!(
+                "leaky bucket leak rate must be a positive number, got {}",
+                self.leak_rate
+            )));
         }
+        if !(0.0..=self.capacity as f64).contains(&self.initial_level) {
+            return Err(RateLimitError::InvalidConfig(format!(
+                "leaky bucket initial level must be between 0 and capacity ({}), got {}",
+                self.capacity, self.initial_level
+            )));
+        }
+
+        let mut bucket = LeakyBucket::with_clock(self.capacity, self.leak_rate, self.clock);
+        bucket.state.water = self.initial_level;
+        Ok(bucket)
+    }
+
+    pub fn build_shared(self) -> Result<LeakyBucketShared<C>, RateLimitError> {
+        self.build().map(LeakyBucketShared::from)
+    }
+}
+
+impl<C: Clock> RateLimiter for LeakyBucket<C> {
+    fn refresh(&mut self) {
+        let now = self.now();
+        self.algorithm.refresh(&mut self.state, now);
+    }
+
+    fn try_acquire(&mut self, amount: u32) -> bool {
+        let now = self.now();
+        self.algorithm.try_acquire(&mut self.state, now, amount)
     }
 
     fn get_limit(&self) -> u32 {
-        self.capacity
+        self.algorithm.limit()
     }
 
     fn get_remaining(&self) -> u32 {
-        (self.capacity as f64 - self.water.round()) as u32
+        self.algorithm.remaining(&self.state, self.now())
     }
 
     fn get_used(&self) -> u32 {
-        self.water.round() as u32
+        self.algorithm.used(&self.state, self.now())
+    }
+
+    fn is_idle(&self) -> bool {
+        self.algorithm.is_idle(&self.state, self.now())
     }
 
     fn get_reset(&self) -> u64 {
-        let now = std::time::SystemTime::now();
-        let seconds = self.water / self.leak_rate;
-        let reset_time = now + Duration::from_secs_f64(seconds);
-        reset_time.duration_since(UNIX_EPOCH).unwrap().as_secs()
+        unix_time_after(self.clock.now_system(), self.reset_after()).as_secs()
+    }
+
+    fn get_reset_ms(&self) -> u64 {
+        as_millis(unix_time_after(self.clock.now_system(), self.reset_after()))
+    }
+
+    fn get_retry_after(&self, amount: u32) -> Duration {
+        self.algorithm.retry_after(&self.state, self.now(), amount)
     }
 }
 
 // *** LEAKY BUCKET SHARED ***
-pub struct LeakyBucketShared {
-    inner: Arc<Mutex<LeakyBucket>>,
+pub struct LeakyBucketShared<C = SystemClock> {
+    pub(crate) inner: Arc<Mutex<LeakyBucket<C>>>,
 }
 
 impl LeakyBucketShared {
     pub fn new(capacity: u32, leak_rate: f64) -> Self {
+        Self::with_clock(capacity, leak_rate, SystemClock)
+    }
+
+    pub fn try_new(capacity: u32, leak_rate: f64) -> Result<Self, RateLimitError> {
+        LeakyBucket::try_new(capacity, leak_rate).map(Self::from)
+    }
+}
+
+impl<C: Clock> LeakyBucketShared<C> {
+    pub fn with_clock(capacity: u32, leak_rate: f64, clock: C) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(LeakyBucket::with_clock(
+                capacity, leak_rate, clock,
+            ))),
+        }
+    }
+}
+
+impl<C> From<LeakyBucket<C>> for LeakyBucketShared<C> {
+    fn from(bucket: LeakyBucket<C>) -> Self {
         Self {
-            inner: Arc::new(Mutex::new(LeakyBucket::new(capacity, leak_rate))),
+            inner: Arc::new(Mutex::new(bucket)),
         }
     }
 }
 
-impl RateLimiterShared for LeakyBucketShared {
+impl<C: Clock> RateLimiterShared for LeakyBucketShared<C> {
     fn refresh(&self) {
-        let mut bucket = self.inner.lock().unwrap();
+        let mut bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         bucket.refresh()
     }
 
     fn try_acquire(&self, amount: u32) -> bool {
-        let mut bucket = self.inner.lock().unwrap();
+        let mut bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         bucket.try_acquire(amount)
     }
 
+    fn try_acquire_decision(&self, amount: u32) -> Decision {
+        let mut bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        bucket.try_acquire_decision(amount)
+    }
+
+    fn try_acquire_checked(&self, amount: u32) -> Result<Decision, RateLimitError> {
+        let mut bucket = self
+            .inner
+            .lock()
+            .map_err(|_| RateLimitError::PoisonedLock)?;
+        bucket.try_acquire_checked(amount)
+    }
+
     fn get_limit(&self) -> u32 {
-        let bucket = self.inner.lock().unwrap();
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         bucket.get_limit()
     }
 
     fn get_remaining(&self) -> u32 {
-        let bucket = self.inner.lock().unwrap();
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         bucket.get_remaining()
     }
 
     fn get_used(&self) -> u32 {
-        let bucket = self.inner.lock().unwrap();
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         bucket.get_used()
     }
 
     fn get_reset(&self) -> u64 {
-        let bucket = self.inner.lock().unwrap();
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         bucket.get_reset()
     }
+
+    fn get_reset_ms(&self) -> u64 {
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        bucket.get_reset_ms()
+    }
+
+    fn get_retry_after(&self, amount: u32) -> Duration {
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        bucket.get_retry_after(amount)
+    }
 }
@@ -0,0 +1,165 @@
+use std::time::Duration;
+
+use crate::store::r#impl::{Algorithm, StateReader, StateWriter};
+
+const TAG: u8 = 2;
+
+// *** LEAKY BUCKET STATE ***
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct LeakyBucketState {
+    pub water: f64,
+    pub last_check: Duration,
+}
+
+// *** LEAKY BUCKET ALGORITHM ***
+/// Leaky bucket transitions, see `LeakyBucket` for a limiter holding its own state.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LeakyBucketAlgorithm {
+    capacity: u32,
+    leak_rate: f64,
+}
+
+impl LeakyBucketAlgorithm {
+    pub fn new(capacity: u32, leak_rate: f64) -> Self {
+        Self {
+            capacity,
+            leak_rate,
+        }
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    pub fn leak_rate(&self) -> f64 {
+        self.leak_rate
+    }
+
+    /// Seconds of leaking since the last check, not yet applied to `water`.
+    fn pending_leak_secs(&self, state: &LeakyBucketState, now: Duration) -> f64 {
+        now.saturating_sub(state.last_check).as_secs_f64()
+    }
+
+    /// Shortest wait after which `amount` fits, starting from the estimate `seconds`. Float
+    /// rounding can leave the estimate a hair short, so it is checked against `try_acquire`.
+    fn wait_until_fits(
+        &self,
+        state: &LeakyBucketState,
+        now: Duration,
+        amount: u32,
+        seconds: f64,
+    ) -> Duration {
+        let mut wait = ceil_secs(seconds);
+        let mut step = Duration::from_nanos(1);
+        loop {
+            let mut probe = *state;
+            if wait == Duration::MAX
+                || self.try_acquire(&mut probe, now.saturating_add(wait), amount)
+            {
+                return wait;
+            }
+            wait = wait.saturating_add(step);
+            step = step.saturating_mul(2);
+        }
+    }
+}
+
+/// `seconds` rounded up to the next nanosecond.
+fn ceil_secs(seconds: f64) -> Duration {
+    let nanos = (seconds.max(0.0) * 1e9).ceil();
+    if nanos < u64::MAX as f64 {
+        Duration::from_nanos(nanos as u64)
+    } else {
+        Duration::MAX
+    }
+}
+
+impl Algorithm for LeakyBucketAlgorithm {
+    type State = LeakyBucketState;
+
+    fn limit(&self) -> u32 {
+        self.capacity
+    }
+
+    fn initial_state(&self, now: Duration) -> LeakyBucketState {
+        LeakyBucketState {
+            water: 0.0,
+            last_check: now,
+        }
+    }
+
+    fn refresh(&self, state: &mut LeakyBucketState, now: Duration) {
+        let leaked = self.pending_leak_secs(state, now) * self.leak_rate;
+
+        if leaked > 0.0 {
+            state.water = (state.water - leaked).max(0.0);
+            state.last_check = now;
+        }
+    }
+
+    fn try_acquire(&self, state: &mut LeakyBucketState, now: Duration, amount: u32) -> bool {
+        self.refresh(state, now);
+        if state.water + amount as f64 <= self.capacity as f64 {
+            state.water += amount as f64;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn remaining(&self, state: &LeakyBucketState, _now: Duration) -> u32 {
+        (self.capacity as f64 - state.water.round()) as u32
+    }
+
+    fn used(&self, state: &LeakyBucketState, _now: Duration) -> u32 {
+        state.water.round() as u32
+    }
+
+    fn is_idle(&self, state: &LeakyBucketState, _now: Duration) -> bool {
+        state.water <= 0.0
+    }
+
+    fn reset_after(&self, state: &LeakyBucketState, now: Duration) -> Duration {
+        if state.water <= 0.0 {
+            return Duration::ZERO;
+        }
+        if self.leak_rate <= 0.0 {
+            return Duration::MAX;
+        }
+        let seconds = state.water / self.leak_rate - self.pending_leak_secs(state, now);
+        ceil_secs(seconds)
+    }
+
+    fn retry_after(&self, state: &LeakyBucketState, now: Duration, amount: u32) -> Duration {
+        if amount > self.capacity || self.leak_rate <= 0.0 {
+            return Duration::MAX;
+        }
+        // Measured from the water level a refresh at `now` would leave
+        let mut refreshed = *state;
+        self.refresh(&mut refreshed, now);
+        let overflow = refreshed.water + amount as f64 - self.capacity as f64;
+        if overflow <= 0.0 {
+            return Duration::ZERO;
+        }
+
+        self.wait_until_fits(state, now, amount, overflow / self.leak_rate)
+    }
+
+    fn encode(&self, state: &LeakyBucketState) -> Vec<u8> {
+        StateWriter::new(TAG)
+            .f64(state.water)
+            .duration(state.last_check)
+            .finish()
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Option<LeakyBucketState> {
+        let mut reader = StateReader::new(bytes, TAG)?;
+        let state = LeakyBucketState {
+            water: reader.f64()?,
+            last_check: reader.duration()?,
+        };
+        let valid = (0.0..=self.capacity as f64).contains(&state.water) && reader.is_empty();
+        valid.then_some(state)
+    }
+}
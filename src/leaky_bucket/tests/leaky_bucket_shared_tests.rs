@@ -1,7 +1,7 @@
 #[cfg(test)]
 mod sequential_tests {
+    use crate::core::RateLimiterShared;
     use crate::leaky_bucket::LeakyBucketShared;
-    use crate::token_bucket::r#impl::RateLimiterShared;
     use std::thread;
     use std::time::Duration;
 
@@ -55,8 +55,8 @@ mod sequential_tests {
 
 #[cfg(test)]
 mod parallel_tests {
+    use crate::core::RateLimiterShared;
     use crate::leaky_bucket::LeakyBucketShared;
-    use crate::token_bucket::r#impl::RateLimiterShared;
     use std::sync::atomic::{AtomicU32, Ordering};
     use std::sync::{Arc, Barrier};
     use std::thread;
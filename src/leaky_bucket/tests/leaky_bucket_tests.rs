@@ -3,8 +3,9 @@ mod sequential_tests {
     use std::thread;
     use std::time::Duration;
 
+    use crate::clock::MockClock;
+    use crate::core::{RateLimitError, RateLimiter};
     use crate::leaky_bucket::LeakyBucket;
-    use crate::token_bucket::r#impl::RateLimiter;
 
     #[test]
     fn basic_test() {
@@ -52,4 +53,109 @@ mod sequential_tests {
         assert_eq!(bucket.get_remaining(), 2);
         assert_eq!(bucket.get_used(), 8);
     }
+
+    #[test]
+    fn decision_test() {
+        let mut bucket = LeakyBucket::new(10, 2.0);
+
+        let decision = bucket.try_acquire_decision(8);
+        assert!(decision.allowed);
+        assert_eq!(decision.limit, 10);
+        assert_eq!(decision.remaining, 2);
+        assert_eq!(decision.retry_after, Duration::ZERO);
+
+        let decision = bucket.try_acquire_decision(4);
+        assert!(!decision.allowed);
+        assert_eq!(decision.remaining, 2);
+        assert!(decision.retry_after >= Duration::from_millis(900));
+        assert!(decision.retry_after <= Duration::from_secs(1));
+
+        let decision = bucket.try_acquire_decision(11);
+        assert!(!decision.allowed);
+        assert_eq!(decision.retry_after, Duration::MAX);
+    }
+
+    #[test]
+    fn mock_clock_test() {
+        let clock = MockClock::new();
+        let mut bucket = LeakyBucket::with_clock(10, 2.0, clock.clone());
+
+        assert!(bucket.try_acquire(10));
+        assert!(!bucket.try_acquire(1));
+
+        clock.advance(Duration::from_millis(500));
+        assert!(bucket.try_acquire(1));
+        assert!(!bucket.try_acquire(1));
+
+        clock.advance(Duration::from_secs(10));
+        bucket.refresh();
+        assert_eq!(bucket.get_remaining(), 10);
+        assert_eq!(bucket.get_used(), 0);
+    }
+
+    #[test]
+    fn builder_test() {
+        for leak_rate in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                LeakyBucket::try_new(10, leak_rate),
+                Err(RateLimitError::InvalidConfig(_))
+            ));
+        }
+        assert!(matches!(
+            LeakyBucket::try_new(0, 1.0),
+            Err(RateLimitError::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            LeakyBucket::builder()
+                .capacity(10)
+                .leak_rate(1.0)
+                .initial_level(10.5)
+                .build(),
+            Err(RateLimitError::InvalidConfig(_))
+        ));
+
+        let mut bucket = LeakyBucket::builder()
+            .capacity(10)
+            .leak_rate(1.0)
+            .initial_level(7.0)
+            .build()
+            .unwrap();
+        assert_eq!(bucket.get_limit(), 10);
+        assert_eq!(bucket.get_used(), 7);
+        assert!(bucket.try_acquire(3));
+        assert!(!bucket.try_acquire(1));
+    }
+
+    #[test]
+    fn retry_after_is_enough_test() {
+        // Deterministic spread of rates, fill levels and request sizes
+        let mut seed = 0x2545_f491_4f6c_dd1du64;
+        let mut next = |bound: u64| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed % bound
+        };
+
+        for _ in 0..2000 {
+            let clock = MockClock::new();
+            let capacity = 1 + next(20) as u32;
+            let leak_rate = (1 + next(5000)) as f64 / 1000.0;
+            let mut bucket = LeakyBucket::with_clock(capacity, leak_rate, clock.clone());
+            assert!(bucket.try_acquire(1 + next(capacity as u64) as u32));
+            clock.advance(Duration::from_nanos(next(2_000_000_000)));
+
+            let amount = 1 + next(capacity as u64) as u32;
+            let decision = bucket.try_acquire_decision(amount);
+            if decision.allowed {
+                continue;
+            }
+            clock.advance(decision.retry_after);
+            assert!(
+                bucket.try_acquire(amount),
+                "capacity {capacity}, leak rate {leak_rate}, amount {amount}, waited {:?}",
+                decision.retry_after
+            );
+        }
+    }
 }
@@ -1,5 +1,33 @@
+// Tests spell out range bounds as comparisons
+#![cfg_attr(test, allow(clippy::manual_range_contains))]
+
+#[cfg(feature = "actix")]
+pub mod actix;
+pub mod asynchronous;
+#[cfg(feature = "axum")]
+pub mod axum;
+#[cfg(feature = "client")]
+pub mod client;
+pub mod clock;
+pub mod core;
 pub mod fixed_window_counter;
+pub mod gcra;
+pub mod headers;
+pub mod keyed;
 pub mod leaky_bucket;
+#[cfg(feature = "redis")]
+pub mod redis;
+#[cfg(feature = "serde")]
+pub mod serde;
 pub mod sliding_window_counter;
 pub mod sliding_window_log;
+pub mod store;
 pub mod token_bucket;
+#[cfg(feature = "tonic")]
+pub mod tonic;
+#[cfg(feature = "tower")]
+pub mod tower;
+
+pub use crate::core::{
+    Decision, KeyedRateLimiterShared, Rate, RateLimitError, RateLimiter, RateLimiterShared,
+};
@@ -0,0 +1,9 @@
+pub mod r#impl;
+pub mod memory;
+pub mod tests;
+
+pub use crate::core::{KeyedRateLimiterShared, RateLimitError, RateLimiterShared};
+pub use memory::MemoryExecutor;
+pub use r#impl::{
+    RedisExecutor, RedisKeyedLimiter, RedisLimiter, Script, ScriptBackend, ScriptExecutor,
+};
@@ -0,0 +1,269 @@
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use crate::core::{Rate, RateLimitError};
+use crate::store::{Backend, Outcome, RemoteKeyedLimiter, RemoteLimiter};
+
+// *** SCRIPT ***
+/// Lua scripts that update the limiter state atomically on the server. Every script takes a
+/// single key, reads the time from Redis `TIME` so replicas with skewed clocks agree, and
+/// replies with `[allowed, remaining, retry_after_us, reset_after_us]` where a negative
+/// `retry_after_us` means the request can never pass.
+///
+/// The scripts write after calling `TIME`, which needs script effects replication, the default
+/// since Redis 5.0, so older servers are not supported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Script {
+    /// Args: capacity, rate tokens, rate period in µs, requested tokens, dry run.
+    TokenBucket,
+    /// Args: emission interval in µs, max delay in µs, requested tokens, dry run.
+    Gcra,
+    /// Args: limit, window in µs, requested tokens, dry run.
+    FixedWindow,
+}
+
+impl Script {
+    pub const ALL: [Script; 3] = [Script::TokenBucket, Script::Gcra, Script::FixedWindow];
+
+    pub fn source(&self) -> &'static str {
+        match self {
+            Self::TokenBucket => include_str!("scripts/token_bucket.lua"),
+            Self::Gcra => include_str!("scripts/gcra.lua"),
+            Self::FixedWindow => include_str!("scripts/fixed_window.lua"),
+        }
+    }
+}
+
+// *** SCRIPT EXECUTOR ***
+/// Runs a `Script` atomically against the store, e.g. `RedisExecutor` or the in-memory
+/// `MemoryExecutor` for tests.
+pub trait ScriptExecutor {
+    fn eval(&self, script: Script, key: &str, args: &[u64]) -> Result<Vec<i64>, RateLimitError>;
+}
+
+impl<E: ScriptExecutor + ?Sized> ScriptExecutor for &E {
+    fn eval(&self, script: Script, key: &str, args: &[u64]) -> Result<Vec<i64>, RateLimitError> {
+        (**self).eval(script, key, args)
+    }
+}
+
+impl<E: ScriptExecutor + ?Sized> ScriptExecutor for std::sync::Arc<E> {
+    fn eval(&self, script: Script, key: &str, args: &[u64]) -> Result<Vec<i64>, RateLimitError> {
+        (**self).eval(script, key, args)
+    }
+}
+
+// *** REDIS EXECUTOR ***
+/// Runs the scripts over a single connection, reconnecting after a failure. Scripts are sent
+/// with `EVALSHA` and loaded on the first `NOSCRIPT` reply.
+pub struct RedisExecutor {
+    client: redis::Client,
+    connection: Mutex<Option<redis::Connection>>,
+    scripts: [redis::Script; 3],
+}
+
+impl RedisExecutor {
+    pub fn new(client: redis::Client) -> Self {
+        Self {
+            client,
+            connection: Mutex::new(None),
+            scripts: Script::ALL.map(|script| redis::Script::new(script.source())),
+        }
+    }
+
+    pub fn open(url: &str) -> Result<Self, RateLimitError> {
+        redis::Client::open(url).map(Self::new).map_err(backend)
+    }
+}
+
+impl ScriptExecutor for RedisExecutor {
+    fn eval(&self, script: Script, key: &str, args: &[u64]) -> Result<Vec<i64>, RateLimitError> {
+        let mut connection = self
+            .connection
+            .lock()
+            .map_err(|_| RateLimitError::PoisonedLock)?;
+        if connection.is_none() {
+            *connection = Some(self.client.get_connection().map_err(backend)?);
+        }
+
+        let mut invocation = self.scripts[script as usize].key(key);
+        for arg in args {
+            invocation.arg(*arg);
+        }
+        let result = invocation.invoke(connection.as_mut().unwrap());
+        if result.is_err() {
+            // The connection may be broken, the next call opens a new one
+            *connection = None;
+        }
+        result.map_err(backend)
+    }
+}
+
+fn backend(err: redis::RedisError) -> RateLimitError {
+    RateLimitError::Backend(err.to_string())
+}
+
+// *** REDIS LIMITER ***
+/// A limiter whose state lives in Redis under `prefix`, so every process sharing the store
+/// enforces the same limit. See `keyed` for a limit per client.
+///
+/// When the store fails, requests are denied unless `with_fail_open(true)` is set, and
+/// `try_acquire_checked` reports the failure as `RateLimitError::Backend` in either case.
+pub type RedisLimiter<E> = RemoteLimiter<ScriptBackend<E>>;
+
+/// A `RedisLimiter` keeping separate state per key under `{prefix}:{key}`, e.g. per client.
+pub type RedisKeyedLimiter<E> = RemoteKeyedLimiter<ScriptBackend<E>>;
+
+impl<E: ScriptExecutor> RedisLimiter<E> {
+    /// Token bucket holding up to `capacity` tokens, refilled at `rate`.
+    pub fn token_bucket(
+        executor: E,
+        prefix: impl Into<String>,
+        capacity: u32,
+        rate: Rate,
+    ) -> Result<Self, RateLimitError> {
+        if capacity == 0 {
+            return Err(RateLimitError::InvalidConfig(
+                "token bucket capacity must be greater than zero".to_string(),
+            ));
+        }
+        let period = micros(rate.period());
+        if rate.tokens() == 0 || period == 0 {
+            return Err(RateLimitError::InvalidConfig(format!(
+                "token bucket refill rate must be greater than zero, got {} per {:?}",
+                rate.tokens(),
+                rate.period()
+            )));
+        }
+
+        let args = vec![u64::from(capacity), u64::from(rate.tokens()), period];
+        Ok(Self::with_script(
+            executor,
+            prefix,
+            Script::TokenBucket,
+            args,
+            capacity,
+        ))
+    }
+
+    /// GCRA with one token per `emission_interval` and bursts allowed within `burst_tolerance`.
+    pub fn gcra(
+        executor: E,
+        prefix: impl Into<String>,
+        emission_interval: Duration,
+        burst_tolerance: Duration,
+    ) -> Result<Self, RateLimitError> {
+        let interval = micros(emission_interval);
+        if interval == 0 {
+            return Err(RateLimitError::InvalidConfig(
+                "gcra emission interval must be at least one microsecond".to_string(),
+            ));
+        }
+
+        let max_delay = interval.saturating_add(micros(burst_tolerance));
+        let limit = u32::try_from(max_delay / interval).unwrap_or(u32::MAX);
+        let args = vec![interval, max_delay];
+        Ok(Self::with_script(
+            executor,
+            prefix,
+            Script::Gcra,
+            args,
+            limit,
+        ))
+    }
+
+    /// Fixed window allowing `limit` tokens per `window`, which starts on the first request.
+    pub fn fixed_window(
+        executor: E,
+        prefix: impl Into<String>,
+        limit: u32,
+        window: Duration,
+    ) -> Result<Self, RateLimitError> {
+        if limit == 0 {
+            return Err(RateLimitError::InvalidConfig(
+                "fixed window limit must be greater than zero".to_string(),
+            ));
+        }
+        let window = micros(window);
+        if window == 0 {
+            return Err(RateLimitError::InvalidConfig(
+                "fixed window length must be greater than zero".to_string(),
+            ));
+        }
+
+        let args = vec![u64::from(limit), window];
+        Ok(Self::with_script(
+            executor,
+            prefix,
+            Script::FixedWindow,
+            args,
+            limit,
+        ))
+    }
+
+    fn with_script(
+        executor: E,
+        prefix: impl Into<String>,
+        script: Script,
+        args: Vec<u64>,
+        limit: u32,
+    ) -> Self {
+        let backend = ScriptBackend {
+            executor,
+            script,
+            args,
+            limit,
+        };
+        Self::with_backend(backend, prefix)
+    }
+
+    pub fn executor(&self) -> &E {
+        &self.backend().executor
+    }
+}
+
+// *** SCRIPT BACKEND ***
+/// The `Backend` of a `RedisLimiter`, running one `Script` on the server.
+pub struct ScriptBackend<E> {
+    executor: E,
+    script: Script,
+    /// Script arguments before the requested tokens and dry run flag.
+    args: Vec<u64>,
+    limit: u32,
+}
+
+impl<E: ScriptExecutor> Backend for ScriptBackend<E> {
+    fn limit(&self) -> u32 {
+        self.limit
+    }
+
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn run(&self, key: &str, tokens: u32, dry_run: bool) -> Result<Outcome, RateLimitError> {
+        let mut args = self.args.clone();
+        args.extend([u64::from(tokens), u64::from(dry_run)]);
+
+        let reply = self.executor.eval(self.script, key, &args)?;
+        let &[allowed, remaining, retry_after, reset_after] = reply.as_slice() else {
+            return Err(RateLimitError::Backend(format!(
+                "unexpected script reply {reply:?}"
+            )));
+        };
+        Ok(Outcome {
+            allowed: allowed == 1,
+            remaining: u32::try_from(remaining.max(0)).unwrap_or(u32::MAX),
+            retry_after: match u64::try_from(retry_after) {
+                Ok(retry_after) => Duration::from_micros(retry_after),
+                Err(_) => Duration::MAX,
+            },
+            reset_after: Duration::from_micros(reset_after.max(0) as u64),
+        })
+    }
+}
+
+/// Whole microseconds, saturating.
+pub(crate) fn micros(duration: Duration) -> u64 {
+    duration.as_micros().min(u64::MAX as u128) as u64
+}
@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+use std::time::UNIX_EPOCH;
+
+use crate::clock::{Clock, SystemClock};
+use crate::core::RateLimitError;
+use crate::redis::r#impl::{micros, Script, ScriptExecutor};
+
+// *** MEMORY EXECUTOR ***
+/// In-memory stand-in for a Redis server that re-implements each `Script` in Rust instead of
+/// running its Lua source, so it is not the production path. Useful to test code built on
+/// `RedisLimiter` without a server, the scripts themselves are tested against a real Redis 5.0
+/// or newer with `cargo test --features redis -- --ignored redis_server`.
+pub struct MemoryExecutor<C = SystemClock> {
+    entries: Mutex<HashMap<String, Entry>>,
+    clock: C,
+}
+
+/// Values of a key and the time it expires at, in µs since UNIX epoch.
+struct Entry {
+    values: [i64; 3],
+    expires_at: i64,
+}
+
+impl MemoryExecutor {
+    pub fn new() -> Self {
+        Self::with_clock(SystemClock)
+    }
+}
+
+impl Default for MemoryExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Clock> MemoryExecutor<C> {
+    pub fn with_clock(clock: C) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            clock,
+        }
+    }
+
+    /// Whether `key` holds state that has not expired yet.
+    pub fn contains_key(&self, key: &str) -> bool {
+        let now = self.now();
+        let entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        entries.get(key).is_some_and(|entry| entry.expires_at > now)
+    }
+
+    fn now(&self) -> i64 {
+        let now = self.clock.now_system().duration_since(UNIX_EPOCH);
+        now.map_or(0, |now| micros(now) as i64)
+    }
+}
+
+impl<C: Clock> ScriptExecutor for MemoryExecutor<C> {
+    fn eval(&self, script: Script, key: &str, args: &[u64]) -> Result<Vec<i64>, RateLimitError> {
+        let expected = match script {
+            Script::TokenBucket => 5,
+            Script::Gcra | Script::FixedWindow => 4,
+        };
+        if args.len() != expected {
+            return Err(RateLimitError::Backend(format!(
+                "{script:?} script expects {expected} arguments, got {}",
+                args.len()
+            )));
+        }
+
+        let args: Vec<i64> = args.iter().map(|arg| *arg as i64).collect();
+        let now = self.now();
+        let mut entries = self
+            .entries
+            .lock()
+            .map_err(|_| RateLimitError::PoisonedLock)?;
+        let state = entries
+            .get(key)
+            .filter(|entry| entry.expires_at > now)
+            .map(|entry| entry.values);
+
+        let (reply, write) = match script {
+            Script::TokenBucket => token_bucket(&args, state, now),
+            Script::Gcra => gcra(&args, state, now),
+            Script::FixedWindow => fixed_window(&args, state, now),
+        };
+        match write {
+            Write::Keep => {}
+            Write::Delete => {
+                entries.remove(key);
+            }
+            Write::Set(values, ttl) => {
+                let expires_at = now + (ttl + 999) / 1000 * 1000;
+                entries.insert(key.to_string(), Entry { values, expires_at });
+            }
+        }
+        Ok(reply.to_vec())
+    }
+}
+
+// *** SCRIPTS ***
+/// State change made by a script, `Set` expires after the given µs rounded up to milliseconds
+/// like `PEXPIRE`.
+enum Write {
+    Keep,
+    Delete,
+    Set([i64; 3], i64),
+}
+
+fn token_bucket(args: &[i64], state: Option<[i64; 3]>, now: i64) -> ([i64; 4], Write) {
+    let &[capacity, rate_tokens, period, requested, dry_run] = args else {
+        unreachable!("argument count is checked by the caller");
+    };
+    let dry_run = dry_run == 1;
+
+    let [mut tokens, mut last, mut carry] = state.unwrap_or([capacity, now, 0]);
+    // Progress in µs times rate tokens, a token takes `period` of it
+    let progress = |last: i64, carry: i64| (now - last).max(0) * rate_tokens + carry;
+    let produced = progress(last, carry) / period;
+    if produced >= capacity - tokens {
+        tokens = capacity;
+        last = now;
+        carry = 0;
+    } else if produced > 0 {
+        let left = progress(last, carry) - produced * period;
+        tokens += produced;
+        last = now - left / rate_tokens;
+        carry = left % rate_tokens;
+    }
+    let time_until = |needed: i64| {
+        let left = needed * period - progress(last, carry);
+        ((left + rate_tokens - 1) / rate_tokens).max(0)
+    };
+
+    let mut allowed = 0;
+    let mut retry_after = 0;
+    if requested > capacity {
+        retry_after = -1;
+    } else if tokens >= requested {
+        allowed = 1;
+        if !dry_run {
+            tokens -= requested;
+        }
+    } else {
+        retry_after = time_until(requested - tokens);
+    }
+
+    let reset_after = time_until(capacity - tokens);
+    let write = match (dry_run, tokens == capacity) {
+        (true, _) => Write::Keep,
+        (false, true) => Write::Delete,
+        (false, false) => Write::Set([tokens, last, carry], reset_after),
+    };
+    ([allowed, tokens, retry_after, reset_after], write)
+}
+
+fn gcra(args: &[i64], state: Option<[i64; 3]>, now: i64) -> ([i64; 4], Write) {
+    let &[interval, max_delay, requested, dry_run] = args else {
+        unreachable!("argument count is checked by the caller");
+    };
+    let dry_run = dry_run == 1;
+
+    let mut tat = state.map_or(now, |[tat, ..]| tat).max(now);
+    let cost = requested * interval;
+
+    let mut allowed = 0;
+    let mut retry_after = 0;
+    if requested > max_delay / interval {
+        retry_after = -1;
+    } else if tat + cost - now <= max_delay {
+        allowed = 1;
+        if !dry_run {
+            tat += cost;
+        }
+    } else {
+        retry_after = tat + cost - now - max_delay;
+    }
+
+    let delay = tat - now;
+    let write = match (dry_run, delay > 0) {
+        (true, _) => Write::Keep,
+        (false, true) => Write::Set([tat, 0, 0], delay),
+        (false, false) => Write::Delete,
+    };
+    let remaining = (max_delay - delay) / interval;
+    ([allowed, remaining, retry_after, delay], write)
+}
+
+fn fixed_window(args: &[i64], state: Option<[i64; 3]>, now: i64) -> ([i64; 4], Write) {
+    let &[limit, window, requested, dry_run] = args else {
+        unreachable!("argument count is checked by the caller");
+    };
+    let dry_run = dry_run == 1;
+
+    let [mut remaining, start] = match state {
+        Some([remaining, start, _]) if now - start < window => [remaining, start],
+        _ => [limit, now],
+    };
+
+    let mut allowed = 0;
+    let mut retry_after = 0;
+    let reset_after = start + window - now;
+    if requested > limit {
+        retry_after = -1;
+    } else if remaining >= requested {
+        allowed = 1;
+        if !dry_run {
+            remaining -= requested;
+        }
+    } else {
+        retry_after = reset_after;
+    }
+
+    let write = if !dry_run && remaining < limit {
+        Write::Set([remaining, start, 0], reset_after)
+    } else {
+        Write::Keep
+    };
+    ([allowed, remaining, retry_after, reset_after], write)
+}
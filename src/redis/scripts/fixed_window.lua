@@ -0,0 +1,39 @@
+-- KEYS[1]: window state
+-- ARGV: limit, window (us), requested tokens, dry run (0 or 1)
+-- Returns: allowed (0 or 1), remaining, retry after (us, -1 if never), reset after (us)
+local limit = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+local requested = tonumber(ARGV[3])
+local dry_run = ARGV[4] == '1'
+
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000000 + tonumber(time[2])
+
+local state = redis.call('HMGET', KEYS[1], 'remaining', 'start')
+local remaining = tonumber(state[1])
+local start = tonumber(state[2])
+if not start or now - start >= window then
+  remaining = limit
+  start = now
+end
+
+local allowed = 0
+local retry_after = 0
+local reset_after = start + window - now
+if requested > limit then
+  retry_after = -1
+elseif remaining >= requested then
+  allowed = 1
+  if not dry_run then
+    remaining = remaining - requested
+  end
+else
+  retry_after = reset_after
+end
+
+if not dry_run and remaining < limit then
+  redis.call('HSET', KEYS[1], 'remaining', remaining, 'start', string.format('%.0f', start))
+  redis.call('PEXPIRE', KEYS[1], math.ceil(reset_after / 1000))
+end
+
+return {allowed, remaining, retry_after, reset_after}
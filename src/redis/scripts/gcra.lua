@@ -0,0 +1,37 @@
+-- KEYS[1]: theoretical arrival time
+-- ARGV: emission interval (us), max delay (us), requested tokens, dry run (0 or 1)
+-- Returns: allowed (0 or 1), remaining, retry after (us, -1 if never), reset after (us)
+local interval = tonumber(ARGV[1])
+local max_delay = tonumber(ARGV[2])
+local requested = tonumber(ARGV[3])
+local dry_run = ARGV[4] == '1'
+
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000000 + tonumber(time[2])
+
+local tat = math.max(tonumber(redis.call('GET', KEYS[1])) or now, now)
+local cost = requested * interval
+
+local allowed = 0
+local retry_after = 0
+if requested > math.floor(max_delay / interval) then
+  retry_after = -1
+elseif tat + cost - now <= max_delay then
+  allowed = 1
+  if not dry_run then
+    tat = tat + cost
+  end
+else
+  retry_after = tat + cost - now - max_delay
+end
+
+local delay = tat - now
+if not dry_run then
+  if delay > 0 then
+    redis.call('SET', KEYS[1], string.format('%.0f', tat), 'PX', math.ceil(delay / 1000))
+  else
+    redis.call('DEL', KEYS[1])
+  end
+end
+
+return {allowed, math.floor((max_delay - delay) / interval), retry_after, delay}
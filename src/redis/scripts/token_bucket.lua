@@ -0,0 +1,64 @@
+-- KEYS[1]: bucket state
+-- ARGV: capacity, rate tokens, rate period (us), requested tokens, dry run (0 or 1)
+-- Returns: allowed (0 or 1), remaining, retry after (us, -1 if never), reset after (us)
+local capacity = tonumber(ARGV[1])
+local rate_tokens = tonumber(ARGV[2])
+local period = tonumber(ARGV[3])
+local requested = tonumber(ARGV[4])
+local dry_run = ARGV[5] == '1'
+
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000000 + tonumber(time[2])
+
+local state = redis.call('HMGET', KEYS[1], 'tokens', 'last', 'carry')
+local tokens = tonumber(state[1]) or capacity
+local last = tonumber(state[2]) or now
+local carry = tonumber(state[3]) or 0
+
+-- Progress in us times rate tokens, a token takes `period` of it
+local function progress()
+  return math.max(now - last, 0) * rate_tokens + carry
+end
+
+local function time_until(needed)
+  return math.max(math.ceil((needed * period - progress()) / rate_tokens), 0)
+end
+
+-- Only whole tokens are added, the exact remainder stays in `last` and `carry`
+local produced = math.floor(progress() / period)
+if produced >= capacity - tokens then
+  tokens = capacity
+  last = now
+  carry = 0
+elseif produced > 0 then
+  local left = progress() - produced * period
+  tokens = tokens + produced
+  last = now - math.floor(left / rate_tokens)
+  carry = left % rate_tokens
+end
+
+local allowed = 0
+local retry_after = 0
+if requested > capacity then
+  retry_after = -1
+elseif tokens >= requested then
+  allowed = 1
+  if not dry_run then
+    tokens = tokens - requested
+  end
+else
+  retry_after = time_until(requested - tokens)
+end
+
+local reset_after = time_until(capacity - tokens)
+if not dry_run then
+  if tokens == capacity then
+    redis.call('DEL', KEYS[1])
+  else
+    local last_us = string.format('%.0f', last)
+    redis.call('HSET', KEYS[1], 'tokens', tokens, 'last', last_us, 'carry', carry)
+    redis.call('PEXPIRE', KEYS[1], math.ceil(reset_after / 1000))
+  end
+end
+
+return {allowed, tokens, retry_after, reset_after}
@@ -0,0 +1,2 @@
+mod redis_server_tests;
+mod redis_tests;
@@ -0,0 +1,170 @@
+/// Runs the Lua scripts against a real Redis 5.0 or newer. These tests are ignored by default, run
+/// them with `REDIS_URL=redis://127.0.0.1/ cargo test --features redis -- --ignored redis_server`.
+#[cfg(test)]
+mod sequential_tests {
+    use crate::core::{KeyedRateLimiterShared, Rate, RateLimiterShared};
+    use crate::redis::{MemoryExecutor, RedisExecutor, RedisLimiter};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    /// The server from `REDIS_URL` and a prefix no other run uses.
+    fn server() -> (RedisExecutor, String) {
+        let url = std::env::var("REDIS_URL").expect("REDIS_URL should be set");
+        let executor = RedisExecutor::open(&url).expect("REDIS_URL should be a valid url");
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let prefix = format!("rate_limiters:test:{}:{nanos}", std::process::id());
+        (executor, prefix)
+    }
+
+    fn exists(key: &str) -> bool {
+        let url = std::env::var("REDIS_URL").unwrap();
+        let mut connection = redis::Client::open(url).unwrap().get_connection().unwrap();
+        redis::cmd("EXISTS")
+            .arg(key)
+            .query::<bool>(&mut connection)
+            .unwrap()
+    }
+
+    #[test]
+    #[ignore = "needs REDIS_URL"]
+    fn token_bucket_server_test() {
+        let (executor, prefix) = server();
+        let key = format!("{prefix}:tb");
+        let bucket = RedisLimiter::token_bucket(&executor, &key, 3, Rate::per_minute(1)).unwrap();
+
+        assert_eq!(bucket.get_remaining(), 3);
+        assert!(!exists(&key));
+        assert!(bucket.try_acquire(2));
+        assert!(exists(&key));
+        assert_eq!(bucket.get_remaining(), 1);
+        assert!(bucket.try_acquire(1));
+
+        let decision = bucket.try_acquire_decision(1);
+        assert!(!decision.allowed);
+        assert_eq!(decision.remaining, 0);
+        assert!(decision.retry_after > Duration::from_secs(59));
+        assert!(decision.retry_after <= Duration::from_secs(60));
+        assert_eq!(bucket.get_retry_after(4), Duration::MAX);
+        assert_eq!(bucket.get_used(), 3);
+    }
+
+    #[test]
+    #[ignore = "needs REDIS_URL"]
+    fn token_bucket_expiry_server_test() {
+        let (executor, prefix) = server();
+        let key = format!("{prefix}:tb");
+        let bucket =
+            RedisLimiter::token_bucket(&executor, &key, 2, Rate::per(1, Duration::from_millis(50)))
+                .unwrap();
+
+        assert!(bucket.try_acquire(2));
+        assert!(!bucket.try_acquire(1));
+        std::thread::sleep(Duration::from_millis(250));
+        // The state expires once the bucket would be full again
+        assert!(!exists(&key));
+        assert_eq!(bucket.get_remaining(), 2);
+        assert!(bucket.try_acquire(2));
+    }
+
+    #[test]
+    #[ignore = "needs REDIS_URL"]
+    fn gcra_server_test() {
+        let (executor, prefix) = server();
+        let key = format!("{prefix}:gcra");
+        let limiter = RedisLimiter::gcra(
+            &executor,
+            &key,
+            Duration::from_secs(10),
+            Duration::from_secs(20),
+        )
+        .unwrap();
+
+        assert_eq!(limiter.get_limit(), 3);
+        assert!(limiter.try_acquire(2));
+        assert_eq!(limiter.get_remaining(), 1);
+        assert!(!limiter.try_acquire(2));
+        assert!(limiter.try_acquire(1));
+
+        let decision = limiter.try_acquire_decision(1);
+        assert!(!decision.allowed);
+        assert!(decision.retry_after > Duration::from_secs(9));
+        assert!(decision.retry_after <= Duration::from_secs(10));
+        assert!(exists(&key));
+    }
+
+    #[test]
+    #[ignore = "needs REDIS_URL"]
+    fn fixed_window_server_test() {
+        let (executor, prefix) = server();
+        let key = format!("{prefix}:window");
+        let window = RedisLimiter::fixed_window(&executor, &key, 2, Duration::from_secs(60))
+            .unwrap()
+            .keyed();
+
+        assert!(window.try_acquire("alice", 2));
+        assert!(window.try_acquire("bob", 1));
+        assert!(exists(&format!("{key}:alice")));
+
+        let decision = window.try_acquire_decision("alice", 1);
+        assert!(!decision.allowed);
+        assert!(decision.retry_after > Duration::from_secs(59));
+        assert!(decision.retry_after <= Duration::from_secs(60));
+        assert_eq!(window.get_remaining("bob"), 1);
+        assert_eq!(window.get_retry_after("alice", 3), Duration::MAX);
+    }
+
+    #[test]
+    #[ignore = "needs REDIS_URL"]
+    fn matches_memory_executor_test() {
+        let (executor, prefix) = server();
+        let memory = MemoryExecutor::new();
+        // Slow rates so the few milliseconds between the calls don't change the replies
+        let limiters = [
+            (
+                RedisLimiter::token_bucket(&executor, format!("{prefix}:tb"), 5, Rate::per_hour(1)),
+                RedisLimiter::token_bucket(&memory, format!("{prefix}:tb"), 5, Rate::per_hour(1)),
+            ),
+            (
+                RedisLimiter::gcra(
+                    &executor,
+                    format!("{prefix}:gcra"),
+                    Duration::from_secs(600),
+                    Duration::from_secs(2400),
+                ),
+                RedisLimiter::gcra(
+                    &memory,
+                    format!("{prefix}:gcra"),
+                    Duration::from_secs(600),
+                    Duration::from_secs(2400),
+                ),
+            ),
+            (
+                RedisLimiter::fixed_window(
+                    &executor,
+                    format!("{prefix}:window"),
+                    5,
+                    Duration::from_secs(3600),
+                ),
+                RedisLimiter::fixed_window(
+                    &memory,
+                    format!("{prefix}:window"),
+                    5,
+                    Duration::from_secs(3600),
+                ),
+            ),
+        ];
+
+        for (server, memory) in limiters {
+            let (server, memory) = (server.unwrap(), memory.unwrap());
+            for tokens in [0, 2, 1, 3, 1, 6, 2, 1] {
+                let expected = memory.try_acquire_decision(tokens);
+                let actual = server.try_acquire_decision(tokens);
+                assert_eq!(actual.allowed, expected.allowed, "{tokens} tokens");
+                assert_eq!(actual.remaining, expected.remaining, "{tokens} tokens");
+                assert_eq!(server.get_remaining(), memory.get_remaining());
+            }
+        }
+    }
+}
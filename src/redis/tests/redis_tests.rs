@@ -0,0 +1,255 @@
+#[cfg(test)]
+mod sequential_tests {
+    use crate::clock::MockClock;
+    use crate::core::{
+        KeyedRateLimiterShared, Rate, RateLimitError, RateLimiter, RateLimiterShared,
+    };
+    use crate::gcra::Gcra;
+    use crate::redis::{MemoryExecutor, RedisLimiter, Script, ScriptExecutor};
+    use crate::token_bucket::TokenBucket;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    struct Unreachable;
+
+    impl ScriptExecutor for Unreachable {
+        fn eval(&self, _: Script, _: &str, _: &[u64]) -> Result<Vec<i64>, RateLimitError> {
+            Err(RateLimitError::Backend("connection refused".to_string()))
+        }
+    }
+
+    #[test]
+    fn token_bucket_test() {
+        let clock = MockClock::new();
+        let store = MemoryExecutor::with_clock(clock.clone());
+        let bucket = RedisLimiter::token_bucket(&store, "api", 3, Rate::per_second(1)).unwrap();
+
+        assert_eq!(bucket.get_limit(), 3);
+        assert!(bucket.try_acquire(3));
+        assert!(store.contains_key("api"));
+        let decision = bucket.try_acquire_decision(1);
+        assert!(!decision.allowed);
+        assert_eq!(decision.remaining, 0);
+        assert_eq!(decision.retry_after, Duration::from_secs(1));
+
+        // Queries don't take tokens
+        clock.advance(Duration::from_millis(500));
+        assert_eq!(bucket.get_retry_after(1), Duration::from_millis(500));
+        assert_eq!(bucket.get_remaining(), 0);
+        assert_eq!(bucket.get_used(), 3);
+
+        clock.advance(Duration::from_millis(500));
+        assert_eq!(bucket.get_remaining(), 1);
+        assert!(bucket.try_acquire(1));
+        assert!(!bucket.try_acquire(1));
+
+        // A full bucket is removed from the store
+        clock.advance(Duration::from_secs(3));
+        assert_eq!(bucket.get_remaining(), 3);
+        assert!(!store.contains_key("api"));
+    }
+
+    #[test]
+    fn gcra_test() {
+        let clock = MockClock::new();
+        let store = MemoryExecutor::with_clock(clock.clone());
+        let limiter = RedisLimiter::gcra(
+            &store,
+            "gcra",
+            Duration::from_millis(100),
+            Duration::from_millis(200),
+        )
+        .unwrap();
+
+        assert_eq!(limiter.get_limit(), 3);
+        assert!(limiter.try_acquire(3));
+        let decision = limiter.try_acquire_decision(1);
+        assert!(!decision.allowed);
+        assert_eq!(decision.retry_after, Duration::from_millis(100));
+
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(limiter.get_remaining(), 1);
+        assert!(limiter.try_acquire(1));
+        assert_eq!(limiter.get_retry_after(2), Duration::from_millis(200));
+
+        clock.advance(Duration::from_millis(300));
+        assert_eq!(limiter.get_remaining(), 3);
+        assert!(!store.contains_key("gcra"));
+    }
+
+    #[test]
+    fn fixed_window_test() {
+        let clock = MockClock::new();
+        let store = MemoryExecutor::with_clock(clock.clone());
+        let window =
+            RedisLimiter::fixed_window(&store, "window", 2, Duration::from_secs(10)).unwrap();
+
+        assert!(window.try_acquire(1));
+        clock.advance(Duration::from_secs(4));
+        assert!(window.try_acquire(1));
+        let decision = window.try_acquire_decision(1);
+        assert!(!decision.allowed);
+        assert_eq!(decision.retry_after, Duration::from_secs(6));
+
+        clock.advance(Duration::from_secs(6));
+        assert_eq!(window.get_remaining(), 2);
+        assert!(window.try_acquire(2));
+    }
+
+    #[test]
+    fn matches_in_memory_test() {
+        let clock = MockClock::new();
+        let store = MemoryExecutor::with_clock(clock.clone());
+        let rate = Rate::per(3, Duration::from_secs(2));
+        let remote = RedisLimiter::token_bucket(&store, "tb", 5, rate).unwrap();
+        let mut local = TokenBucket::with_rate_and_clock(5, rate, clock.clone());
+        let remote_gcra = RedisLimiter::gcra(
+            &store,
+            "gcra",
+            Duration::from_millis(250),
+            Duration::from_millis(500),
+        )
+        .unwrap();
+        let mut local_gcra = Gcra::with_clock(
+            Duration::from_millis(250),
+            Duration::from_millis(500),
+            clock.clone(),
+        );
+
+        for step in 0..200u32 {
+            let tokens = step % 3;
+            let remote_decision = remote.try_acquire_decision(tokens);
+            let local_decision = local.try_acquire_decision(tokens);
+            assert_eq!(
+                remote_decision.allowed, local_decision.allowed,
+                "step {step}"
+            );
+            assert_eq!(remote_decision.remaining, local_decision.remaining);
+
+            let remote_decision = remote_gcra.try_acquire_decision(tokens);
+            let local_decision = local_gcra.try_acquire_decision(tokens);
+            assert_eq!(
+                remote_decision.allowed, local_decision.allowed,
+                "step {step}"
+            );
+            assert_eq!(remote_decision.remaining, local_decision.remaining);
+            assert_eq!(remote_decision.retry_after, local_decision.retry_after);
+
+            clock.advance(Duration::from_millis(u64::from(step % 7) * 50));
+        }
+    }
+
+    #[test]
+    fn shared_store_test() {
+        let clock = MockClock::new();
+        let store = Arc::new(MemoryExecutor::with_clock(clock));
+        // Two replicas configured alike enforce one limit
+        let first =
+            RedisLimiter::token_bucket(Arc::clone(&store), "api", 4, Rate::per_minute(1)).unwrap();
+        let second =
+            RedisLimiter::token_bucket(Arc::clone(&store), "api", 4, Rate::per_minute(1)).unwrap();
+
+        assert!(first.try_acquire(3));
+        assert_eq!(second.get_remaining(), 1);
+        assert!(!second.try_acquire(2));
+        assert!(second.try_acquire(1));
+        assert!(!first.try_acquire(1));
+    }
+
+    #[test]
+    fn keyed_test() {
+        let clock = MockClock::new();
+        let store = MemoryExecutor::with_clock(clock.clone());
+        let limiter = RedisLimiter::token_bucket(&store, "api", 2, Rate::per_second(1))
+            .unwrap()
+            .keyed();
+
+        assert!(limiter.try_acquire("alice", 2));
+        assert!(!limiter.try_acquire("alice", 1));
+        assert!(limiter.try_acquire("bob", 1));
+        assert!(store.contains_key("api:alice"));
+        assert!(!store.contains_key("api"));
+
+        assert_eq!(limiter.get_remaining("alice"), 0);
+        assert_eq!(limiter.get_remaining("bob"), 1);
+        assert_eq!(limiter.get_remaining(&42), 2);
+        assert_eq!(limiter.get_retry_after("alice", 2), Duration::from_secs(2));
+
+        let err = limiter.try_acquire_checked("alice", 1).unwrap_err();
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn capacity_test() {
+        let store = MemoryExecutor::new();
+        let bucket = RedisLimiter::token_bucket(&store, "api", 3, Rate::per_second(1)).unwrap();
+
+        assert_eq!(
+            bucket.try_acquire_checked(4),
+            Err(RateLimitError::InsufficientCapacity {
+                requested: 4,
+                capacity: 3
+            })
+        );
+        let decision = bucket.try_acquire_decision(4);
+        assert!(!decision.allowed);
+        assert!(!decision.can_retry());
+        assert_eq!(bucket.get_retry_after(4), Duration::MAX);
+    }
+
+    #[test]
+    fn invalid_config_test() {
+        let store = MemoryExecutor::new();
+        let invalid = |result: Result<RedisLimiter<_>, _>| {
+            matches!(result, Err(RateLimitError::InvalidConfig(_)))
+        };
+
+        assert!(invalid(RedisLimiter::token_bucket(
+            &store,
+            "tb",
+            0,
+            Rate::per_second(1)
+        )));
+        assert!(invalid(RedisLimiter::token_bucket(
+            &store,
+            "tb",
+            1,
+            Rate::per_second(0)
+        )));
+        assert!(invalid(RedisLimiter::gcra(
+            &store,
+            "gcra",
+            Duration::from_nanos(10),
+            Duration::ZERO
+        )));
+        assert!(invalid(RedisLimiter::fixed_window(
+            &store,
+            "window",
+            1,
+            Duration::ZERO
+        )));
+    }
+
+    #[test]
+    fn backend_failure_test() {
+        let closed =
+            RedisLimiter::token_bucket(Unreachable, "api", 3, Rate::per_second(1)).unwrap();
+        assert!(!closed.try_acquire(1));
+        assert_eq!(closed.get_remaining(), 0);
+        // The limiter can't tell when the store is back, so the denial is not retryable
+        let decision = closed.try_acquire_decision(1);
+        assert!(!decision.can_retry());
+        assert_eq!(closed.get_retry_after(1), Duration::MAX);
+        let err = closed.try_acquire_checked(1).unwrap_err();
+        assert!(matches!(err, RateLimitError::Backend(_)));
+        assert!(!err.is_retryable());
+        assert_eq!(err.to_string(), "limiter backend error: connection refused");
+
+        let open = RedisLimiter::token_bucket(Unreachable, "api", 3, Rate::per_second(1))
+            .unwrap()
+            .with_fail_open(true);
+        assert!(open.try_acquire(1));
+        assert_eq!(open.get_remaining(), 3);
+        assert!(open.try_acquire_checked(1).unwrap().allowed);
+    }
+}
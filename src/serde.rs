@@ -0,0 +1,11 @@
+pub mod r#impl;
+pub mod policy;
+pub mod tests;
+
+pub use policy::{BoxLimiter, LimitPolicy, Policy};
+pub use r#impl::{
+    FixedWindowCounterConfig, FixedWindowCounterSnapshot, GcraConfig, GcraSnapshot,
+    LeakyBucketConfig, LeakyBucketSnapshot, SlidingWindowCounterConfig,
+    SlidingWindowCounterSnapshot, SlidingWindowLogConfig, SlidingWindowLogSnapshot, Snapshot,
+    TokenBucketConfig, TokenBucketSnapshot,
+};
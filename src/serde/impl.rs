@@ -0,0 +1,669 @@
+use std::sync::PoisonError;
+use std::time::Duration;
+
+use ::serde::{Deserialize, Serialize};
+
+use crate::clock::{since_unix, Clock, SystemClock};
+use crate::core::{Rate, RateLimitError};
+use crate::fixed_window_counter::{
+    FixedWindowCounter, FixedWindowCounterShared, FixedWindowCounterState,
+};
+use crate::gcra::{Gcra, GcraShared, GcraState};
+use crate::leaky_bucket::{LeakyBucket, LeakyBucketShared, LeakyBucketState};
+use crate::sliding_window_counter::{
+    SlidingWindowCounter, SlidingWindowCounterShared, SlidingWindowCounterState,
+};
+use crate::sliding_window_log::{SlidingWindowLog, SlidingWindowLogShared, SlidingWindowLogState};
+use crate::store::Algorithm;
+use crate::token_bucket::{TokenBucket, TokenBucketShared, TokenBucketState};
+
+// *** CONFIG ***
+/// Durations are written in seconds, e.g. `window: 60` or `emission_interval: 0.25`, or with a
+/// unit, e.g. `window: "1m"` or `emission_interval: "250ms"`. Unknown fields are rejected so a
+/// typo doesn't silently fall back to a default.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TokenBucketConfig {
+    pub capacity: u32,
+    /// Tokens added every `refill_period`.
+    pub refill_rate: u32,
+    #[serde(default = "one_second", with = "secs")]
+    pub refill_period: Duration,
+    /// Defaults to a full bucket.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial_tokens: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LeakyBucketConfig {
+    pub capacity: u32,
+    /// Amount leaked per second.
+    pub leak_rate: f64,
+    #[serde(default)]
+    pub initial_level: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FixedWindowCounterConfig {
+    pub limit: u32,
+    #[serde(with = "secs")]
+    pub window: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SlidingWindowCounterConfig {
+    pub capacity: u32,
+    #[serde(with = "secs")]
+    pub window: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SlidingWindowLogConfig {
+    pub capacity: u32,
+    #[serde(with = "secs")]
+    pub window: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GcraConfig {
+    #[serde(with = "secs")]
+    pub emission_interval: Duration,
+    #[serde(default, with = "secs")]
+    pub burst_tolerance: Duration,
+}
+
+fn one_second() -> Duration {
+    Duration::from_secs(1)
+}
+
+impl TokenBucketConfig {
+    pub fn build(&self) -> Result<TokenBucket, RateLimitError> {
+        self.build_with_clock(SystemClock)
+    }
+
+    pub fn build_shared(&self) -> Result<TokenBucketShared, RateLimitError> {
+        self.build().map(TokenBucketShared::from)
+    }
+
+    pub fn build_with_clock<C: Clock>(&self, clock: C) -> Result<TokenBucket<C>, RateLimitError> {
+        let mut builder = TokenBucket::builder()
+            .capacity(self.capacity)
+            .rate(Rate::per(self.refill_rate, self.refill_period))
+            .clock(clock);
+        if let Some(tokens) = self.initial_tokens {
+            builder = builder.initial_tokens(tokens);
+        }
+        builder.build()
+    }
+}
+
+impl LeakyBucketConfig {
+    pub fn build(&self) -> Result<LeakyBucket, RateLimitError> {
+        self.build_with_clock(SystemClock)
+    }
+
+    pub fn build_shared(&self) -> Result<LeakyBucketShared, RateLimitError> {
+        self.build().map(LeakyBucketShared::from)
+    }
+
+    pub fn build_with_clock<C: Clock>(&self, clock: C) -> Result<LeakyBucket<C>, RateLimitError> {
+        LeakyBucket::builder()
+            .capacity(self.capacity)
+            .leak_rate(self.leak_rate)
+            .initial_level(self.initial_level)
+            .clock(clock)
+            .build()
+    }
+}
+
+impl FixedWindowCounterConfig {
+    pub fn build(&self) -> Result<FixedWindowCounter, RateLimitError> {
+        self.build_with_clock(SystemClock)
+    }
+
+    pub fn build_shared(&self) -> Result<FixedWindowCounterShared, RateLimitError> {
+        self.build().map(FixedWindowCounterShared::from)
+    }
+
+    pub fn build_with_clock<C: Clock>(
+        &self,
+        clock: C,
+    ) -> Result<FixedWindowCounter<C>, RateLimitError> {
+        FixedWindowCounter::builder()
+            .limit(self.limit)
+            .window(self.window)
+            .clock(clock)
+            .build()
+    }
+}
+
+impl SlidingWindowCounterConfig {
+    pub fn build(&self) -> Result<SlidingWindowCounter, RateLimitError> {
+        self.build_with_clock(SystemClock)
+    }
+
+    pub fn build_shared(&self) -> Result<SlidingWindowCounterShared, RateLimitError> {
+        self.build().map(SlidingWindowCounterShared::from)
+    }
+
+    pub fn build_with_clock<C: Clock>(
+        &self,
+        clock: C,
+    ) -> Result<SlidingWindowCounter<C>, RateLimitError> {
+        SlidingWindowCounter::builder()
+            .capacity(self.capacity)
+            .window(self.window)
+            .clock(clock)
+            .build()
+    }
+}
+
+impl SlidingWindowLogConfig {
+    pub fn build(&self) -> Result<SlidingWindowLog, RateLimitError> {
+        self.build_with_clock(SystemClock)
+    }
+
+    pub fn build_shared(&self) -> Result<SlidingWindowLogShared, RateLimitError> {
+        self.build().map(SlidingWindowLogShared::from)
+    }
+
+    pub fn build_with_clock<C: Clock>(
+        &self,
+        clock: C,
+    ) -> Result<SlidingWindowLog<C>, RateLimitError> {
+        SlidingWindowLog::builder()
+            .capacity(self.capacity)
+            .window(self.window)
+            .clock(clock)
+            .build()
+    }
+}
+
+impl GcraConfig {
+    pub fn build(&self) -> Result<Gcra, RateLimitError> {
+        self.build_with_clock(SystemClock)
+    }
+
+    pub fn build_shared(&self) -> Result<GcraShared, RateLimitError> {
+        self.build().map(GcraShared::from)
+    }
+
+    pub fn build_with_clock<C: Clock>(&self, clock: C) -> Result<Gcra<C>, RateLimitError> {
+        Gcra::builder()
+            .emission_interval(self.emission_interval)
+            .burst_tolerance(self.burst_tolerance)
+            .clock(clock)
+            .build()
+    }
+}
+
+// *** SNAPSHOT ***
+/// The configuration and live state of a limiter, with every timestamp in `state` expressed as
+/// time since the UNIX epoch. Restoring it in another process resumes with the same budget,
+/// minus whatever the algorithm recovered in between, e.g. tokens refilled while it was down.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot<Config, State> {
+    pub config: Config,
+    pub state: State,
+}
+
+pub type TokenBucketSnapshot = Snapshot<TokenBucketConfig, TokenBucketState>;
+pub type LeakyBucketSnapshot = Snapshot<LeakyBucketConfig, LeakyBucketState>;
+pub type FixedWindowCounterSnapshot = Snapshot<FixedWindowCounterConfig, FixedWindowCounterState>;
+pub type SlidingWindowCounterSnapshot =
+    Snapshot<SlidingWindowCounterConfig, SlidingWindowCounterState>;
+pub type SlidingWindowLogSnapshot = Snapshot<SlidingWindowLogConfig, SlidingWindowLogState>;
+pub type GcraSnapshot = Snapshot<GcraConfig, GcraState>;
+
+/// States whose timestamps can be moved between a limiter's own time and wall-clock time.
+trait Timestamps {
+    fn map_times(&mut self, f: impl Fn(Duration) -> Duration);
+
+    /// Whether the state can belong to a limiter allowing `limit` tokens.
+    fn fits(&self, limit: u32) -> bool;
+}
+
+impl Timestamps for TokenBucketState {
+    fn map_times(&mut self, f: impl Fn(Duration) -> Duration) {
+        self.last_refill = f(self.last_refill);
+    }
+
+    fn fits(&self, limit: u32) -> bool {
+        self.tokens <= limit
+    }
+}
+
+impl Timestamps for LeakyBucketState {
+    fn map_times(&mut self, f: impl Fn(Duration) -> Duration) {
+        self.last_check = f(self.last_check);
+    }
+
+    fn fits(&self, limit: u32) -> bool {
+        (0.0..=limit as f64).contains(&self.water)
+    }
+}
+
+impl Timestamps for FixedWindowCounterState {
+    fn map_times(&mut self, f: impl Fn(Duration) -> Duration) {
+        self.last_reset = f(self.last_reset);
+    }
+
+    fn fits(&self, limit: u32) -> bool {
+        self.remaining <= limit
+    }
+}
+
+impl Timestamps for SlidingWindowCounterState {
+    fn map_times(&mut self, f: impl Fn(Duration) -> Duration) {
+        self.window_start = f(self.window_start);
+    }
+
+    fn fits(&self, limit: u32) -> bool {
+        self.previous <= limit && self.current <= limit
+    }
+}
+
+impl Timestamps for SlidingWindowLogState {
+    fn map_times(&mut self, f: impl Fn(Duration) -> Duration) {
+        for (ts, _) in self.log.iter_mut() {
+            *ts = f(*ts);
+        }
+    }
+
+    fn fits(&self, limit: u32) -> bool {
+        let total = self
+            .log
+            .iter()
+            .map(|&(_, tokens)| u64::from(tokens))
+            .sum::<u64>();
+        let ordered = self
+            .log
+            .iter()
+            .zip(self.log.iter().skip(1))
+            .all(|(a, b)| a.0 <= b.0);
+        total == u64::from(self.used) && self.used <= limit && ordered
+    }
+}
+
+impl Timestamps for GcraState {
+    fn map_times(&mut self, f: impl Fn(Duration) -> Duration) {
+        self.tat = f(self.tat);
+    }
+
+    fn fits(&self, _limit: u32) -> bool {
+        true
+    }
+}
+
+/// Moves `time` from a timeline where the present is `from` to one where it is `to`.
+fn rebase(time: Duration, from: Duration, to: Duration) -> Duration {
+    if time <= from {
+        to.saturating_sub(from - time)
+    } else {
+        to.saturating_add(time - from)
+    }
+}
+
+fn to_wall_clock<S: Timestamps + Clone>(state: &S, now: Duration, clock: &impl Clock) -> S {
+    let wall = since_unix(clock.now_system());
+    let mut state = state.clone();
+    state.map_times(|time| rebase(time, now, wall));
+    state
+}
+
+fn from_wall_clock<S: Timestamps + Clone>(
+    state: &S,
+    limit: u32,
+    now: Duration,
+    clock: &impl Clock,
+) -> Result<S, RateLimitError> {
+    if !state.fits(limit) {
+        return Err(RateLimitError::InvalidConfig(format!(
+            "snapshot state doesn't fit a limit of {limit}"
+        )));
+    }
+    let wall = since_unix(clock.now_system());
+    let mut state = state.clone();
+    state.map_times(|time| rebase(time, wall, now));
+    Ok(state)
+}
+
+impl<C: Clock> TokenBucket<C> {
+    pub fn config(&self) -> TokenBucketConfig {
+        let rate = self.algorithm.rate();
+        TokenBucketConfig {
+            capacity: self.algorithm.capacity(),
+            refill_rate: rate.tokens(),
+            refill_period: rate.period(),
+            initial_tokens: None,
+        }
+    }
+
+    pub fn snapshot(&self) -> TokenBucketSnapshot {
+        Snapshot {
+            config: self.config(),
+            state: to_wall_clock(&self.state, self.now(), &self.clock),
+        }
+    }
+
+    pub fn restore_with_clock(
+        snapshot: &TokenBucketSnapshot,
+        clock: C,
+    ) -> Result<Self, RateLimitError> {
+        let mut bucket = snapshot.config.build_with_clock(clock)?;
+        let limit = bucket.algorithm.limit();
+        bucket.state = from_wall_clock(&snapshot.state, limit, bucket.now(), &bucket.clock)?;
+        Ok(bucket)
+    }
+}
+
+impl TokenBucket {
+    pub fn restore(snapshot: &TokenBucketSnapshot) -> Result<Self, RateLimitError> {
+        Self::restore_with_clock(snapshot, SystemClock)
+    }
+}
+
+impl<C: Clock> LeakyBucket<C> {
+    pub fn config(&self) -> LeakyBucketConfig {
+        LeakyBucketConfig {
+            capacity: self.algorithm.capacity(),
+            leak_rate: self.algorithm.leak_rate(),
+            initial_level: 0.0,
+        }
+    }
+
+    pub fn snapshot(&self) -> LeakyBucketSnapshot {
+        Snapshot {
+            config: self.config(),
+            state: to_wall_clock(&self.state, self.now(), &self.clock),
+        }
+    }
+
+    pub fn restore_with_clock(
+        snapshot: &LeakyBucketSnapshot,
+        clock: C,
+    ) -> Result<Self, RateLimitError> {
+        let mut bucket = snapshot.config.build_with_clock(clock)?;
+        let limit = bucket.algorithm.limit();
+        bucket.state = from_wall_clock(&snapshot.state, limit, bucket.now(), &bucket.clock)?;
+        Ok(bucket)
+    }
+}
+
+impl LeakyBucket {
+    pub fn restore(snapshot: &LeakyBucketSnapshot) -> Result<Self, RateLimitError> {
+        Self::restore_with_clock(snapshot, SystemClock)
+    }
+}
+
+impl<C: Clock> FixedWindowCounter<C> {
+    pub fn config(&self) -> FixedWindowCounterConfig {
+        FixedWindowCounterConfig {
+            limit: self.algorithm.limit(),
+            window: self.algorithm.window(),
+        }
+    }
+
+    pub fn snapshot(&self) -> FixedWindowCounterSnapshot {
+        Snapshot {
+            config: self.config(),
+            state: to_wall_clock(&self.state, self.now(), &self.clock),
+        }
+    }
+
+    pub fn restore_with_clock(
+        snapshot: &FixedWindowCounterSnapshot,
+        clock: C,
+    ) -> Result<Self, RateLimitError> {
+        let mut limiter = snapshot.config.build_with_clock(clock)?;
+        let limit = limiter.algorithm.limit();
+        limiter.state = from_wall_clock(&snapshot.state, limit, limiter.now(), &limiter.clock)?;
+        Ok(limiter)
+    }
+}
+
+impl FixedWindowCounter {
+    pub fn restore(snapshot: &FixedWindowCounterSnapshot) -> Result<Self, RateLimitError> {
+        Self::restore_with_clock(snapshot, SystemClock)
+    }
+}
+
+impl<C: Clock> SlidingWindowCounter<C> {
+    pub fn config(&self) -> SlidingWindowCounterConfig {
+        SlidingWindowCounterConfig {
+            capacity: self.algorithm.limit(),
+            window: self.algorithm.window(),
+        }
+    }
+
+    pub fn snapshot(&self) -> SlidingWindowCounterSnapshot {
+        Snapshot {
+            config: self.config(),
+            state: to_wall_clock(&self.state, self.now(), &self.clock),
+        }
+    }
+
+    pub fn restore_with_clock(
+        snapshot: &SlidingWindowCounterSnapshot,
+        clock: C,
+    ) -> Result<Self, RateLimitError> {
+        let mut limiter = snapshot.config.build_with_clock(clock)?;
+        let limit = limiter.algorithm.limit();
+        limiter.state = from_wall_clock(&snapshot.state, limit, limiter.now(), &limiter.clock)?;
+        Ok(limiter)
+    }
+}
+
+impl SlidingWindowCounter {
+    pub fn restore(snapshot: &SlidingWindowCounterSnapshot) -> Result<Self, RateLimitError> {
+        Self::restore_with_clock(snapshot, SystemClock)
+    }
+}
+
+impl<C: Clock> SlidingWindowLog<C> {
+    pub fn config(&self) -> SlidingWindowLogConfig {
+        SlidingWindowLogConfig {
+            capacity: self.algorithm.limit(),
+            window: self.algorithm.window(),
+        }
+    }
+
+    pub fn snapshot(&self) -> SlidingWindowLogSnapshot {
+        Snapshot {
+            config: self.config(),
+            state: to_wall_clock(&self.state, self.now(), &self.clock),
+        }
+    }
+
+    pub fn restore_with_clock(
+        snapshot: &SlidingWindowLogSnapshot,
+        clock: C,
+    ) -> Result<Self, RateLimitError> {
+        let mut limiter = snapshot.config.build_with_clock(clock)?;
+        let limit = limiter.algorithm.limit();
+        limiter.state = from_wall_clock(&snapshot.state, limit, limiter.now(), &limiter.clock)?;
+        Ok(limiter)
+    }
+}
+
+impl SlidingWindowLog {
+    pub fn restore(snapshot: &SlidingWindowLogSnapshot) -> Result<Self, RateLimitError> {
+        Self::restore_with_clock(snapshot, SystemClock)
+    }
+}
+
+impl<C: Clock> Gcra<C> {
+    pub fn config(&self) -> GcraConfig {
+        GcraConfig {
+            emission_interval: self.algorithm.emission_interval(),
+            burst_tolerance: self.algorithm.burst_tolerance(),
+        }
+    }
+
+    pub fn snapshot(&self) -> GcraSnapshot {
+        Snapshot {
+            config: self.config(),
+            state: to_wall_clock(&self.state, self.now(), &self.clock),
+        }
+    }
+
+    pub fn restore_with_clock(snapshot: &GcraSnapshot, clock: C) -> Result<Self, RateLimitError> {
+        let mut limiter = snapshot.config.build_with_clock(clock)?;
+        let limit = limiter.algorithm.limit();
+        limiter.state = from_wall_clock(&snapshot.state, limit, limiter.now(), &limiter.clock)?;
+        Ok(limiter)
+    }
+}
+
+impl Gcra {
+    pub fn restore(snapshot: &GcraSnapshot) -> Result<Self, RateLimitError> {
+        Self::restore_with_clock(snapshot, SystemClock)
+    }
+}
+
+// *** SHARED SNAPSHOTS ***
+impl<C: Clock> TokenBucketShared<C> {
+    pub fn snapshot(&self) -> TokenBucketSnapshot {
+        self.inner
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .snapshot()
+    }
+}
+
+impl<C: Clock> LeakyBucketShared<C> {
+    pub fn snapshot(&self) -> LeakyBucketSnapshot {
+        self.inner
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .snapshot()
+    }
+}
+
+impl<C: Clock> FixedWindowCounterShared<C> {
+    pub fn snapshot(&self) -> FixedWindowCounterSnapshot {
+        self.inner
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .snapshot()
+    }
+}
+
+impl<C: Clock> SlidingWindowCounterShared<C> {
+    pub fn snapshot(&self) -> SlidingWindowCounterSnapshot {
+        self.inner
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .snapshot()
+    }
+}
+
+impl<C: Clock> SlidingWindowLogShared<C> {
+    pub fn snapshot(&self) -> SlidingWindowLogSnapshot {
+        self.inner
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .snapshot()
+    }
+}
+
+impl<C: Clock> GcraShared<C> {
+    pub fn snapshot(&self) -> GcraSnapshot {
+        self.inner
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .snapshot()
+    }
+}
+
+// *** DURATION IN SECONDS ***
+/// (De)serializes a `Duration` as a number of seconds, whole seconds are written as integers.
+/// Strings with a unit such as `"1m"` are accepted too.
+pub(super) mod secs {
+    use std::fmt;
+    use std::time::Duration;
+
+    use ::serde::de::{self, Visitor};
+    use ::serde::{Deserializer, Serializer};
+
+    pub(in crate::serde) fn serialize<S: Serializer>(
+        duration: &Duration,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        if duration.subsec_nanos() == 0 {
+            serializer.serialize_u64(duration.as_secs())
+        } else {
+            serializer.serialize_f64(duration.as_secs_f64())
+        }
+    }
+
+    pub(in crate::serde) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Duration, D::Error> {
+        deserializer.deserialize_any(SecsVisitor)
+    }
+
+    const UNITS: [(&str, u64); 7] = [
+        ("d", 86_400_000_000_000),
+        ("h", 3_600_000_000_000),
+        ("m", 60_000_000_000),
+        ("s", 1_000_000_000),
+        ("ms", 1_000_000),
+        ("us", 1_000),
+        ("ns", 1),
+    ];
+
+    /// Parses a whole count followed by a unit, e.g. `"30s"`, `"1m"` or `"500ms"`.
+    pub(in crate::serde) fn parse(text: &str) -> Option<Duration> {
+        let text = text.trim();
+        let split = text.find(|c: char| !c.is_ascii_digit())?;
+        let (count, unit) = text.split_at(split);
+        let count = count.parse::<u64>().ok()?;
+        let (_, nanos) = UNITS.iter().find(|(name, _)| *name == unit.trim())?;
+        count.checked_mul(*nanos).map(Duration::from_nanos)
+    }
+
+    /// Inverse of `parse`, in the largest unit that divides `duration`.
+    pub(in crate::serde) fn format(duration: Duration) -> String {
+        let nanos = duration.as_nanos();
+        let (unit, size) = UNITS
+            .iter()
+            .find(|(_, size)| nanos.is_multiple_of(u128::from(*size)))
+            .unwrap_or(&UNITS[UNITS.len() - 1]);
+        format!("{}{unit}", nanos / u128::from(*size))
+    }
+
+    struct SecsVisitor;
+
+    impl Visitor<'_> for SecsVisitor {
+        type Value = Duration;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a non-negative number of seconds or a duration such as \"30s\" or \"1m\"")
+        }
+
+        fn visit_str<E: de::Error>(self, text: &str) -> Result<Duration, E> {
+            parse(text).ok_or_else(|| E::invalid_value(de::Unexpected::Str(text), &self))
+        }
+
+        fn visit_u64<E: de::Error>(self, secs: u64) -> Result<Duration, E> {
+            Ok(Duration::from_secs(secs))
+        }
+
+        fn visit_i64<E: de::Error>(self, secs: i64) -> Result<Duration, E> {
+            u64::try_from(secs)
+                .map(Duration::from_secs)
+                .map_err(|_| E::invalid_value(de::Unexpected::Signed(secs), &self))
+        }
+
+        fn visit_f64<E: de::Error>(self, secs: f64) -> Result<Duration, E> {
+            Duration::try_from_secs_f64(secs)
+                .map_err(|_| E::invalid_value(de::Unexpected::Float(secs), &self))
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use ::serde::{Deserialize, Serialize};
+
+use crate::clock::{Clock, SystemClock};
+use crate::core::{Rate, RateLimitError, RateLimiterShared};
+use crate::fixed_window_counter::FixedWindowCounterShared;
+use crate::gcra::GcraShared;
+use crate::leaky_bucket::LeakyBucketShared;
+use crate::serde::r#impl::{
+    secs, FixedWindowCounterConfig, GcraConfig, LeakyBucketConfig, SlidingWindowCounterConfig,
+    SlidingWindowLogConfig, TokenBucketConfig,
+};
+use crate::sliding_window_counter::SlidingWindowCounterShared;
+use crate::sliding_window_log::SlidingWindowLogShared;
+use crate::token_bucket::TokenBucketShared;
+
+pub type BoxLimiter = Box<dyn RateLimiterShared + Send + Sync>;
+
+// *** POLICY ***
+/// Named limits loaded from any serde format, e.g. in TOML:
+///
+/// ```toml
+/// [limits.api]
+/// algorithm = "token_bucket"
+/// capacity = 100
+/// rate = "10/s"
+///
+/// [limits.search]
+/// algorithm = "sliding_window_log"
+/// limit = 1000
+/// window = "1m"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    #[serde(default)]
+    pub limits: BTreeMap<String, LimitPolicy>,
+}
+
+impl Policy {
+    /// Builds every limit, failing on the first invalid one with its name in the error.
+    pub fn build(&self) -> Result<BTreeMap<String, BoxLimiter>, RateLimitError> {
+        self.build_with_clock(SystemClock)
+    }
+
+    pub fn build_with_clock<C>(
+        &self,
+        clock: C,
+    ) -> Result<BTreeMap<String, BoxLimiter>, RateLimitError>
+    where
+        C: Clock + Clone + Send + Sync + 'static,
+    {
+        self.limits
+            .iter()
+            .map(|(name, limit)| {
+                let limiter = limit
+                    .build_with_clock(clock.clone())
+                    .map_err(|err| match err {
+                        RateLimitError::InvalidConfig(reason) => {
+                            RateLimitError::InvalidConfig(format!("limit {name:?}: {reason}"))
+                        }
+                        err => err,
+                    })?;
+                Ok((name.clone(), limiter))
+            })
+            .collect()
+    }
+}
+
+// *** LIMIT POLICY ***
+/// One limit, tagged by `algorithm`. Rates are written as `"10/s"`, `"100/m"` or `"1/500ms"`,
+/// windows as `"30s"`, `"1m"` or a number of seconds. Each limit is built through the matching
+/// config, e.g. `TokenBucketConfig`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "algorithm", rename_all = "snake_case", deny_unknown_fields)]
+pub enum LimitPolicy {
+    TokenBucket {
+        capacity: u32,
+        /// Refill rate.
+        #[serde(with = "rate")]
+        rate: Rate,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        initial_tokens: Option<u32>,
+    },
+    LeakyBucket {
+        capacity: u32,
+        /// Leak rate.
+        #[serde(with = "rate")]
+        rate: Rate,
+    },
+    FixedWindowCounter {
+        limit: u32,
+        #[serde(with = "secs")]
+        window: Duration,
+    },
+    SlidingWindowCounter {
+        #[serde(alias = "capacity")]
+        limit: u32,
+        #[serde(with = "secs")]
+        window: Duration,
+    },
+    SlidingWindowLog {
+        #[serde(alias = "capacity")]
+        limit: u32,
+        #[serde(with = "secs")]
+        window: Duration,
+    },
+    Gcra {
+        #[serde(with = "rate")]
+        rate: Rate,
+        /// Requests allowed back to back.
+        #[serde(default = "one")]
+        burst: u32,
+    },
+}
+
+fn one() -> u32 {
+    1
+}
+
+impl LimitPolicy {
+    pub fn build(&self) -> Result<BoxLimiter, RateLimitError> {
+        self.build_with_clock(SystemClock)
+    }
+
+    pub fn build_with_clock<C>(&self, clock: C) -> Result<BoxLimiter, RateLimitError>
+    where
+        C: Clock + Send + Sync + 'static,
+    {
+        Ok(match *self {
+            Self::TokenBucket {
+                capacity,
+                rate,
+                initial_tokens,
+            } => {
+                let config = TokenBucketConfig {
+                    capacity,
+                    refill_rate: rate.tokens(),
+                    refill_period: rate.period(),
+                    initial_tokens,
+                };
+                Box::new(TokenBucketShared::from(config.build_with_clock(clock)?))
+            }
+            Self::LeakyBucket { capacity, rate } => {
+                let config = LeakyBucketConfig {
+                    capacity,
+                    leak_rate: rate.tokens() as f64 / rate.period().as_secs_f64(),
+                    initial_level: 0.0,
+                };
+                Box::new(LeakyBucketShared::from(config.build_with_clock(clock)?))
+            }
+            Self::FixedWindowCounter { limit, window } => {
+                let config = FixedWindowCounterConfig { limit, window };
+                Box::new(FixedWindowCounterShared::from(
+                    config.build_with_clock(clock)?,
+                ))
+            }
+            Self::SlidingWindowCounter { limit, window } => {
+                let config = SlidingWindowCounterConfig {
+                    capacity: limit,
+                    window,
+                };
+                Box::new(SlidingWindowCounterShared::from(
+                    config.build_with_clock(clock)?,
+                ))
+            }
+            Self::SlidingWindowLog { limit, window } => {
+                let config = SlidingWindowLogConfig {
+                    capacity: limit,
+                    window,
+                };
+                Box::new(SlidingWindowLogShared::from(
+                    config.build_with_clock(clock)?,
+                ))
+            }
+            Self::Gcra { rate, burst } => {
+                if burst == 0 {
+                    return Err(RateLimitError::InvalidConfig(
+                        "gcra burst must be greater than zero".to_string(),
+                    ));
+                }
+                let emission_interval = rate.interval();
+                let config = GcraConfig {
+                    emission_interval,
+                    burst_tolerance: emission_interval.saturating_mul(burst - 1),
+                };
+                Box::new(GcraShared::from(config.build_with_clock(clock)?))
+            }
+        })
+    }
+}
+
+/// Rates as `"{tokens}/{period}"`, the period count defaults to 1 as in `"10/s"`. A bare
+/// number means tokens per second.
+mod rate {
+    use std::fmt;
+
+    use ::serde::de::{self, Visitor};
+    use ::serde::{Deserializer, Serializer};
+
+    use crate::core::Rate;
+    use crate::serde::r#impl::secs;
+
+    pub(super) fn serialize<S: Serializer>(rate: &Rate, serializer: S) -> Result<S::Ok, S::Error> {
+        let period = secs::format(rate.period());
+        let period = period
+            .strip_prefix('1')
+            .filter(|unit| !unit.starts_with(|c: char| c.is_ascii_digit()))
+            .unwrap_or(&period);
+        serializer.serialize_str(&format!("{}/{period}", rate.tokens()))
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Rate, D::Error> {
+        deserializer.deserialize_any(RateVisitor)
+    }
+
+    struct RateVisitor;
+
+    impl Visitor<'_> for RateVisitor {
+        type Value = Rate;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a positive rate such as \"10/s\", \"100/m\" or \"1/500ms\"")
+        }
+
+        fn visit_str<E: de::Error>(self, text: &str) -> Result<Rate, E> {
+            let rate = text.split_once('/').and_then(|(tokens, period)| {
+                let tokens = tokens.trim().parse::<u32>().ok()?;
+                let period = period.trim();
+                let period = if period.starts_with(|c: char| c.is_ascii_digit()) {
+                    secs::parse(period)?
+                } else {
+                    secs::parse(&format!("1{period}"))?
+                };
+                Some(Rate::per(tokens, period))
+            });
+            match rate {
+                Some(rate) if rate.is_valid() => Ok(rate),
+                _ => Err(E::invalid_value(de::Unexpected::Str(text), &self)),
+            }
+        }
+
+        fn visit_u64<E: de::Error>(self, tokens: u64) -> Result<Rate, E> {
+            match u32::try_from(tokens) {
+                Ok(tokens) if tokens > 0 => Ok(Rate::per_second(tokens)),
+                _ => Err(E::invalid_value(de::Unexpected::Unsigned(tokens), &self)),
+            }
+        }
+
+        fn visit_i64<E: de::Error>(self, tokens: i64) -> Result<Rate, E> {
+            u64::try_from(tokens)
+                .map_err(|_| E::invalid_value(de::Unexpected::Signed(tokens), &self))
+                .and_then(|tokens| self.visit_u64(tokens))
+        }
+    }
+}
@@ -0,0 +1,2 @@
+mod policy_tests;
+mod serde_tests;
@@ -0,0 +1,164 @@
+#[cfg(test)]
+mod sequential_tests {
+    use crate::clock::MockClock;
+    use crate::core::{Rate, RateLimitError};
+    use crate::serde::{LimitPolicy, Policy};
+    use std::time::Duration;
+
+    const POLICY: &str = r#"
+        [limits.api]
+        algorithm = "token_bucket"
+        capacity = 100
+        rate = "10/s"
+
+        [limits.search]
+        algorithm = "sliding_window_log"
+        limit = 1000
+        window = "1m"
+
+        [limits.uploads]
+        algorithm = "leaky_bucket"
+        capacity = 5
+        rate = "1/500ms"
+
+        [limits.login]
+        algorithm = "fixed_window_counter"
+        limit = 3
+        window = 60
+
+        [limits.feed]
+        algorithm = "sliding_window_counter"
+        limit = 50
+        window = "1h"
+
+        [limits.webhooks]
+        algorithm = "gcra"
+        rate = "100/m"
+        burst = 10
+    "#;
+
+    /// A policy holding the limit `name` with the given JSON fields.
+    fn single(name: &str, fields: &str) -> String {
+        format!(r#"{{"limits": {{"{name}": {{{fields}}}}}}}"#)
+    }
+
+    #[test]
+    fn policy_parse_test() {
+        let policy: Policy = toml::from_str(POLICY).unwrap();
+        assert_eq!(policy.limits.len(), 6);
+        assert_eq!(
+            policy.limits["api"],
+            LimitPolicy::TokenBucket {
+                capacity: 100,
+                rate: Rate::per_second(10),
+                initial_tokens: None,
+            }
+        );
+        assert_eq!(
+            policy.limits["search"],
+            LimitPolicy::SlidingWindowLog {
+                limit: 1000,
+                window: Duration::from_secs(60),
+            }
+        );
+        assert_eq!(
+            policy.limits["uploads"],
+            LimitPolicy::LeakyBucket {
+                capacity: 5,
+                rate: Rate::per(1, Duration::from_millis(500)),
+            }
+        );
+    }
+
+    #[test]
+    fn policy_request_examples_test() {
+        let toml = r#"
+            api = {algorithm = "token_bucket", capacity = 100, rate = "10/s"}
+            search = {algorithm = "sliding_window_log", limit = 1000, window = "1m"}
+        "#;
+        let json = r#"{
+            "api": {"algorithm": "token_bucket", "capacity": 100, "rate": "10/s"},
+            "search": {"algorithm": "sliding_window_log", "limit": 1000, "window": "1m"}
+        }"#;
+        let from_toml: Policy = toml::from_str(&format!("[limits]\n{toml}")).unwrap();
+        let from_json: Policy = serde_json::from_str(&format!(r#"{{"limits": {json}}}"#)).unwrap();
+        assert_eq!(from_toml, from_json);
+
+        let limiters = from_json.build_with_clock(MockClock::new()).unwrap();
+        assert_eq!(limiters["api"].get_limit(), 100);
+        assert_eq!(limiters["search"].get_limit(), 1000);
+    }
+
+    #[test]
+    fn policy_roundtrip_test() {
+        let policy: Policy = toml::from_str(POLICY).unwrap();
+        let json = serde_json::to_value(&policy).unwrap();
+        assert_eq!(json["limits"]["api"]["rate"], "10/s");
+        assert_eq!(json["limits"]["uploads"]["rate"], "1/500ms");
+        assert_eq!(json["limits"]["webhooks"]["rate"], "100/m");
+        assert_eq!(json["limits"]["login"]["window"], 60);
+        assert_eq!(serde_json::from_value::<Policy>(json).unwrap(), policy);
+    }
+
+    #[test]
+    fn policy_build_test() {
+        let limiters = toml::from_str::<Policy>(POLICY)
+            .unwrap()
+            .build_with_clock(MockClock::new())
+            .unwrap();
+        assert_eq!(limiters["api"].get_limit(), 100);
+        assert_eq!(limiters["search"].get_limit(), 1000);
+        assert_eq!(limiters["uploads"].get_limit(), 5);
+        assert_eq!(limiters["feed"].get_limit(), 50);
+        assert_eq!(limiters["webhooks"].get_limit(), 10);
+
+        let login = &limiters["login"];
+        assert!(login.try_acquire(3));
+        assert!(!login.try_acquire(1));
+    }
+
+    #[test]
+    fn policy_capacity_alias_test() {
+        let fields = r#""algorithm": "sliding_window_counter", "capacity": 20, "window": 1"#;
+        let policy: Policy = serde_json::from_str(&single("feed", fields)).unwrap();
+        assert_eq!(
+            policy.limits["feed"],
+            LimitPolicy::SlidingWindowCounter {
+                limit: 20,
+                window: Duration::from_secs(1),
+            }
+        );
+    }
+
+    #[test]
+    fn policy_rejects_malformed_test() {
+        let cases = [
+            r#""algorithm": "token_bucket", "capacity": 1, "rate": "10/fortnight""#,
+            r#""algorithm": "token_bucket", "capacity": 1, "rate": "0/s""#,
+            r#""algorithm": "fixed_window_counter", "limit": 1, "window": "soon""#,
+            r#""algorithm": "fixed_window_counter", "limit": 1, "windw": "1m""#,
+            r#""algorithm": "bogus_bucket", "capacity": 1"#,
+        ];
+        for case in cases {
+            assert!(
+                serde_json::from_str::<Policy>(&single("a", case)).is_err(),
+                "{case}"
+            );
+        }
+    }
+
+    #[test]
+    fn policy_names_invalid_limit_test() {
+        let fields = r#""algorithm": "sliding_window_log", "limit": 0, "window": "1m""#;
+        let policy: Policy = serde_json::from_str(&single("empty", fields)).unwrap();
+        match policy.build() {
+            Err(RateLimitError::InvalidConfig(reason)) => {
+                assert!(reason.starts_with("limit \"empty\": "), "{reason}")
+            }
+            other => panic!(
+                "expected an invalid config error, got {:?}",
+                other.map(|_| ())
+            ),
+        }
+    }
+}
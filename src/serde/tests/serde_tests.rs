@@ -0,0 +1,182 @@
+#[cfg(test)]
+mod sequential_tests {
+    use crate::clock::MockClock;
+    use crate::core::{Rate, RateLimitError, RateLimiter};
+    use crate::fixed_window_counter::FixedWindowCounter;
+    use crate::gcra::Gcra;
+    use crate::leaky_bucket::LeakyBucket;
+    use crate::serde::{
+        FixedWindowCounterConfig, GcraConfig, LeakyBucketConfig, SlidingWindowLogConfig,
+        TokenBucketConfig, TokenBucketSnapshot,
+    };
+    use crate::sliding_window_counter::SlidingWindowCounter;
+    use crate::sliding_window_log::SlidingWindowLog;
+    use crate::token_bucket::TokenBucket;
+    use std::time::Duration;
+
+    #[test]
+    fn config_parse_test() {
+        let config: TokenBucketConfig =
+            serde_json::from_str(r#"{"capacity": 10, "refill_rate": 5}"#).unwrap();
+        assert_eq!(config.refill_period, Duration::from_secs(1));
+        assert_eq!(config.initial_tokens, None);
+
+        let config: GcraConfig =
+            serde_json::from_str(r#"{"emission_interval": 0.25, "burst_tolerance": 1}"#).unwrap();
+        assert_eq!(config.emission_interval, Duration::from_millis(250));
+        assert_eq!(config.burst_tolerance, Duration::from_secs(1));
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(json, r#"{"emission_interval":0.25,"burst_tolerance":1}"#);
+
+        let config: FixedWindowCounterConfig =
+            serde_json::from_str(r#"{"limit": 10, "window": "1m"}"#).unwrap();
+        assert_eq!(config.window, Duration::from_secs(60));
+        let config: GcraConfig =
+            serde_json::from_str(r#"{"emission_interval": "250ms", "burst_tolerance": "1h"}"#)
+                .unwrap();
+        assert_eq!(config.emission_interval, Duration::from_millis(250));
+        assert_eq!(config.burst_tolerance, Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn config_rejects_invalid_test() {
+        let typo = serde_json::from_str::<FixedWindowCounterConfig>(
+            r#"{"limit": 10, "window": 60, "windw": 1}"#,
+        );
+        assert!(typo.is_err());
+
+        let negative =
+            serde_json::from_str::<FixedWindowCounterConfig>(r#"{"limit": 10, "window": -1}"#);
+        assert!(negative.is_err());
+
+        for window in ["\"1fortnight\"", "\"m\"", "\"-1s\""] {
+            let json = format!(r#"{{"limit": 10, "window": {window}}}"#);
+            assert!(serde_json::from_str::<FixedWindowCounterConfig>(&json).is_err());
+        }
+
+        let config = LeakyBucketConfig {
+            capacity: 0,
+            leak_rate: 1.0,
+            initial_level: 0.0,
+        };
+        assert!(matches!(
+            config.build(),
+            Err(RateLimitError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn config_build_test() {
+        let config: SlidingWindowLogConfig =
+            serde_json::from_str(r#"{"capacity": 3, "window": 60}"#).unwrap();
+        let mut limiter = config.build_with_clock(MockClock::new()).unwrap();
+        assert_eq!(limiter.get_limit(), 3);
+        assert!(limiter.try_acquire(3));
+        assert!(!limiter.try_acquire(1));
+        assert_eq!(limiter.config(), config);
+    }
+
+    #[test]
+    fn snapshot_roundtrip_test() {
+        let clock = MockClock::new();
+        let mut bucket = TokenBucket::builder()
+            .capacity(10)
+            .rate(Rate::per_second(1))
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        clock.advance(Duration::from_secs(100));
+        assert!(bucket.try_acquire(7));
+
+        let json = serde_json::to_string(&bucket.snapshot()).unwrap();
+        let snapshot: TokenBucketSnapshot = serde_json::from_str(&json).unwrap();
+        let restored = TokenBucket::restore_with_clock(&snapshot, MockClock::new()).unwrap();
+        assert_eq!(restored.get_remaining(), 3);
+        assert_eq!(restored.get_limit(), 10);
+    }
+
+    #[test]
+    fn snapshot_accounts_for_downtime_test() {
+        let clock = MockClock::new();
+        let mut limiter = FixedWindowCounter::builder()
+            .limit(5)
+            .window(Duration::from_secs(60))
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        assert!(limiter.try_acquire(5));
+        let snapshot = limiter.snapshot();
+
+        let restarted = MockClock::new();
+        restarted.advance(Duration::from_secs(30));
+        let mut restored =
+            FixedWindowCounter::restore_with_clock(&snapshot, restarted.clone()).unwrap();
+        assert!(!restored.try_acquire(1));
+
+        restarted.advance(Duration::from_secs(31));
+        assert!(restored.try_acquire(5));
+    }
+
+    #[test]
+    fn snapshot_all_algorithms_test() {
+        let clock = MockClock::new();
+        let window = Duration::from_secs(60);
+
+        let mut leaky = LeakyBucket::builder()
+            .capacity(10)
+            .leak_rate(0.001)
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        assert!(leaky.try_acquire(4));
+        let restored = LeakyBucket::restore_with_clock(&leaky.snapshot(), MockClock::new());
+        assert_eq!(restored.unwrap().get_remaining(), 6);
+
+        let mut counter = SlidingWindowCounter::builder()
+            .capacity(10)
+            .window(window)
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        assert!(counter.try_acquire(4));
+        let restored = SlidingWindowCounter::restore_with_clock(&counter.snapshot(), clock.clone());
+        assert_eq!(restored.unwrap().get_remaining(), 6);
+
+        let mut log = SlidingWindowLog::builder()
+            .capacity(10)
+            .window(window)
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        assert!(log.try_acquire(4));
+        let restored = SlidingWindowLog::restore_with_clock(&log.snapshot(), MockClock::new());
+        assert_eq!(restored.unwrap().get_remaining(), 6);
+
+        let mut gcra = Gcra::builder()
+            .emission_interval(Duration::from_secs(10))
+            .burst_tolerance(Duration::from_secs(90))
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        assert!(gcra.try_acquire(4));
+        let restored = Gcra::restore_with_clock(&gcra.snapshot(), MockClock::new()).unwrap();
+        assert_eq!(restored.get_remaining(), gcra.get_remaining());
+    }
+
+    #[test]
+    fn restore_rejects_invalid_state_test() {
+        let bucket = TokenBucket::builder()
+            .capacity(10)
+            .rate(Rate::per_second(1))
+            .clock(MockClock::new())
+            .build()
+            .unwrap();
+        let mut snapshot = bucket.snapshot();
+        snapshot.state.tokens = 11;
+        assert!(matches!(
+            TokenBucket::restore_with_clock(&snapshot, MockClock::new()),
+            Err(RateLimitError::InvalidConfig(_))
+        ));
+    }
+}
@@ -1,4 +1,11 @@
+pub mod exact;
 pub mod r#impl;
+pub mod state;
 pub mod tests;
 
-pub use r#impl::{SlidingWindowCounter, SlidingWindowCounterShared};
+pub use crate::core::{Decision, RateLimitError, RateLimiter, RateLimiterShared};
+pub use exact::{
+    SlidingWindowCounterExact, SlidingWindowCounterExactBuilder, SlidingWindowCounterExactShared,
+};
+pub use r#impl::{SlidingWindowCounter, SlidingWindowCounterBuilder, SlidingWindowCounterShared};
+pub use state::{SlidingWindowCounterAlgorithm, SlidingWindowCounterState};
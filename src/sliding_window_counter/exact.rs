@@ -0,0 +1,296 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::clock::{Clock, SystemClock};
+use crate::core::{
+    as_millis, unix_time_after, Decision, RateLimitError, RateLimiter, RateLimiterShared,
+};
+
+/// *** SLIDING WINDOW COUNTER EXACT ***
+/// Keeps one timestamp per acquired token, so memory grows with the capacity.
+/// Prefer `SlidingWindowCounter` unless the exact count matters.
+pub struct SlidingWindowCounterExact<C = SystemClock> {
+    capacity: u32,
+    window: Duration,
+    events: VecDeque<Instant>,
+    clock: C,
+}
+
+impl SlidingWindowCounterExact {
+    pub fn new(capacity: u32, window_secs: u64) -> Self {
+        Self::with_clock(capacity, window_secs, SystemClock)
+    }
+
+    pub fn try_new(capacity: u32, window_secs: u64) -> Result<Self, RateLimitError> {
+        Self::builder()
+            .capacity(capacity)
+            .window(Duration::from_secs(window_secs))
+            .build()
+    }
+
+    pub fn with_window(capacity: u32, window: Duration) -> Self {
+        Self::with_window_and_clock(capacity, window, SystemClock)
+    }
+
+    pub fn builder() -> SlidingWindowCounterExactBuilder {
+        SlidingWindowCounterExactBuilder::new()
+    }
+}
+
+impl<C: Clock> SlidingWindowCounterExact<C> {
+    pub fn with_clock(capacity: u32, window_secs: u64, clock: C) -> Self {
+        Self::with_window_and_clock(capacity, Duration::from_secs(window_secs), clock)
+    }
+
+    pub fn with_window_and_clock(capacity: u32, window: Duration, clock: C) -> Self {
+        Self {
+            capacity,
+            window,
+            events: VecDeque::new(),
+            clock,
+        }
+    }
+
+    /// Time until the oldest event in the window expires.
+    fn reset_after(&self) -> Duration {
+        match self.events.front() {
+            Some(&first) => self.expires_in(first),
+            None => Duration::ZERO,
+        }
+    }
+
+    /// Time until an event recorded at `at` leaves the window, without adding `window` to an
+    /// `Instant` so huge windows don't overflow.
+    fn expires_in(&self, at: Instant) -> Duration {
+        let elapsed = self.clock.now().saturating_duration_since(at);
+        self.window.saturating_sub(elapsed)
+    }
+
+    fn purge_old(&mut self) {
+        let now = self.clock.now();
+        while let Some(&front) = self.events.front() {
+            if now.saturating_duration_since(front) >= self.window {
+                self.events.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+// *** SLIDING WINDOW COUNTER EXACT BUILDER ***
+pub struct SlidingWindowCounterExactBuilder<C = SystemClock> {
+    capacity: u32,
+    window: Duration,
+    clock: C,
+}
+
+impl SlidingWindowCounterExactBuilder {
+    pub fn new() -> Self {
+        Self {
+            capacity: 0,
+            window: Duration::ZERO,
+            clock: SystemClock,
+        }
+    }
+}
+
+impl Default for SlidingWindowCounterExactBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Clock> SlidingWindowCounterExactBuilder<C> {
+    pub fn capacity(mut self, capacity: u32) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    pub fn clock<D: Clock>(self, clock: D) -> SlidingWindowCounterExactBuilder<D> {
+        SlidingWindowCounterExactBuilder {
+            capacity: self.capacity,
+            window: self.window,
+            clock,
+        }
+    }
+
+    pub fn build(self) -> Result<SlidingWindowCounterExact<C>, RateLimitError> {
+        if self.capacity == 0 {
+            return Err(RateLimitError::InvalidConfig(
+                "exact sliding window counter capacity must be greater than zero".to_string(),
+            ));
+        }
+        if self.window.is_zero() {
+            return Err(RateLimitError::InvalidConfig(
+                "exact sliding window counter window must be greater than zero".to_string(),
+            ));
+        }
+
+        Ok(SlidingWindowCounterExact::with_window_and_clock(
+            self.capacity,
+            self.window,
+            self.clock,
+        ))
+    }
+
+    pub fn build_shared(self) -> Result<SlidingWindowCounterExactShared<C>, RateLimitError> {
+        self.build().map(SlidingWindowCounterExactShared::from)
+    }
+}
+
+impl<C: Clock> RateLimiter for SlidingWindowCounterExact<C> {
+    fn refresh(&mut self) {
+        self.purge_old();
+    }
+
+    fn try_acquire(&mut self, tokens: u32) -> bool {
+        self.refresh();
+        if (self.events.len() as u32 + tokens) <= self.capacity {
+            let now = self.clock.now();
+            for _ in 0..tokens {
+                self.events.push_back(now);
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    fn get_limit(&self) -> u32 {
+        self.capacity
+    }
+
+    fn get_remaining(&self) -> u32 {
+        if (self.events.len() as u32) >= self.capacity {
+            0
+        } else {
+            self.capacity - self.events.len() as u32
+        }
+    }
+
+    fn get_used(&self) -> u32 {
+        self.events.len() as u32
+    }
+
+    fn get_reset(&self) -> u64 {
+        unix_time_after(self.clock.now_system(), self.reset_after()).as_secs()
+    }
+
+    fn get_reset_ms(&self) -> u64 {
+        as_millis(unix_time_after(self.clock.now_system(), self.reset_after()))
+    }
+
+    fn get_retry_after(&self, tokens: u32) -> Duration {
+        if tokens > self.capacity {
+            return Duration::MAX;
+        }
+        let overflow = (self.events.len() + tokens as usize).saturating_sub(self.capacity as usize);
+        if overflow == 0 {
+            return Duration::ZERO;
+        }
+
+        // The request fits once the `overflow` oldest events have expired
+        self.expires_in(self.events[overflow - 1])
+    }
+}
+
+/// *** SLIDING WINDOW COUNTER EXACT SHARED ***
+pub struct SlidingWindowCounterExactShared<C = SystemClock> {
+    inner: Arc<Mutex<SlidingWindowCounterExact<C>>>,
+}
+
+impl SlidingWindowCounterExactShared {
+    pub fn new(capacity: u32, window_secs: u64) -> Self {
+        Self::with_clock(capacity, window_secs, SystemClock)
+    }
+
+    pub fn try_new(capacity: u32, window_secs: u64) -> Result<Self, RateLimitError> {
+        SlidingWindowCounterExact::try_new(capacity, window_secs).map(Self::from)
+    }
+
+    pub fn with_window(capacity: u32, window: Duration) -> Self {
+        Self::from(SlidingWindowCounterExact::with_window(capacity, window))
+    }
+}
+
+impl<C: Clock> SlidingWindowCounterExactShared<C> {
+    pub fn with_clock(capacity: u32, window_secs: u64, clock: C) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(SlidingWindowCounterExact::with_clock(
+                capacity,
+                window_secs,
+                clock,
+            ))),
+        }
+    }
+}
+
+impl<C> From<SlidingWindowCounterExact<C>> for SlidingWindowCounterExactShared<C> {
+    fn from(limiter: SlidingWindowCounterExact<C>) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(limiter)),
+        }
+    }
+}
+
+impl<C: Clock> RateLimiterShared for SlidingWindowCounterExactShared<C> {
+    fn refresh(&self) {
+        let mut inner = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        inner.refresh()
+    }
+
+    fn try_acquire(&self, tokens: u32) -> bool {
+        let mut inner = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        inner.try_acquire(tokens)
+    }
+
+    fn try_acquire_decision(&self, tokens: u32) -> Decision {
+        let mut inner = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        inner.try_acquire_decision(tokens)
+    }
+
+    fn try_acquire_checked(&self, tokens: u32) -> Result<Decision, RateLimitError> {
+        let mut inner = self
+            .inner
+            .lock()
+            .map_err(|_| RateLimitError::PoisonedLock)?;
+        inner.try_acquire_checked(tokens)
+    }
+
+    fn get_limit(&self) -> u32 {
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        bucket.get_limit()
+    }
+
+    fn get_remaining(&self) -> u32 {
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        bucket.get_remaining()
+    }
+
+    fn get_used(&self) -> u32 {
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        bucket.get_used()
+    }
+
+    fn get_reset(&self) -> u64 {
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        bucket.get_reset()
+    }
+
+    fn get_reset_ms(&self) -> u64 {
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        bucket.get_reset_ms()
+    }
+
+    fn get_retry_after(&self, tokens: u32) -> Duration {
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        bucket.get_retry_after(tokens)
+    }
+}
@@ -1,126 +1,263 @@
-use std::collections::VecDeque;
-use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
 
-use crate::token_bucket::r#impl::{RateLimiter, RateLimiterShared};
+use crate::clock::{Clock, Epoch, SystemClock};
+use crate::core::{
+    as_millis, unix_time_after, Decision, RateLimitError, RateLimiter, RateLimiterShared,
+};
+use crate::sliding_window_counter::{SlidingWindowCounterAlgorithm, SlidingWindowCounterState};
+use crate::store::Algorithm;
 
 /// *** SLIDING WINDOW COUNTER ***
-pub struct SlidingWindowCounter {
-    capacity: u32,
-    window: Duration,
-    events: VecDeque<Instant>,
+/// Counts tokens in the current and the previous fixed window and estimates the sliding
+/// window usage as `previous * (1 - elapsed / window) + current`, so memory stays constant
+/// regardless of the capacity. See `SlidingWindowCounterExact` for an exact, per-token log.
+pub struct SlidingWindowCounter<C = SystemClock> {
+    pub(crate) algorithm: SlidingWindowCounterAlgorithm,
+    pub(crate) state: SlidingWindowCounterState,
+    epoch: Epoch,
+    pub(crate) clock: C,
 }
 
 impl SlidingWindowCounter {
     pub fn new(capacity: u32, window_secs: u64) -> Self {
+        Self::with_clock(capacity, window_secs, SystemClock)
+    }
+
+    pub fn try_new(capacity: u32, window_secs: u64) -> Result<Self, RateLimitError> {
+        Self::builder()
+            .capacity(capacity)
+            .window(Duration::from_secs(window_secs))
+            .build()
+    }
+
+    pub fn with_window(capacity: u32, window: Duration) -> Self {
+        Self::with_window_and_clock(capacity, window, SystemClock)
+    }
+
+    pub fn builder() -> SlidingWindowCounterBuilder {
+        SlidingWindowCounterBuilder::new()
+    }
+}
+
+impl<C: Clock> SlidingWindowCounter<C> {
+    pub fn with_clock(capacity: u32, window_secs: u64, clock: C) -> Self {
+        Self::with_window_and_clock(capacity, Duration::from_secs(window_secs), clock)
+    }
+
+    pub fn with_window_and_clock(capacity: u32, window: Duration, clock: C) -> Self {
+        let algorithm = SlidingWindowCounterAlgorithm::new(capacity, window);
+        let epoch = Epoch::new(&clock);
+        Self {
+            algorithm,
+            state: algorithm.initial_state(epoch.now(&clock)),
+            epoch,
+            clock,
+        }
+    }
+
+    pub(crate) fn now(&self) -> Duration {
+        self.epoch.now(&self.clock)
+    }
+
+    /// Time until both windows no longer weigh anything.
+    fn reset_after(&self) -> Duration {
+        self.algorithm.reset_after(&self.state, self.now())
+    }
+}
+
+// *** SLIDING WINDOW COUNTER BUILDER ***
+pub struct SlidingWindowCounterBuilder<C = SystemClock> {
+    capacity: u32,
+    window: Duration,
+    clock: C,
+}
+
+impl SlidingWindowCounterBuilder {
+    pub fn new() -> Self {
         Self {
-            capacity,
-            window: Duration::from_secs(window_secs),
-            events: VecDeque::new(),
+            capacity: 0,
+            window: Duration::ZERO,
+            clock: SystemClock,
+        }
+    }
+}
+
+impl Default for SlidingWindowCounterBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Clock> SlidingWindowCounterBuilder<C> {
+    pub fn capacity(mut self, capacity: u32) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    pub fn clock<D: Clock>(self, clock: D) -> SlidingWindowCounterBuilder<D> {
+        SlidingWindowCounterBuilder {
+            capacity: self.capacity,
+            window: self.window,
+            clock,
         }
     }
 
-    fn purge_old(&mut self) {
-        let now = Instant::now();
-        while let Some(&front) = self.events.front() {
-            if now.duration_since(front) > self.window {
-                self.events.pop_front();
-            } else {
-                break;
-            }
+    pub fn build(self) -> Result<SlidingWindowCounter<C>, RateLimitError> {
+        if self.capacity == 0 {
+            return Err(RateLimitError::InvalidConfig(
+                "sliding window counter capacity must be greater than zero".to_string(),
+            ));
         }
+        if self.window.is_zero() {
+            return Err(RateLimitError::InvalidConfig(
+                "sliding window counter window must be greater than zero".to_string(),
+            ));
+        }
+
+        Ok(SlidingWindowCounter::with_window_and_clock(
+            self.capacity,
+            self.window,
+            self.clock,
+        ))
+    }
+
+    pub fn build_shared(self) -> Result<SlidingWindowCounterShared<C>, RateLimitError> {
+        self.build().map(SlidingWindowCounterShared::from)
     }
 }
 
-impl RateLimiter for SlidingWindowCounter {
+impl<C: Clock> RateLimiter for SlidingWindowCounter<C> {
     fn refresh(&mut self) {
-        self.purge_old();
+        let now = self.now();
+        self.algorithm.refresh(&mut self.state, now);
     }
 
     fn try_acquire(&mut self, tokens: u32) -> bool {
-        self.refresh();
-        if (self.events.len() as u32 + tokens) <= self.capacity {
-            let now = Instant::now();
-            for _ in 0..tokens {
-                self.events.push_back(now);
-            }
-            true
-        } else {
-            false
-        }
+        let now = self.now();
+        self.algorithm.try_acquire(&mut self.state, now, tokens)
     }
 
     fn get_limit(&self) -> u32 {
-        self.capacity
+        self.algorithm.limit()
     }
 
     fn get_remaining(&self) -> u32 {
-        if (self.events.len() as u32) >= self.capacity {
-            0
-        } else {
-            self.capacity - self.events.len() as u32
-        }
+        self.algorithm.remaining(&self.state, self.now())
     }
 
     fn get_used(&self) -> u32 {
-        self.events.len() as u32
+        self.algorithm.used(&self.state, self.now())
     }
 
     fn get_reset(&self) -> u64 {
-        if let Some(&first) = self.events.front() {
-            let expire = first + self.window;
-            let reset_time = SystemTime::now() + expire.saturating_duration_since(Instant::now());
-            reset_time.duration_since(UNIX_EPOCH).unwrap().as_secs()
-        } else {
-            SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs()
-        }
+        unix_time_after(self.clock.now_system(), self.reset_after()).as_secs()
+    }
+
+    fn get_reset_ms(&self) -> u64 {
+        as_millis(unix_time_after(self.clock.now_system(), self.reset_after()))
+    }
+
+    fn get_retry_after(&self, tokens: u32) -> Duration {
+        self.algorithm.retry_after(&self.state, self.now(), tokens)
     }
 }
 
 /// *** SLIDING WINDOW COUNTER SHARED ***
-pub struct SlidingWindowCounterShared {
-    inner: Arc<Mutex<SlidingWindowCounter>>,
+pub struct SlidingWindowCounterShared<C = SystemClock> {
+    pub(crate) inner: Arc<Mutex<SlidingWindowCounter<C>>>,
 }
 
 impl SlidingWindowCounterShared {
     pub fn new(capacity: u32, window_secs: u64) -> Self {
+        Self::with_clock(capacity, window_secs, SystemClock)
+    }
+
+    pub fn try_new(capacity: u32, window_secs: u64) -> Result<Self, RateLimitError> {
+        SlidingWindowCounter::try_new(capacity, window_secs).map(Self::from)
+    }
+
+    pub fn with_window(capacity: u32, window: Duration) -> Self {
+        Self::from(SlidingWindowCounter::with_window(capacity, window))
+    }
+}
+
+impl<C: Clock> SlidingWindowCounterShared<C> {
+    pub fn with_clock(capacity: u32, window_secs: u64, clock: C) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(SlidingWindowCounter::with_clock(
+                capacity,
+                window_secs,
+                clock,
+            ))),
+        }
+    }
+}
+
+impl<C> From<SlidingWindowCounter<C>> for SlidingWindowCounterShared<C> {
+    fn from(limiter: SlidingWindowCounter<C>) -> Self {
         Self {
-            inner: Arc::new(Mutex::new(SlidingWindowCounter::new(capacity, window_secs))),
+            inner: Arc::new(Mutex::new(limiter)),
         }
     }
 }
 
-impl RateLimiterShared for SlidingWindowCounterShared {
+impl<C: Clock> RateLimiterShared for SlidingWindowCounterShared<C> {
     fn refresh(&self) {
-        let mut inner = self.inner.lock().unwrap();
+        let mut inner = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         inner.refresh()
     }
 
     fn try_acquire(&self, tokens: u32) -> bool {
-        let mut inner = self.inner.lock().unwrap();
+        let mut inner = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         inner.try_acquire(tokens)
     }
 
+    fn try_acquire_decision(&self, tokens: u32) -> Decision {
+        let mut inner = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        inner.try_acquire_decision(tokens)
+    }
+
+    fn try_acquire_checked(&self, tokens: u32) -> Result<Decision, RateLimitError> {
+        let mut inner = self
+            .inner
+            .lock()
+            .map_err(|_| RateLimitError::PoisonedLock)?;
+        inner.try_acquire_checked(tokens)
+    }
+
     fn get_limit(&self) -> u32 {
-        let bucket = self.inner.lock().unwrap();
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         bucket.get_limit()
     }
 
     fn get_remaining(&self) -> u32 {
-        let bucket = self.inner.lock().unwrap();
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         bucket.get_remaining()
     }
 
     fn get_used(&self) -> u32 {
-        let bucket = self.inner.lock().unwrap();
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         bucket.get_used()
     }
 
     fn get_reset(&self) -> u64 {
-        let bucket = self.inner.lock().unwrap();
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         bucket.get_reset()
     }
+
+    fn get_reset_ms(&self) -> u64 {
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        bucket.get_reset_ms()
+    }
+
+    fn get_retry_after(&self, tokens: u32) -> Duration {
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        bucket.get_retry_after(tokens)
+    }
 }
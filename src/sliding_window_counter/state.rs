@@ -0,0 +1,181 @@
+use std::time::Duration;
+
+use crate::store::r#impl::{Algorithm, StateReader, StateWriter};
+
+const TAG: u8 = 4;
+
+// *** SLIDING WINDOW COUNTER STATE ***
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct SlidingWindowCounterState {
+    pub window_start: Duration,
+    pub previous: u32,
+    pub current: u32,
+}
+
+// *** SLIDING WINDOW COUNTER ALGORITHM ***
+/// Sliding window counter transitions, see `SlidingWindowCounter` for a limiter holding its
+/// own state. Reads are always up to date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlidingWindowCounterAlgorithm {
+    capacity: u32,
+    window: Duration,
+}
+
+impl SlidingWindowCounterAlgorithm {
+    pub fn new(capacity: u32, window: Duration) -> Self {
+        Self { capacity, window }
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// `state` as it would be after rolling the windows to `now`.
+    fn windows_at(
+        &self,
+        state: &SlidingWindowCounterState,
+        now: Duration,
+    ) -> SlidingWindowCounterState {
+        let elapsed = now.saturating_sub(state.window_start);
+        if self.window.is_zero() || elapsed < self.window {
+            return *state;
+        }
+
+        let passed = elapsed.as_nanos() / self.window.as_nanos();
+        let window_start = state.window_start.saturating_add(self.window_span(passed));
+        let previous = if passed == 1 { state.current } else { 0 };
+        SlidingWindowCounterState {
+            window_start,
+            previous,
+            current: 0,
+        }
+    }
+
+    fn window_span(&self, windows: u128) -> Duration {
+        let nanos = windows * self.window.as_nanos();
+        Duration::from_nanos(nanos.min(u64::MAX as u128) as u64)
+    }
+
+    /// Share of `previous` still inside the sliding window, rounded up.
+    fn weighted_previous(&self, previous: u32, elapsed: Duration) -> u32 {
+        let window = self.window.as_nanos();
+        let left = window.saturating_sub(elapsed.as_nanos());
+        if window == 0 {
+            return 0;
+        }
+        (previous as u128 * left).div_ceil(window) as u32
+    }
+
+    /// Time into the window after which `previous` weighs at most `budget`.
+    fn elapsed_for_budget(&self, previous: u32, budget: u32) -> Duration {
+        if budget >= previous {
+            return Duration::ZERO;
+        }
+        let window = self.window.as_nanos();
+        let allowed = budget as u128 * window / previous as u128;
+        Duration::from_nanos((window - allowed).min(u64::MAX as u128) as u64)
+    }
+}
+
+impl Algorithm for SlidingWindowCounterAlgorithm {
+    type State = SlidingWindowCounterState;
+
+    fn limit(&self) -> u32 {
+        self.capacity
+    }
+
+    fn initial_state(&self, now: Duration) -> SlidingWindowCounterState {
+        SlidingWindowCounterState {
+            window_start: now,
+            previous: 0,
+            current: 0,
+        }
+    }
+
+    fn refresh(&self, state: &mut SlidingWindowCounterState, now: Duration) {
+        *state = self.windows_at(state, now);
+    }
+
+    fn try_acquire(
+        &self,
+        state: &mut SlidingWindowCounterState,
+        now: Duration,
+        tokens: u32,
+    ) -> bool {
+        self.refresh(state, now);
+        if self.used(state, now).saturating_add(tokens) <= self.capacity {
+            state.current += tokens;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn remaining(&self, state: &SlidingWindowCounterState, now: Duration) -> u32 {
+        self.capacity.saturating_sub(self.used(state, now))
+    }
+
+    fn used(&self, state: &SlidingWindowCounterState, now: Duration) -> u32 {
+        let state = self.windows_at(state, now);
+        let elapsed = now.saturating_sub(state.window_start);
+        self.weighted_previous(state.previous, elapsed)
+            .saturating_add(state.current)
+    }
+
+    /// Time until both windows no longer weigh anything.
+    fn reset_after(&self, state: &SlidingWindowCounterState, now: Duration) -> Duration {
+        let state = self.windows_at(state, now);
+        let elapsed = now.saturating_sub(state.window_start);
+        let window_left = self.window.saturating_sub(elapsed);
+        if state.current > 0 {
+            window_left.saturating_add(self.window)
+        } else if state.previous > 0 {
+            window_left
+        } else {
+            Duration::ZERO
+        }
+    }
+
+    fn retry_after(
+        &self,
+        state: &SlidingWindowCounterState,
+        now: Duration,
+        tokens: u32,
+    ) -> Duration {
+        if tokens > self.capacity {
+            return Duration::MAX;
+        }
+        let state = self.windows_at(state, now);
+        let elapsed = now.saturating_sub(state.window_start);
+
+        if tokens <= self.capacity - state.current {
+            // Fits in this window once enough of the previous one has slid out
+            let budget = self.capacity - state.current - tokens;
+            self.elapsed_for_budget(state.previous, budget)
+                .saturating_sub(elapsed)
+        } else {
+            // Has to wait for the next window, where `current` becomes the previous one
+            let needed = self.elapsed_for_budget(state.current, self.capacity - tokens);
+            self.window.saturating_sub(elapsed).saturating_add(needed)
+        }
+    }
+
+    fn encode(&self, state: &SlidingWindowCounterState) -> Vec<u8> {
+        StateWriter::new(TAG)
+            .duration(state.window_start)
+            .u32(state.previous)
+            .u32(state.current)
+            .finish()
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Option<SlidingWindowCounterState> {
+        let mut reader = StateReader::new(bytes, TAG)?;
+        let state = SlidingWindowCounterState {
+            window_start: reader.duration()?,
+            previous: reader.u32()?.min(self.capacity),
+            current: reader.u32()?.min(self.capacity),
+        };
+        reader.is_empty().then_some(state)
+    }
+}
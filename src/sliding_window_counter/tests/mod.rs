@@ -1,2 +1,4 @@
+mod sliding_window_counter_exact_shared_tests;
+mod sliding_window_counter_exact_tests;
 mod sliding_window_counter_shared_tests;
 mod sliding_window_counter_tests;
@@ -0,0 +1,191 @@
+#[cfg(test)]
+mod sequential_tests {
+    use crate::clock::{Clock, MockClock};
+    use crate::core::{RateLimitError, RateLimiter};
+    use crate::sliding_window_counter::SlidingWindowCounterExact;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn basic_test() {
+        let now_unix = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let mut bucket = SlidingWindowCounterExact::new(10, 2);
+        assert_eq!(bucket.get_limit(), 10);
+        assert_eq!(bucket.get_remaining(), 10);
+        assert_eq!(bucket.get_used(), 0);
+        assert!(bucket.get_reset() >= now_unix);
+
+        assert!(bucket.try_acquire(5));
+        assert_eq!(bucket.get_limit(), 10);
+        assert_eq!(bucket.get_remaining(), 5);
+        assert_eq!(bucket.get_used(), 5);
+        let diff = bucket.get_reset() - now_unix;
+        assert_eq!(diff, 2);
+
+        assert!(bucket.try_acquire(5));
+        assert_eq!(bucket.get_limit(), 10);
+        assert_eq!(bucket.get_remaining(), 0);
+        assert_eq!(bucket.get_used(), 10);
+        let diff = bucket.get_reset() - now_unix;
+        assert_eq!(diff, 2);
+
+        assert!(!bucket.try_acquire(1));
+        assert_eq!(bucket.get_limit(), 10);
+        assert_eq!(bucket.get_remaining(), 0);
+        assert_eq!(bucket.get_used(), 10);
+        let diff = bucket.get_reset() - now_unix;
+        assert_eq!(diff, 2);
+
+        thread::sleep(Duration::from_secs(1));
+        bucket.refresh(); // <-- Call refresh to update details w/ try_acquire call
+        assert_eq!(bucket.get_limit(), 10);
+        assert_eq!(bucket.get_remaining(), 0);
+        assert_eq!(bucket.get_used(), 10);
+        let diff = bucket.get_reset() - now_unix;
+        assert_eq!(diff, 2);
+
+        thread::sleep(Duration::from_secs(1));
+        bucket.refresh(); // <-- Call refresh to update details w/ try_acquire call
+        assert_eq!(bucket.get_limit(), 10);
+        assert_eq!(bucket.get_remaining(), 10);
+        assert_eq!(bucket.get_used(), 0);
+        let diff = bucket.get_reset() - now_unix;
+        assert_eq!(diff, 2);
+    }
+
+    #[test]
+    fn decision_test() {
+        let mut bucket = SlidingWindowCounterExact::new(10, 2);
+
+        let decision = bucket.try_acquire_decision(8);
+        assert!(decision.allowed);
+        assert_eq!(decision.limit, 10);
+        assert_eq!(decision.remaining, 2);
+        assert_eq!(decision.retry_after, Duration::ZERO);
+
+        let decision = bucket.try_acquire_decision(4);
+        assert!(!decision.allowed);
+        assert_eq!(decision.remaining, 2);
+        assert!(decision.retry_after >= Duration::from_millis(1900));
+        assert!(decision.retry_after <= Duration::from_secs(2));
+
+        let decision = bucket.try_acquire_decision(11);
+        assert!(!decision.allowed);
+        assert_eq!(decision.retry_after, Duration::MAX);
+    }
+
+    #[test]
+    fn mock_clock_test() {
+        let clock = MockClock::new();
+        let mut bucket = SlidingWindowCounterExact::with_clock(10, 2, clock.clone());
+
+        assert!(bucket.try_acquire(10));
+        assert!(!bucket.try_acquire(1));
+
+        clock.advance(Duration::from_millis(1999));
+        assert!(!bucket.try_acquire(1));
+
+        clock.advance(Duration::from_millis(1));
+        bucket.refresh();
+        assert_eq!(bucket.get_remaining(), 10);
+        assert_eq!(bucket.get_used(), 0);
+    }
+
+    #[test]
+    fn builder_test() {
+        assert!(matches!(
+            SlidingWindowCounterExact::try_new(10, 0),
+            Err(RateLimitError::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            SlidingWindowCounterExact::try_new(0, 1),
+            Err(RateLimitError::InvalidConfig(_))
+        ));
+
+        let clock = MockClock::new();
+        let mut bucket = SlidingWindowCounterExact::builder()
+            .capacity(10)
+            .window(Duration::from_millis(500))
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        assert!(bucket.try_acquire(10));
+        assert!(!bucket.try_acquire(1));
+
+        clock.advance(Duration::from_millis(501));
+        assert!(bucket.try_acquire(10));
+    }
+
+    #[test]
+    fn sub_second_window_test() {
+        let clock = MockClock::new();
+        let mut bucket = SlidingWindowCounterExact::builder()
+            .capacity(20)
+            .window(Duration::from_millis(100))
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        let start_ms = clock
+            .now_system()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+
+        assert!(bucket.try_acquire(20));
+        assert!(!bucket.try_acquire(1));
+        assert_eq!(bucket.get_reset_ms() - start_ms, 100);
+
+        clock.advance(Duration::from_millis(60));
+        assert!(!bucket.try_acquire(1));
+        assert_eq!(bucket.get_retry_after(1), Duration::from_millis(40));
+        assert_eq!(bucket.get_reset_ms() - start_ms, 100);
+
+        clock.advance(Duration::from_millis(41));
+        assert!(bucket.try_acquire(20));
+    }
+
+    #[test]
+    fn retry_after_is_enough_test() {
+        let clock = MockClock::new();
+        let mut bucket = SlidingWindowCounterExact::builder()
+            .capacity(5)
+            .window(Duration::from_millis(300))
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+
+        for step in 0..50u64 {
+            clock.advance(Duration::from_millis(step * 37 % 90));
+
+            let tokens = 1 + (step % 5) as u32;
+            let decision = bucket.try_acquire_decision(tokens);
+            if !decision.allowed {
+                clock.advance(decision.retry_after);
+                assert!(bucket.try_acquire(tokens), "step {step}");
+            }
+        }
+    }
+
+    #[test]
+    fn huge_window_test() {
+        let clock = MockClock::new();
+        let mut bucket = SlidingWindowCounterExact::builder()
+            .capacity(2)
+            .window(Duration::MAX)
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+
+        assert!(bucket.try_acquire(2));
+        clock.advance(Duration::from_secs(1));
+        let decision = bucket.try_acquire_decision(1);
+        assert!(!decision.allowed);
+        assert!(decision.retry_after > Duration::from_secs(u64::from(u32::MAX)));
+        assert!(bucket.get_retry_after(2) > Duration::from_secs(u64::from(u32::MAX)));
+        assert!(bucket.get_reset() > u64::from(u32::MAX));
+    }
+}
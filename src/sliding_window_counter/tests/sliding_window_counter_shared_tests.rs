@@ -1,66 +1,36 @@
 #[cfg(test)]
 mod sequential_tests {
+    use crate::clock::MockClock;
+    use crate::core::RateLimiterShared;
     use crate::sliding_window_counter::SlidingWindowCounterShared;
-    use crate::token_bucket::r#impl::RateLimiterShared;
-    use std::thread;
     use std::time::Duration;
 
     #[test]
     fn basic_test() {
-        let now_unix = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
-        let bucket = SlidingWindowCounterShared::new(10, 2);
+        let clock = MockClock::new();
+        let bucket = SlidingWindowCounterShared::with_clock(10, 2, clock.clone());
         assert_eq!(bucket.get_limit(), 10);
         assert_eq!(bucket.get_remaining(), 10);
         assert_eq!(bucket.get_used(), 0);
-        assert!(bucket.get_reset() >= now_unix);
-
-        assert!(bucket.try_acquire(5));
-        assert_eq!(bucket.get_limit(), 10);
-        assert_eq!(bucket.get_remaining(), 5);
-        assert_eq!(bucket.get_used(), 5);
-        let diff = bucket.get_reset() - now_unix;
-        assert_eq!(diff, 2);
 
-        assert!(bucket.try_acquire(5));
-        assert_eq!(bucket.get_limit(), 10);
+        assert!(bucket.try_acquire(10));
         assert_eq!(bucket.get_remaining(), 0);
         assert_eq!(bucket.get_used(), 10);
-        let diff = bucket.get_reset() - now_unix;
-        assert_eq!(diff, 2);
-
         assert!(!bucket.try_acquire(1));
-        assert_eq!(bucket.get_limit(), 10);
-        assert_eq!(bucket.get_remaining(), 0);
-        assert_eq!(bucket.get_used(), 10);
-        let diff = bucket.get_reset() - now_unix;
-        assert_eq!(diff, 2);
 
-        thread::sleep(Duration::from_secs(1));
-        bucket.refresh(); // <-- Call refresh to update details w/ try_acquire call
-        assert_eq!(bucket.get_limit(), 10);
-        assert_eq!(bucket.get_remaining(), 0);
-        assert_eq!(bucket.get_used(), 10);
-        let diff = bucket.get_reset() - now_unix;
-        assert_eq!(diff, 2);
-
-        thread::sleep(Duration::from_secs(1));
-        bucket.refresh(); // <-- Call refresh to update details w/ try_acquire call
-        assert_eq!(bucket.get_limit(), 10);
-        assert_eq!(bucket.get_remaining(), 10);
-        assert_eq!(bucket.get_used(), 0);
-        let diff = bucket.get_reset() - now_unix;
-        assert_eq!(diff, 2);
+        clock.advance(Duration::from_secs(3));
+        bucket.refresh();
+        assert_eq!(bucket.get_used(), 5);
+        assert_eq!(bucket.get_remaining(), 5);
+        assert!(bucket.try_acquire(5));
+        assert!(!bucket.try_acquire(1));
     }
 }
 
 #[cfg(test)]
 mod parallel_tests {
+    use crate::core::RateLimiterShared;
     use crate::sliding_window_log::SlidingWindowLogShared;
-    use crate::token_bucket::r#impl::RateLimiterShared;
     use std::sync::atomic::{AtomicU32, Ordering};
     use std::sync::{Arc, Barrier};
     use std::thread;
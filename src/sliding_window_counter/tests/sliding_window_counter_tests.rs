@@ -1,58 +1,132 @@
 #[cfg(test)]
 mod sequential_tests {
+    use crate::clock::MockClock;
+    use crate::core::{RateLimitError, RateLimiter};
     use crate::sliding_window_counter::SlidingWindowCounter;
-    use crate::token_bucket::r#impl::RateLimiter;
-    use std::thread;
     use std::time::Duration;
 
     #[test]
     fn basic_test() {
-        let now_unix = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
-        let mut bucket = SlidingWindowCounter::new(10, 2);
+        let clock = MockClock::new();
+        let mut bucket = SlidingWindowCounter::with_clock(10, 2, clock.clone());
         assert_eq!(bucket.get_limit(), 10);
         assert_eq!(bucket.get_remaining(), 10);
         assert_eq!(bucket.get_used(), 0);
-        assert!(bucket.get_reset() >= now_unix);
 
         assert!(bucket.try_acquire(5));
-        assert_eq!(bucket.get_limit(), 10);
-        assert_eq!(bucket.get_remaining(), 5);
-        assert_eq!(bucket.get_used(), 5);
-        let diff = bucket.get_reset() - now_unix;
-        assert_eq!(diff, 2);
-
         assert!(bucket.try_acquire(5));
-        assert_eq!(bucket.get_limit(), 10);
         assert_eq!(bucket.get_remaining(), 0);
         assert_eq!(bucket.get_used(), 10);
-        let diff = bucket.get_reset() - now_unix;
-        assert_eq!(diff, 2);
+        assert!(!bucket.try_acquire(1));
 
+        clock.advance(Duration::from_secs(1));
         assert!(!bucket.try_acquire(1));
-        assert_eq!(bucket.get_limit(), 10);
-        assert_eq!(bucket.get_remaining(), 0);
-        assert_eq!(bucket.get_used(), 10);
-        let diff = bucket.get_reset() - now_unix;
-        assert_eq!(diff, 2);
 
-        thread::sleep(Duration::from_secs(1));
-        bucket.refresh(); // <-- Call refresh to update details w/ try_acquire call
-        assert_eq!(bucket.get_limit(), 10);
-        assert_eq!(bucket.get_remaining(), 0);
+        // New window, the previous one still fully weighs in
+        clock.advance(Duration::from_secs(1));
         assert_eq!(bucket.get_used(), 10);
-        let diff = bucket.get_reset() - now_unix;
-        assert_eq!(diff, 2);
+        assert!(!bucket.try_acquire(1));
+        assert_eq!(bucket.get_retry_after(1), Duration::from_millis(200));
 
-        thread::sleep(Duration::from_secs(1));
-        bucket.refresh(); // <-- Call refresh to update details w/ try_acquire call
-        assert_eq!(bucket.get_limit(), 10);
+        // A quarter of the window later, 10 * 0.75 = 7.5 is rounded up to 8
+        clock.advance(Duration::from_millis(500));
+        assert_eq!(bucket.get_used(), 8);
+        assert_eq!(bucket.get_remaining(), 2);
+        assert!(bucket.try_acquire(2));
+        assert!(!bucket.try_acquire(1));
+
+        clock.advance(Duration::from_secs(2));
+        assert_eq!(bucket.get_used(), 2);
+
+        clock.advance(Duration::from_secs(4));
+        bucket.refresh();
+        assert_eq!(bucket.get_used(), 0);
         assert_eq!(bucket.get_remaining(), 10);
+    }
+
+    #[test]
+    fn decision_test() {
+        let clock = MockClock::new();
+        let mut bucket = SlidingWindowCounter::with_clock(10, 2, clock.clone());
+
+        let decision = bucket.try_acquire_decision(8);
+        assert!(decision.allowed);
+        assert_eq!(decision.limit, 10);
+        assert_eq!(decision.remaining, 2);
+        assert_eq!(decision.retry_after, Duration::ZERO);
+
+        // Waits for the next window, then for 8 * (1 - 0.25) = 6 to slide out
+        let decision = bucket.try_acquire_decision(4);
+        assert!(!decision.allowed);
+        assert_eq!(decision.remaining, 2);
+        assert_eq!(decision.retry_after, Duration::from_millis(2500));
+
+        clock.advance(decision.retry_after);
+        assert!(bucket.try_acquire(4));
+
+        let decision = bucket.try_acquire_decision(11);
+        assert!(!decision.allowed);
+        assert_eq!(decision.retry_after, Duration::MAX);
+    }
+
+    #[test]
+    fn reset_test() {
+        let clock = MockClock::new();
+        let mut bucket = SlidingWindowCounter::builder()
+            .capacity(20)
+            .window(Duration::from_millis(100))
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        let start_ms = bucket.get_reset_ms();
+
+        assert!(bucket.try_acquire(20));
+        assert_eq!(bucket.get_reset_ms() - start_ms, 200);
+
+        clock.advance(Duration::from_millis(150));
+        assert_eq!(bucket.get_reset_ms() - start_ms, 200);
+
+        clock.advance(Duration::from_millis(50));
+        assert_eq!(bucket.get_reset_ms() - start_ms, 200);
         assert_eq!(bucket.get_used(), 0);
-        let diff = bucket.get_reset() - now_unix;
-        assert_eq!(diff, 2);
+    }
+
+    #[test]
+    fn builder_test() {
+        assert!(matches!(
+            SlidingWindowCounter::try_new(10, 0),
+            Err(RateLimitError::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            SlidingWindowCounter::try_new(0, 1),
+            Err(RateLimitError::InvalidConfig(_))
+        ));
+
+        let mut bucket = SlidingWindowCounter::builder()
+            .capacity(100_000)
+            .window(Duration::from_secs(60))
+            .build()
+            .unwrap();
+        assert!(bucket.try_acquire(100_000));
+        assert!(!bucket.try_acquire(1));
+    }
+
+    #[test]
+    fn huge_window_test() {
+        let clock = MockClock::new();
+        let mut bucket = SlidingWindowCounter::builder()
+            .capacity(2)
+            .window(Duration::MAX)
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+
+        assert!(bucket.try_acquire(2));
+        clock.advance(Duration::from_secs(1));
+        let decision = bucket.try_acquire_decision(1);
+        assert!(!decision.allowed);
+        assert!(decision.retry_after > Duration::from_secs(u64::from(u32::MAX)));
+        assert!(bucket.get_retry_after(2) > Duration::from_secs(u64::from(u32::MAX)));
+        assert!(bucket.get_reset() > u64::from(u32::MAX));
     }
 }
@@ -1,4 +1,7 @@
 pub mod r#impl;
+pub mod state;
 pub mod tests;
 
-pub use r#impl::{SlidingWindowLog, SlidingWindowLogShared};
+pub use crate::core::{Decision, RateLimitError, RateLimiter, RateLimiterShared};
+pub use r#impl::{SlidingWindowLog, SlidingWindowLogBuilder, SlidingWindowLogShared};
+pub use state::{SlidingWindowLogAlgorithm, SlidingWindowLogState};
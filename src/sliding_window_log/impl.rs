@@ -1,131 +1,265 @@
-use std::collections::VecDeque;
-use std::sync::{Arc, Mutex};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
 
-use crate::token_bucket::r#impl::{RateLimiter, RateLimiterShared};
+use crate::clock::{Clock, Epoch, SystemClock};
+use crate::core::{
+    as_millis, unix_time_after, Decision, RateLimitError, RateLimiter, RateLimiterShared,
+};
+use crate::sliding_window_log::{SlidingWindowLogAlgorithm, SlidingWindowLogState};
+use crate::store::Algorithm;
 
 // *** SLIDING WINDOW LOG ***
-pub struct SlidingWindowLog {
-    capacity: u32,
-    window: Duration,
-    log: VecDeque<u64>,
+pub struct SlidingWindowLog<C = SystemClock> {
+    pub(crate) algorithm: SlidingWindowLogAlgorithm,
+    pub(crate) state: SlidingWindowLogState,
+    epoch: Epoch,
+    pub(crate) clock: C,
 }
 
 impl SlidingWindowLog {
     pub fn new(capacity: u32, window_secs: u64) -> Self {
+        Self::with_clock(capacity, window_secs, SystemClock)
+    }
+
+    pub fn try_new(capacity: u32, window_secs: u64) -> Result<Self, RateLimitError> {
+        Self::builder()
+            .capacity(capacity)
+            .window(Duration::from_secs(window_secs))
+            .build()
+    }
+
+    pub fn with_window(capacity: u32, window: Duration) -> Self {
+        Self::with_window_and_clock(capacity, window, SystemClock)
+    }
+
+    pub fn builder() -> SlidingWindowLogBuilder {
+        SlidingWindowLogBuilder::new()
+    }
+}
+
+impl<C: Clock> SlidingWindowLog<C> {
+    pub fn with_clock(capacity: u32, window_secs: u64, clock: C) -> Self {
+        Self::with_window_and_clock(capacity, Duration::from_secs(window_secs), clock)
+    }
+
+    pub fn with_window_and_clock(capacity: u32, window: Duration, clock: C) -> Self {
+        let algorithm = SlidingWindowLogAlgorithm::new(capacity, window);
+        let epoch = Epoch::new(&clock);
         Self {
-            capacity,
-            window: Duration::from_secs(window_secs),
-            log: VecDeque::new(),
+            algorithm,
+            state: algorithm.initial_state(epoch.now(&clock)),
+            epoch,
+            clock,
         }
     }
 
-    fn now_secs() -> u64 {
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
+    #[cfg(test)]
+    pub(crate) fn log_len(&self) -> usize {
+        self.state.log.len()
+    }
+
+    pub(crate) fn now(&self) -> Duration {
+        self.epoch.now(&self.clock)
+    }
+
+    /// Time until the oldest entry in the log expires.
+    fn reset_after(&self) -> Duration {
+        self.algorithm.reset_after(&self.state, self.now())
     }
+}
+
+// *** SLIDING WINDOW LOG BUILDER ***
+pub struct SlidingWindowLogBuilder<C = SystemClock> {
+    capacity: u32,
+    window: Duration,
+    clock: C,
+}
 
-    fn cleanup(&mut self) {
-        let now = Self::now_secs();
-        while let Some(&ts) = self.log.front() {
-            if now - ts >= self.window.as_secs() {
-                self.log.pop_front();
-            } else {
-                break;
-            }
+impl SlidingWindowLogBuilder {
+    pub fn new() -> Self {
+        Self {
+            capacity: 0,
+            window: Duration::ZERO,
+            clock: SystemClock,
         }
     }
 }
 
-impl RateLimiter for SlidingWindowLog {
+impl Default for SlidingWindowLogBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Clock> SlidingWindowLogBuilder<C> {
+    pub fn capacity(mut self, capacity: u32) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    pub fn clock<D: Clock>(self, clock: D) -> SlidingWindowLogBuilder<D> {
+        SlidingWindowLogBuilder {
+            capacity: self.capacity,
+            window: self.window,
+            clock,
+        }
+    }
+
+    pub fn build(self) -> Result<SlidingWindowLog<C>, RateLimitError> {
+        if self.capacity == 0 {
+            return Err(RateLimitError::InvalidConfig(
+                "sliding window log capacity must be greater than zero".to_string(),
+            ));
+        }
+        if self.window.is_zero() {
+            return Err(RateLimitError::InvalidConfig(
+                "sliding window log window must be greater than zero".to_string(),
+            ));
+        }
+
+        Ok(SlidingWindowLog::with_window_and_clock(
+            self.capacity,
+            self.window,
+            self.clock,
+        ))
+    }
+
+    pub fn build_shared(self) -> Result<SlidingWindowLogShared<C>, RateLimitError> {
+        self.build().map(SlidingWindowLogShared::from)
+    }
+}
+
+impl<C: Clock> RateLimiter for SlidingWindowLog<C> {
     fn refresh(&mut self) {
-        self.cleanup();
+        let now = self.now();
+        self.algorithm.refresh(&mut self.state, now);
     }
 
     fn try_acquire(&mut self, tokens: u32) -> bool {
-        self.cleanup();
-        if tokens != 1 {
-            if self.log.len() + tokens as usize <= self.capacity as usize {
-                let now = Self::now_secs();
-                for _ in 0..tokens {
-                    self.log.push_back(now);
-                }
-                true
-            } else {
-                false
-            }
-        } else if self.log.len() < self.capacity as usize {
-            self.log.push_back(Self::now_secs());
-            true
-        } else {
-            false
-        }
+        let now = self.now();
+        self.algorithm.try_acquire(&mut self.state, now, tokens)
     }
 
     fn get_limit(&self) -> u32 {
-        self.capacity
+        self.algorithm.limit()
     }
 
     fn get_remaining(&self) -> u32 {
-        self.capacity.saturating_sub(self.log.len() as u32)
+        self.algorithm.remaining(&self.state, self.now())
     }
 
     fn get_used(&self) -> u32 {
-        self.log.len() as u32
+        self.algorithm.used(&self.state, self.now())
     }
 
     fn get_reset(&self) -> u64 {
-        if let Some(&oldest) = self.log.front() {
-            oldest + self.window.as_secs()
-        } else {
-            Self::now_secs()
-        }
+        unix_time_after(self.clock.now_system(), self.reset_after()).as_secs()
+    }
+
+    fn get_reset_ms(&self) -> u64 {
+        as_millis(unix_time_after(self.clock.now_system(), self.reset_after()))
+    }
+
+    fn get_retry_after(&self, tokens: u32) -> Duration {
+        self.algorithm.retry_after(&self.state, self.now(), tokens)
     }
 }
 
 // *** SLIDING WINDOW LOG SHARED ***
-pub struct SlidingWindowLogShared {
-    inner: Arc<Mutex<SlidingWindowLog>>,
+pub struct SlidingWindowLogShared<C = SystemClock> {
+    pub(crate) inner: Arc<Mutex<SlidingWindowLog<C>>>,
 }
 
 impl SlidingWindowLogShared {
     pub fn new(capacity: u32, window_secs: u64) -> Self {
+        Self::with_clock(capacity, window_secs, SystemClock)
+    }
+
+    pub fn try_new(capacity: u32, window_secs: u64) -> Result<Self, RateLimitError> {
+        SlidingWindowLog::try_new(capacity, window_secs).map(Self::from)
+    }
+
+    pub fn with_window(capacity: u32, window: Duration) -> Self {
+        Self::from(SlidingWindowLog::with_window(capacity, window))
+    }
+}
+
+impl<C: Clock> SlidingWindowLogShared<C> {
+    pub fn with_clock(capacity: u32, window_secs: u64, clock: C) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(SlidingWindowLog::with_clock(
+                capacity,
+                window_secs,
+                clock,
+            ))),
+        }
+    }
+}
+
+impl<C> From<SlidingWindowLog<C>> for SlidingWindowLogShared<C> {
+    fn from(limiter: SlidingWindowLog<C>) -> Self {
         Self {
-            inner: Arc::new(Mutex::new(SlidingWindowLog::new(capacity, window_secs))),
+            inner: Arc::new(Mutex::new(limiter)),
         }
     }
 }
 
-impl RateLimiterShared for SlidingWindowLogShared {
+impl<C: Clock> RateLimiterShared for SlidingWindowLogShared<C> {
     fn refresh(&self) {
-        let mut bucket = self.inner.lock().unwrap();
+        let mut bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         bucket.refresh();
     }
 
     fn try_acquire(&self, tokens: u32) -> bool {
-        let mut bucket = self.inner.lock().unwrap();
+        let mut bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         bucket.try_acquire(tokens)
     }
 
+    fn try_acquire_decision(&self, tokens: u32) -> Decision {
+        let mut bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        bucket.try_acquire_decision(tokens)
+    }
+
+    fn try_acquire_checked(&self, tokens: u32) -> Result<Decision, RateLimitError> {
+        let mut bucket = self
+            .inner
+            .lock()
+            .map_err(|_| RateLimitError::PoisonedLock)?;
+        bucket.try_acquire_checked(tokens)
+    }
+
     fn get_limit(&self) -> u32 {
-        let bucket = self.inner.lock().unwrap();
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         bucket.get_limit()
     }
 
     fn get_remaining(&self) -> u32 {
-        let bucket = self.inner.lock().unwrap();
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         bucket.get_remaining()
     }
 
     fn get_used(&self) -> u32 {
-        let bucket = self.inner.lock().unwrap();
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         bucket.get_used()
     }
 
     fn get_reset(&self) -> u64 {
-        let bucket = self.inner.lock().unwrap();
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         bucket.get_reset()
     }
+
+    fn get_reset_ms(&self) -> u64 {
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        bucket.get_reset_ms()
+    }
+
+    fn get_retry_after(&self, tokens: u32) -> Duration {
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        bucket.get_retry_after(tokens)
+    }
 }
@@ -0,0 +1,134 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::store::r#impl::{Algorithm, StateReader, StateWriter};
+
+const TAG: u8 = 5;
+
+// *** SLIDING WINDOW LOG STATE ***
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct SlidingWindowLogState {
+    /// Acquisitions as `(timestamp, tokens)`, tokens acquired at the same instant share an entry.
+    pub log: VecDeque<(Duration, u32)>,
+    /// Sum of the tokens in `log`.
+    pub used: u32,
+}
+
+// *** SLIDING WINDOW LOG ALGORITHM ***
+/// Sliding window log transitions, see `SlidingWindowLog` for a limiter holding its own state.
+/// The encoded state grows with the number of acquisitions in the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlidingWindowLogAlgorithm {
+    capacity: u32,
+    window: Duration,
+}
+
+impl SlidingWindowLogAlgorithm {
+    pub fn new(capacity: u32, window: Duration) -> Self {
+        Self { capacity, window }
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    fn record(&self, state: &mut SlidingWindowLogState, now: Duration, tokens: u32) {
+        match state.log.back_mut() {
+            Some((ts, count)) if *ts == now => *count += tokens,
+            _ => state.log.push_back((now, tokens)),
+        }
+        state.used += tokens;
+    }
+}
+
+impl Algorithm for SlidingWindowLogAlgorithm {
+    type State = SlidingWindowLogState;
+
+    fn limit(&self) -> u32 {
+        self.capacity
+    }
+
+    fn initial_state(&self, _now: Duration) -> SlidingWindowLogState {
+        SlidingWindowLogState::default()
+    }
+
+    /// Drops the entries that left the window.
+    fn refresh(&self, state: &mut SlidingWindowLogState, now: Duration) {
+        while let Some(&(ts, tokens)) = state.log.front() {
+            if now.saturating_sub(ts) >= self.window {
+                state.log.pop_front();
+                state.used -= tokens;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn try_acquire(&self, state: &mut SlidingWindowLogState, now: Duration, tokens: u32) -> bool {
+        self.refresh(state, now);
+        if tokens <= self.capacity.saturating_sub(state.used) {
+            if tokens > 0 {
+                self.record(state, now, tokens);
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    fn remaining(&self, state: &SlidingWindowLogState, _now: Duration) -> u32 {
+        self.capacity.saturating_sub(state.used)
+    }
+
+    fn used(&self, state: &SlidingWindowLogState, _now: Duration) -> u32 {
+        state.used
+    }
+
+    /// Time until the oldest entry in the log expires.
+    fn reset_after(&self, state: &SlidingWindowLogState, now: Duration) -> Duration {
+        match state.log.front() {
+            Some(&(oldest, _)) => oldest.saturating_add(self.window).saturating_sub(now),
+            None => Duration::ZERO,
+        }
+    }
+
+    fn retry_after(&self, state: &SlidingWindowLogState, now: Duration, tokens: u32) -> Duration {
+        if tokens > self.capacity {
+            return Duration::MAX;
+        }
+        let overflow = (state.used as u64 + tokens as u64).saturating_sub(self.capacity as u64);
+        if overflow == 0 {
+            return Duration::ZERO;
+        }
+
+        // The request fits once the oldest `overflow` tokens have expired
+        let mut expired = 0u64;
+        for &(ts, count) in &state.log {
+            expired += count as u64;
+            if expired >= overflow {
+                return ts.saturating_add(self.window).saturating_sub(now);
+            }
+        }
+        Duration::ZERO
+    }
+
+    fn encode(&self, state: &SlidingWindowLogState) -> Vec<u8> {
+        let mut writer = StateWriter::new(TAG).u32(state.log.len() as u32);
+        for &(ts, tokens) in &state.log {
+            writer = writer.duration(ts).u32(tokens);
+        }
+        writer.finish()
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Option<SlidingWindowLogState> {
+        let mut reader = StateReader::new(bytes, TAG)?;
+        let mut state = SlidingWindowLogState::default();
+        for _ in 0..reader.u32()? {
+            let entry = (reader.duration()?, reader.u32()?);
+            state.used = state.used.checked_add(entry.1)?;
+            state.log.push_back(entry);
+        }
+        reader.is_empty().then_some(state)
+    }
+}
@@ -0,0 +1,9 @@
+pub mod r#impl;
+pub mod memory;
+pub mod remote;
+pub mod tests;
+
+pub use crate::core::{KeyedRateLimiterShared, RateLimitError, RateLimiterShared};
+pub use memory::MemoryStore;
+pub use r#impl::{Algorithm, AlgorithmBackend, Store, StoreKeyedLimiter, StoreLimiter};
+pub use remote::{Backend, Outcome, RemoteKeyedLimiter, RemoteLimiter};
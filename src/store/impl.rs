@@ -0,0 +1,266 @@
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use crate::clock::{since_unix, Clock, SystemClock};
+use crate::core::RateLimitError;
+use crate::store::remote::{Backend, Outcome, RemoteKeyedLimiter, RemoteLimiter};
+
+// *** ALGORITHM ***
+/// A rate limiting algorithm as pure transitions over its `State`, so the state can live
+/// anywhere: in the limiter itself or behind a `Store` shared between processes.
+///
+/// `now` is the time since an epoch agreed on by everyone sharing a state, the UNIX epoch for
+/// the limiters in this crate. Reads see the state as of its last transition unless noted, call
+/// `refresh` first for an up to date answer.
+pub trait Algorithm {
+    type State: Clone;
+
+    fn limit(&self) -> u32;
+    /// State of a limiter created at `now`.
+    fn initial_state(&self, now: Duration) -> Self::State;
+    fn refresh(&self, state: &mut Self::State, now: Duration);
+    /// Refreshes `state` and takes `tokens` from it if they are available.
+    fn try_acquire(&self, state: &mut Self::State, now: Duration, tokens: u32) -> bool;
+    fn remaining(&self, state: &Self::State, now: Duration) -> u32;
+    fn used(&self, state: &Self::State, now: Duration) -> u32;
+    /// Time until the state is back to its initial capacity.
+    fn reset_after(&self, state: &Self::State, now: Duration) -> Duration;
+    fn retry_after(&self, state: &Self::State, now: Duration, tokens: u32) -> Duration;
+
+    /// Whether `state` behaves like a fresh one, see `RateLimiter::is_idle`.
+    fn is_idle(&self, state: &Self::State, now: Duration) -> bool {
+        self.used(state, now) == 0
+    }
+
+    fn encode(&self, state: &Self::State) -> Vec<u8>;
+    /// Returns `None` if `bytes` were not produced by `encode` of the same algorithm.
+    fn decode(&self, bytes: &[u8]) -> Option<Self::State>;
+}
+
+// *** STATE ENCODING ***
+/// Writes a state as a tag byte identifying the algorithm followed by little endian fields.
+pub(crate) struct StateWriter(Vec<u8>);
+
+impl StateWriter {
+    pub(crate) fn new(tag: u8) -> Self {
+        Self(vec![tag])
+    }
+
+    pub(crate) fn u32(mut self, value: u32) -> Self {
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    pub(crate) fn u64(mut self, value: u64) -> Self {
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    pub(crate) fn f64(self, value: f64) -> Self {
+        self.u64(value.to_bits())
+    }
+
+    /// Whole nanoseconds, saturating at about 584 years.
+    pub(crate) fn duration(self, value: Duration) -> Self {
+        self.u64(value.as_nanos().min(u64::MAX as u128) as u64)
+    }
+
+    pub(crate) fn finish(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+/// Reads back the fields written by a `StateWriter` in the same order.
+pub(crate) struct StateReader<'a>(&'a [u8]);
+
+impl<'a> StateReader<'a> {
+    /// Returns `None` if `bytes` don't start with `tag`.
+    pub(crate) fn new(bytes: &'a [u8], tag: u8) -> Option<Self> {
+        match bytes.split_first() {
+            Some((&first, rest)) if first == tag => Some(Self(rest)),
+            _ => None,
+        }
+    }
+
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (head, rest) = self.0.split_first_chunk::<N>()?;
+        self.0 = rest;
+        Some(*head)
+    }
+
+    pub(crate) fn u32(&mut self) -> Option<u32> {
+        self.take().map(u32::from_le_bytes)
+    }
+
+    pub(crate) fn u64(&mut self) -> Option<u64> {
+        self.take().map(u64::from_le_bytes)
+    }
+
+    pub(crate) fn f64(&mut self) -> Option<f64> {
+        self.u64().map(f64::from_bits)
+    }
+
+    pub(crate) fn duration(&mut self) -> Option<Duration> {
+        self.u64().map(Duration::from_nanos)
+    }
+
+    /// Whether every byte was read.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+// *** STORE ***
+/// Holds the encoded state of an algorithm per key, e.g. `MemoryStore`. A distributed store
+/// only needs these two operations to be atomic for every algorithm to work on top of it.
+pub trait Store {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, RateLimitError>;
+
+    /// Replaces the state of `key` with `new` if it still is `current`, `None` meaning the key
+    /// is absent. Returns whether the state was replaced.
+    fn compare_and_set(
+        &self,
+        key: &str,
+        current: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, RateLimitError>;
+}
+
+impl<S: Store + ?Sized> Store for &S {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, RateLimitError> {
+        (**self).get(key)
+    }
+
+    fn compare_and_set(
+        &self,
+        key: &str,
+        current: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, RateLimitError> {
+        (**self).compare_and_set(key, current, new)
+    }
+}
+
+impl<S: Store + ?Sized> Store for Arc<S> {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, RateLimitError> {
+        (**self).get(key)
+    }
+
+    fn compare_and_set(
+        &self,
+        key: &str,
+        current: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, RateLimitError> {
+        (**self).compare_and_set(key, current, new)
+    }
+}
+
+// *** STORE LIMITER ***
+/// Drives an `Algorithm` over the state kept in a `Store` under `prefix`, so every process
+/// sharing the store enforces the same limit. Times are measured from the UNIX epoch, the
+/// processes' clocks should agree. See `keyed` for a limit per client.
+///
+/// Updates read the state, apply the algorithm and write it back with `compare_and_set`,
+/// starting over when another process got there first. When the store fails, requests are
+/// denied unless `with_fail_open(true)` is set, and `try_acquire_checked` reports the failure
+/// as `RateLimitError::Backend` in either case.
+pub type StoreLimiter<A, S, C = SystemClock> = RemoteLimiter<AlgorithmBackend<A, S, C>>;
+
+/// A `StoreLimiter` keeping separate state per key under `{prefix}:{key}`, e.g. per client.
+pub type StoreKeyedLimiter<A, S, C = SystemClock> = RemoteKeyedLimiter<AlgorithmBackend<A, S, C>>;
+
+impl<A: Algorithm, S: Store> StoreLimiter<A, S> {
+    pub fn new(algorithm: A, store: S, prefix: impl Into<String>) -> Self {
+        Self::with_clock(algorithm, store, prefix, SystemClock)
+    }
+}
+
+impl<A: Algorithm, S: Store, C: Clock> StoreLimiter<A, S, C> {
+    pub fn with_clock(algorithm: A, store: S, prefix: impl Into<String>, clock: C) -> Self {
+        let backend = AlgorithmBackend {
+            algorithm,
+            store,
+            clock,
+        };
+        Self::with_backend(backend, prefix)
+    }
+
+    pub fn algorithm(&self) -> &A {
+        &self.backend().algorithm
+    }
+
+    pub fn store(&self) -> &S {
+        &self.backend().store
+    }
+}
+
+// *** ALGORITHM BACKEND ***
+/// The `Backend` of a `StoreLimiter`, running the algorithm in process on the stored state.
+pub struct AlgorithmBackend<A, S, C = SystemClock> {
+    algorithm: A,
+    store: S,
+    clock: C,
+}
+
+impl<A: Algorithm, S: Store, C: Clock> AlgorithmBackend<A, S, C> {
+    /// The stored state of `key` as read, and decoded or fresh if there is none.
+    fn load(
+        &self,
+        key: &str,
+        now: Duration,
+    ) -> Result<(Option<Vec<u8>>, A::State), RateLimitError> {
+        let current = self.store.get(key)?;
+        let state = match &current {
+            Some(bytes) => self.algorithm.decode(bytes).ok_or_else(|| {
+                RateLimitError::Backend(format!("undecodable limiter state under {key:?}"))
+            })?,
+            None => self.algorithm.initial_state(now),
+        };
+        Ok((current, state))
+    }
+
+    fn outcome(&self, state: &A::State, now: Duration, allowed: bool, tokens: u32) -> Outcome {
+        Outcome {
+            allowed,
+            remaining: self.algorithm.remaining(state, now),
+            retry_after: if allowed {
+                Duration::ZERO
+            } else {
+                self.algorithm.retry_after(state, now, tokens)
+            },
+            reset_after: self.algorithm.reset_after(state, now),
+        }
+    }
+}
+
+impl<A: Algorithm, S: Store, C: Clock> Backend for AlgorithmBackend<A, S, C> {
+    fn limit(&self) -> u32 {
+        self.algorithm.limit()
+    }
+
+    fn now(&self) -> SystemTime {
+        self.clock.now_system()
+    }
+
+    fn run(&self, key: &str, tokens: u32, dry_run: bool) -> Result<Outcome, RateLimitError> {
+        loop {
+            let now = since_unix(self.clock.now_system());
+            let (current, mut state) = self.load(key, now)?;
+            if dry_run {
+                self.algorithm.refresh(&mut state, now);
+                let allowed = self.algorithm.retry_after(&state, now, tokens).is_zero();
+                return Ok(self.outcome(&state, now, allowed, tokens));
+            }
+
+            let allowed = self.algorithm.try_acquire(&mut state, now, tokens);
+            let outcome = self.outcome(&state, now, allowed, tokens);
+            let new = self.algorithm.encode(&state);
+            if current.as_deref() == Some(new.as_slice())
+                || self.store.compare_and_set(key, current.as_deref(), &new)?
+            {
+                return Ok(outcome);
+            }
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+
+use crate::core::RateLimitError;
+use crate::store::r#impl::Store;
+
+// *** MEMORY STORE ***
+/// Keeps states in a map behind a lock, for limiters sharing a process or for tests. States
+/// are never removed, use `remove` or `clear` to drop the ones no longer needed.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    entries: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains_key(key)
+    }
+
+    /// Drops the state of `key`, the next request starts from a fresh one.
+    pub fn remove(&self, key: &str) -> bool {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(key)
+            .is_some()
+    }
+
+    pub fn clear(&self) {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear()
+    }
+}
+
+impl Store for MemoryStore {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, RateLimitError> {
+        let entries = self
+            .entries
+            .lock()
+            .map_err(|_| RateLimitError::PoisonedLock)?;
+        Ok(entries.get(key).cloned())
+    }
+
+    fn compare_and_set(
+        &self,
+        key: &str,
+        current: Option<&[u8]>,
+        new: &[u8],
+    ) -> Result<bool, RateLimitError> {
+        let mut entries = self
+            .entries
+            .lock()
+            .map_err(|_| RateLimitError::PoisonedLock)?;
+        if entries.get(key).map(Vec::as_slice) != current {
+            return Ok(false);
+        }
+        entries.insert(key.to_string(), new.to_vec());
+        Ok(true)
+    }
+}
@@ -0,0 +1,246 @@
+use std::fmt::Display;
+use std::time::{Duration, SystemTime};
+
+use crate::core::{
+    as_millis, unix_time_after, Decision, KeyedRateLimiterShared, RateLimitError, RateLimiterShared,
+};
+
+// *** BACKEND ***
+/// Reply of a `Backend` for the state of one key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    pub allowed: bool,
+    pub remaining: u32,
+    /// Zero when allowed, `Duration::MAX` when the request can never succeed.
+    pub retry_after: Duration,
+    pub reset_after: Duration,
+}
+
+/// Keeps the state of a `RemoteLimiter` outside the process, e.g. an `Algorithm` over a `Store`
+/// or the Redis scripts.
+pub trait Backend {
+    fn limit(&self) -> u32;
+    /// Current time, the reset timestamps of decisions are computed from it.
+    fn now(&self) -> SystemTime;
+    /// Refreshes the state of `key` and takes `tokens` from it if they are available. With
+    /// `dry_run` the state is left as it is.
+    fn run(&self, key: &str, tokens: u32, dry_run: bool) -> Result<Outcome, RateLimitError>;
+}
+
+// *** REMOTE LIMITER ***
+/// Drives a `Backend` holding the state under `prefix`, so every process sharing the backend
+/// enforces the same limit. See `keyed` for a limit per client.
+///
+/// When the backend fails, requests are denied unless `with_fail_open(true)` is set, and
+/// `try_acquire_checked` reports the failure as `RateLimitError::Backend` in either case.
+pub struct RemoteLimiter<B> {
+    backend: B,
+    prefix: String,
+    fail_open: bool,
+}
+
+impl<B: Backend> RemoteLimiter<B> {
+    pub fn with_backend(backend: B, prefix: impl Into<String>) -> Self {
+        Self {
+            backend,
+            prefix: prefix.into(),
+            fail_open: false,
+        }
+    }
+
+    /// Allows requests while the backend is failing instead of denying them.
+    pub fn with_fail_open(mut self, fail_open: bool) -> Self {
+        self.fail_open = fail_open;
+        self
+    }
+
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Limits every key separately instead of sharing the state under `prefix`.
+    pub fn keyed(self) -> RemoteKeyedLimiter<B> {
+        RemoteKeyedLimiter(self)
+    }
+
+    /// What the limiter reports while the backend is failing. A denial can't tell when the
+    /// backend is back, so it is not retryable.
+    fn fallback(&self) -> Outcome {
+        if self.fail_open {
+            Outcome {
+                allowed: true,
+                remaining: self.backend.limit(),
+                retry_after: Duration::ZERO,
+                reset_after: Duration::ZERO,
+            }
+        } else {
+            Outcome {
+                allowed: false,
+                remaining: 0,
+                retry_after: Duration::MAX,
+                reset_after: Duration::ZERO,
+            }
+        }
+    }
+
+    fn decision(&self, outcome: &Outcome) -> Decision {
+        Decision {
+            allowed: outcome.allowed,
+            limit: self.backend.limit(),
+            remaining: outcome.remaining,
+            retry_after: outcome.retry_after,
+            reset: unix_time_after(self.backend.now(), outcome.reset_after).as_secs(),
+        }
+    }
+
+    fn peek(&self, key: &str, tokens: u32) -> Outcome {
+        self.backend
+            .run(key, tokens, true)
+            .unwrap_or_else(|_| self.fallback())
+    }
+
+    fn acquire_decision(&self, key: &str, tokens: u32) -> Decision {
+        let outcome = self
+            .backend
+            .run(key, tokens, false)
+            .unwrap_or_else(|_| self.fallback());
+        self.decision(&outcome)
+    }
+
+    fn acquire_checked(&self, key: &str, tokens: u32) -> Result<Decision, RateLimitError> {
+        let limit = self.backend.limit();
+        if tokens > limit {
+            return Err(RateLimitError::InsufficientCapacity {
+                requested: tokens,
+                capacity: limit,
+            });
+        }
+
+        let outcome = match self.backend.run(key, tokens, false) {
+            Ok(outcome) => outcome,
+            Err(err) if !self.fail_open => return Err(err),
+            Err(_) => self.fallback(),
+        };
+        let decision = self.decision(&outcome);
+        if decision.allowed {
+            Ok(decision)
+        } else {
+            Err(RateLimitError::Denied(decision))
+        }
+    }
+
+    fn used(&self, key: &str) -> u32 {
+        self.backend
+            .limit()
+            .saturating_sub(self.peek(key, 0).remaining)
+    }
+
+    fn reset_ms(&self, key: &str) -> u64 {
+        let reset_after = self.peek(key, 0).reset_after;
+        as_millis(unix_time_after(self.backend.now(), reset_after))
+    }
+}
+
+impl<B: Backend> RateLimiterShared for RemoteLimiter<B> {
+    fn refresh(&self) {
+        // Every backend refreshes the state before using it
+    }
+
+    fn try_acquire(&self, tokens: u32) -> bool {
+        self.acquire_decision(&self.prefix, tokens).allowed
+    }
+
+    fn try_acquire_decision(&self, tokens: u32) -> Decision {
+        self.acquire_decision(&self.prefix, tokens)
+    }
+
+    fn try_acquire_checked(&self, tokens: u32) -> Result<Decision, RateLimitError> {
+        self.acquire_checked(&self.prefix, tokens)
+    }
+
+    fn get_limit(&self) -> u32 {
+        self.backend.limit()
+    }
+
+    fn get_remaining(&self) -> u32 {
+        self.peek(&self.prefix, 0).remaining
+    }
+
+    fn get_used(&self) -> u32 {
+        self.used(&self.prefix)
+    }
+
+    fn get_reset(&self) -> u64 {
+        self.reset_ms(&self.prefix) / 1000
+    }
+
+    fn get_reset_ms(&self) -> u64 {
+        self.reset_ms(&self.prefix)
+    }
+
+    fn get_retry_after(&self, tokens: u32) -> Duration {
+        self.peek(&self.prefix, tokens).retry_after
+    }
+}
+
+// *** REMOTE KEYED LIMITER ***
+/// A `RemoteLimiter` keeping separate state per key under `{prefix}:{key}`, e.g. per client.
+pub struct RemoteKeyedLimiter<B>(RemoteLimiter<B>);
+
+impl<B: Backend> RemoteKeyedLimiter<B> {
+    pub fn limiter(&self) -> &RemoteLimiter<B> {
+        &self.0
+    }
+
+    fn key<K: Display + ?Sized>(&self, key: &K) -> String {
+        format!("{}:{key}", self.0.prefix)
+    }
+}
+
+impl<B> From<RemoteLimiter<B>> for RemoteKeyedLimiter<B> {
+    fn from(limiter: RemoteLimiter<B>) -> Self {
+        Self(limiter)
+    }
+}
+
+impl<B: Backend, K: Display + ?Sized> KeyedRateLimiterShared<K> for RemoteKeyedLimiter<B> {
+    fn try_acquire(&self, key: &K, tokens: u32) -> bool {
+        self.0.acquire_decision(&self.key(key), tokens).allowed
+    }
+
+    fn try_acquire_decision(&self, key: &K, tokens: u32) -> Decision {
+        self.0.acquire_decision(&self.key(key), tokens)
+    }
+
+    fn try_acquire_checked(&self, key: &K, tokens: u32) -> Result<Decision, RateLimitError> {
+        self.0.acquire_checked(&self.key(key), tokens)
+    }
+
+    fn get_limit(&self, _key: &K) -> u32 {
+        self.0.backend.limit()
+    }
+
+    fn get_remaining(&self, key: &K) -> u32 {
+        self.0.peek(&self.key(key), 0).remaining
+    }
+
+    fn get_used(&self, key: &K) -> u32 {
+        self.0.used(&self.key(key))
+    }
+
+    fn get_reset(&self, key: &K) -> u64 {
+        self.0.reset_ms(&self.key(key)) / 1000
+    }
+
+    fn get_reset_ms(&self, key: &K) -> u64 {
+        self.0.reset_ms(&self.key(key))
+    }
+
+    fn get_retry_after(&self, key: &K, tokens: u32) -> Duration {
+        self.0.peek(&self.key(key), tokens).retry_after
+    }
+}
@@ -0,0 +1,247 @@
+#[cfg(test)]
+mod sequential_tests {
+    use crate::clock::MockClock;
+    use crate::core::{
+        KeyedRateLimiterShared, Rate, RateLimitError, RateLimiter, RateLimiterShared,
+    };
+    use crate::fixed_window_counter::{FixedWindowCounter, FixedWindowCounterAlgorithm};
+    use crate::gcra::{Gcra, GcraAlgorithm};
+    use crate::leaky_bucket::{LeakyBucket, LeakyBucketAlgorithm};
+    use crate::sliding_window_counter::{SlidingWindowCounter, SlidingWindowCounterAlgorithm};
+    use crate::sliding_window_log::{SlidingWindowLog, SlidingWindowLogAlgorithm};
+    use crate::store::{Algorithm, MemoryStore, Store, StoreLimiter};
+    use crate::token_bucket::{TokenBucket, TokenBucketAlgorithm, TokenBucketState};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    struct Unreachable;
+
+    impl Store for Unreachable {
+        fn get(&self, _: &str) -> Result<Option<Vec<u8>>, RateLimitError> {
+            Err(RateLimitError::Backend("connection refused".to_string()))
+        }
+
+        fn compare_and_set(
+            &self,
+            _: &str,
+            _: Option<&[u8]>,
+            _: &[u8],
+        ) -> Result<bool, RateLimitError> {
+            Err(RateLimitError::Backend("connection refused".to_string()))
+        }
+    }
+
+    /// Runs `before_set` ahead of the next write, standing in for another process.
+    struct Racing<F> {
+        inner: MemoryStore,
+        before_set: Mutex<Option<F>>,
+    }
+
+    impl<F: FnOnce(&MemoryStore)> Store for Racing<F> {
+        fn get(&self, key: &str) -> Result<Option<Vec<u8>>, RateLimitError> {
+            self.inner.get(key)
+        }
+
+        fn compare_and_set(
+            &self,
+            key: &str,
+            current: Option<&[u8]>,
+            new: &[u8],
+        ) -> Result<bool, RateLimitError> {
+            if let Some(before_set) = self.before_set.lock().unwrap().take() {
+                before_set(&self.inner);
+            }
+            self.inner.compare_and_set(key, current, new)
+        }
+    }
+
+    /// Checks that a `StoreLimiter` over `algorithm` decides like the in-memory `local`.
+    fn assert_matches<A: Algorithm, L: RateLimiter>(algorithm: A, mut local: L, clock: MockClock) {
+        let store = MemoryStore::new();
+        let remote = StoreLimiter::with_clock(algorithm, &store, "key", clock.clone());
+
+        for step in 0..200u32 {
+            let tokens = step % 4;
+            let remote_decision = remote.try_acquire_decision(tokens);
+            let local_decision = local.try_acquire_decision(tokens);
+            assert_eq!(
+                remote_decision.allowed, local_decision.allowed,
+                "step {step}"
+            );
+            assert_eq!(remote_decision.remaining, local_decision.remaining);
+            assert_eq!(remote_decision.retry_after, local_decision.retry_after);
+            assert_eq!(remote.get_retry_after(3), local.get_retry_after(3));
+
+            clock.advance(Duration::from_millis(u64::from(step % 7) * 70));
+        }
+    }
+
+    #[test]
+    fn memory_store_test() {
+        let store = MemoryStore::new();
+        assert_eq!(store.get("key"), Ok(None));
+
+        assert_eq!(store.compare_and_set("key", Some(b"a"), b"b"), Ok(false));
+        assert_eq!(store.compare_and_set("key", None, b"a"), Ok(true));
+        assert_eq!(store.compare_and_set("key", None, b"b"), Ok(false));
+        assert_eq!(store.compare_and_set("key", Some(b"a"), b"b"), Ok(true));
+        assert_eq!(store.get("key"), Ok(Some(b"b".to_vec())));
+        assert_eq!(store.len(), 1);
+
+        assert!(store.remove("key"));
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn encode_test() {
+        let bucket = TokenBucketAlgorithm::new(5, Rate::per_second(1));
+        let state = TokenBucketState {
+            tokens: 3,
+            last_refill: Duration::from_millis(1500),
+            carry: 2,
+        };
+        let bytes = bucket.encode(&state);
+        assert_eq!(bucket.decode(&bytes), Some(state));
+
+        // States of another algorithm or cut short are rejected
+        let window = FixedWindowCounterAlgorithm::new(5, Duration::from_secs(1));
+        assert_eq!(window.decode(&bytes), None);
+        assert_eq!(bucket.decode(&bytes[..bytes.len() - 1]), None);
+
+        let log = SlidingWindowLogAlgorithm::new(5, Duration::from_secs(1));
+        let mut state = log.initial_state(Duration::ZERO);
+        assert!(log.try_acquire(&mut state, Duration::from_millis(10), 2));
+        assert!(log.try_acquire(&mut state, Duration::from_millis(20), 1));
+        assert_eq!(log.decode(&log.encode(&state)), Some(state));
+    }
+
+    #[test]
+    fn matches_in_memory_test() {
+        let clock = MockClock::new();
+        let rate = Rate::per(3, Duration::from_secs(2));
+        assert_matches(
+            TokenBucketAlgorithm::new(5, rate),
+            TokenBucket::with_rate_and_clock(5, rate, clock.clone()),
+            clock.clone(),
+        );
+        assert_matches(
+            LeakyBucketAlgorithm::new(5, 1.5),
+            LeakyBucket::with_clock(5, 1.5, clock.clone()),
+            clock.clone(),
+        );
+
+        let window = Duration::from_secs(1);
+        assert_matches(
+            FixedWindowCounterAlgorithm::new(5, window),
+            FixedWindowCounter::with_window_and_clock(5, window, clock.clone()),
+            clock.clone(),
+        );
+        assert_matches(
+            SlidingWindowCounterAlgorithm::new(5, window),
+            SlidingWindowCounter::with_window_and_clock(5, window, clock.clone()),
+            clock.clone(),
+        );
+        assert_matches(
+            SlidingWindowLogAlgorithm::new(5, window),
+            SlidingWindowLog::with_window_and_clock(5, window, clock.clone()),
+            clock.clone(),
+        );
+
+        let interval = Duration::from_millis(250);
+        let tolerance = Duration::from_millis(750);
+        assert_matches(
+            GcraAlgorithm::new(interval, tolerance),
+            Gcra::with_clock(interval, tolerance, clock.clone()),
+            clock,
+        );
+    }
+
+    #[test]
+    fn shared_store_test() {
+        let clock = MockClock::new();
+        let store = Arc::new(MemoryStore::new());
+        let algorithm = TokenBucketAlgorithm::new(4, Rate::per_minute(1));
+        // Two replicas configured alike enforce one limit
+        let first = StoreLimiter::with_clock(algorithm, Arc::clone(&store), "api", clock.clone());
+        let second = StoreLimiter::with_clock(algorithm, Arc::clone(&store), "api", clock);
+
+        assert!(first.try_acquire(3));
+        assert_eq!(second.get_remaining(), 1);
+        assert!(!second.try_acquire(2));
+        assert!(second.try_acquire(1));
+        assert!(!first.try_acquire(1));
+        assert_eq!(first.get_used(), 4);
+    }
+
+    #[test]
+    fn conflict_test() {
+        let clock = MockClock::new();
+        let other_clock = clock.clone();
+        let algorithm = FixedWindowCounterAlgorithm::new(3, Duration::from_secs(1));
+        let store = Racing {
+            inner: MemoryStore::new(),
+            before_set: Mutex::new(Some(move |store: &MemoryStore| {
+                let other = StoreLimiter::with_clock(algorithm, store, "api", other_clock);
+                assert!(other.try_acquire(2));
+            })),
+        };
+        let limiter = StoreLimiter::with_clock(algorithm, &store, "api", clock);
+
+        // The first write loses the race and is retried on top of the other one
+        let decision = limiter.try_acquire_decision(1);
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 0);
+        assert!(!limiter.try_acquire(1));
+    }
+
+    #[test]
+    fn keyed_test() {
+        let clock = MockClock::new();
+        let store = MemoryStore::new();
+        let algorithm = SlidingWindowLogAlgorithm::new(2, Duration::from_secs(1));
+        let limiter = StoreLimiter::with_clock(algorithm, &store, "api", clock.clone()).keyed();
+
+        assert!(limiter.try_acquire("alice", 2));
+        assert!(!limiter.try_acquire("alice", 1));
+        assert!(limiter.try_acquire("bob", 1));
+        assert!(store.contains_key("api:alice"));
+        assert!(!store.contains_key("api"));
+
+        assert_eq!(limiter.get_remaining("alice"), 0);
+        assert_eq!(limiter.get_remaining(&42), 2);
+        assert_eq!(limiter.get_retry_after("alice", 1), Duration::from_secs(1));
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(limiter.get_remaining("alice"), 2);
+        let err = limiter.try_acquire_checked("bob", 3).unwrap_err();
+        assert!(!err.is_retryable());
+    }
+
+    #[test]
+    fn backend_failure_test() {
+        let algorithm = TokenBucketAlgorithm::new(3, Rate::per_second(1));
+        let closed = StoreLimiter::new(algorithm, Unreachable, "api");
+        assert!(!closed.try_acquire(1));
+        assert_eq!(closed.get_remaining(), 0);
+        // The limiter can't tell when the store is back, so the denial is not retryable
+        let decision = closed.try_acquire_decision(1);
+        assert!(!decision.can_retry());
+        assert_eq!(closed.get_retry_after(1), Duration::MAX);
+        let err = closed.try_acquire_checked(1).unwrap_err();
+        assert!(matches!(err, RateLimitError::Backend(_)));
+
+        let open = StoreLimiter::new(algorithm, Unreachable, "api").with_fail_open(true);
+        assert!(open.try_acquire(1));
+        assert_eq!(open.get_remaining(), 3);
+        assert!(open.try_acquire_checked(1).unwrap().allowed);
+
+        // A state that doesn't decode is reported like a failing store
+        let store = MemoryStore::new();
+        assert_eq!(store.compare_and_set("api", None, b"garbage"), Ok(true));
+        let corrupt = StoreLimiter::new(algorithm, &store, "api");
+        assert!(matches!(
+            corrupt.try_acquire_checked(1),
+            Err(RateLimitError::Backend(_))
+        ));
+    }
+}
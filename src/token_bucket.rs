@@ -1,4 +1,9 @@
+pub mod atomic;
 pub mod r#impl;
+pub mod state;
 pub mod tests;
 
-pub use r#impl::{TokenBucket, TokenBucketShared};
+pub use crate::core::{Decision, Rate, RateLimitError, RateLimiter, RateLimiterShared};
+pub use atomic::AtomicTokenBucket;
+pub use r#impl::{TokenBucket, TokenBucketBuilder, TokenBucketShared};
+pub use state::{TokenBucketAlgorithm, TokenBucketState};
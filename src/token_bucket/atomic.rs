@@ -0,0 +1,207 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::clock::{Clock, SystemClock};
+use crate::core::{as_millis, unix_time_after, Decision, Rate, RateLimitError, RateLimiterShared};
+use crate::token_bucket::TokenBucket;
+
+// *** ATOMIC TOKEN BUCKET ***
+/// Lock-free token bucket for hot paths.
+///
+/// Instead of a token count it stores the "theoretical arrival time" (the moment the bucket
+/// is full again) as nanoseconds in a single `AtomicU64`, updated with a CAS loop. Behaves
+/// like `TokenBucketShared` with the same capacity and rate.
+///
+/// Every token costs the same `Rate::interval`, so the debt is always a whole number of tokens
+/// and rates like 3/s don't lose a token of burst to rounding.
+pub struct AtomicTokenBucket<C = SystemClock> {
+    capacity: u32,
+    rate: Rate,
+    origin: Instant,
+    full_at: AtomicU64,
+    clock: C,
+}
+
+impl AtomicTokenBucket {
+    pub fn new(capacity: u32, refill_rate: u32) -> Self {
+        Self::with_clock(capacity, refill_rate, SystemClock)
+    }
+
+    pub fn try_new(capacity: u32, refill_rate: u32) -> Result<Self, RateLimitError> {
+        TokenBucket::builder()
+            .capacity(capacity)
+            .refill_rate(refill_rate)
+            .build_atomic()
+    }
+
+    pub fn with_rate(capacity: u32, rate: Rate) -> Self {
+        Self::with_rate_and_clock(capacity, rate, SystemClock)
+    }
+}
+
+impl<C: Clock> AtomicTokenBucket<C> {
+    pub fn with_clock(capacity: u32, refill_rate: u32, clock: C) -> Self {
+        Self::with_rate_and_clock(capacity, Rate::per_second(refill_rate), clock)
+    }
+
+    pub fn with_rate_and_clock(capacity: u32, rate: Rate, clock: C) -> Self {
+        Self {
+            capacity,
+            rate,
+            origin: clock.now(),
+            full_at: AtomicU64::new(0),
+            clock,
+        }
+    }
+
+    pub(crate) fn with_tokens(self, tokens: u32) -> Self {
+        let missing = self.cost(self.capacity.saturating_sub(tokens));
+        self.full_at.store(missing, Ordering::Relaxed);
+        self
+    }
+
+    fn now_nanos(&self) -> u64 {
+        as_nanos(self.clock.now().saturating_duration_since(self.origin))
+    }
+
+    /// Time it takes to produce a single token, in nanoseconds.
+    fn token_time(&self) -> u64 {
+        as_nanos(self.rate.interval()).max(1)
+    }
+
+    /// Time it takes to produce `tokens`, a whole multiple of `token_time`.
+    fn cost(&self, tokens: u32) -> u64 {
+        self.token_time().saturating_mul(tokens as u64)
+    }
+
+    /// Time the bucket needs to refill completely from empty.
+    fn burst_time(&self) -> u64 {
+        self.cost(self.capacity)
+    }
+
+    /// Time until the bucket is full again.
+    fn reset_after(&self) -> Duration {
+        let full_at = self.full_at.load(Ordering::Acquire);
+        Duration::from_nanos(full_at.saturating_sub(self.now_nanos()))
+    }
+
+    fn remaining_at(&self, full_at: u64, now: u64) -> u32 {
+        let debt = full_at.saturating_sub(now);
+        let available = self.burst_time().saturating_sub(debt);
+        (available / self.token_time()).min(self.capacity as u64) as u32
+    }
+
+    fn retry_after_at(&self, tokens: u32, full_at: u64, now: u64) -> Duration {
+        if tokens > self.capacity || !self.rate.is_valid() {
+            return Duration::MAX;
+        }
+        let debt = full_at.saturating_sub(now);
+        let allowed_debt = self.cost(self.capacity - tokens);
+        Duration::from_nanos(debt.saturating_sub(allowed_debt))
+    }
+
+    fn decision_at(&self, allowed: bool, tokens: u32, full_at: u64, now: u64) -> Decision {
+        Decision {
+            allowed,
+            limit: self.capacity,
+            remaining: self.remaining_at(full_at, now),
+            retry_after: if allowed {
+                Duration::ZERO
+            } else {
+                self.retry_after_at(tokens, full_at, now)
+            },
+            reset: unix_time_after(
+                self.clock.now_system(),
+                Duration::from_nanos(full_at.saturating_sub(now)),
+            )
+            .as_secs(),
+        }
+    }
+
+    /// Returns whether the tokens were acquired, along with the state it was decided on.
+    fn acquire(&self, tokens: u32) -> (bool, u64, u64) {
+        let cost = self.cost(tokens);
+        let burst = self.burst_time();
+        let mut full_at = self.full_at.load(Ordering::Acquire);
+        loop {
+            let now = self.now_nanos();
+            let new_full_at = full_at.max(now).saturating_add(cost);
+            if tokens > self.capacity || !self.rate.is_valid() || new_full_at - now > burst {
+                return (false, full_at, now);
+            }
+
+            match self.full_at.compare_exchange_weak(
+                full_at,
+                new_full_at,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return (true, new_full_at, now),
+                Err(actual) => full_at = actual,
+            }
+        }
+    }
+}
+
+impl<C: Clock> RateLimiterShared for AtomicTokenBucket<C> {
+    fn refresh(&self) {
+        // State is derived from the clock on every call, nothing to refresh
+    }
+
+    fn try_acquire(&self, tokens: u32) -> bool {
+        self.acquire(tokens).0
+    }
+
+    fn try_acquire_decision(&self, tokens: u32) -> Decision {
+        let (allowed, full_at, now) = self.acquire(tokens);
+        self.decision_at(allowed, tokens, full_at, now)
+    }
+
+    fn try_acquire_checked(&self, tokens: u32) -> Result<Decision, RateLimitError> {
+        if tokens > self.capacity {
+            return Err(RateLimitError::InsufficientCapacity {
+                requested: tokens,
+                capacity: self.capacity,
+            });
+        }
+
+        let decision = self.try_acquire_decision(tokens);
+        if decision.allowed {
+            Ok(decision)
+        } else {
+            Err(RateLimitError::Denied(decision))
+        }
+    }
+
+    fn get_limit(&self) -> u32 {
+        self.capacity
+    }
+
+    fn get_remaining(&self) -> u32 {
+        self.remaining_at(self.full_at.load(Ordering::Acquire), self.now_nanos())
+    }
+
+    fn get_used(&self) -> u32 {
+        self.capacity - self.get_remaining()
+    }
+
+    fn get_reset(&self) -> u64 {
+        unix_time_after(self.clock.now_system(), self.reset_after()).as_secs()
+    }
+
+    fn get_reset_ms(&self) -> u64 {
+        as_millis(unix_time_after(self.clock.now_system(), self.reset_after()))
+    }
+
+    fn get_retry_after(&self, tokens: u32) -> Duration {
+        self.retry_after_at(
+            tokens,
+            self.full_at.load(Ordering::Acquire),
+            self.now_nanos(),
+        )
+    }
+}
+
+fn as_nanos(duration: Duration) -> u64 {
+    duration.as_nanos().min(u64::MAX as u128) as u64
+}
@@ -1,128 +1,299 @@
-use std::sync::{Arc, Mutex};
-use std::time::{Instant, UNIX_EPOCH};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use crate::clock::{Clock, Epoch, SystemClock};
+// Kept for code importing the traits from `token_bucket::r#impl`
+use crate::core::{as_millis, unix_time_after, Rate};
+pub use crate::core::{Decision, RateLimitError, RateLimiter, RateLimiterShared};
+use crate::store::Algorithm;
+use crate::token_bucket::{AtomicTokenBucket, TokenBucketAlgorithm, TokenBucketState};
 
 // *** TOKEN BUCKET ***
-pub trait RateLimiter {
-    fn refresh(&mut self);
-    fn try_acquire(&mut self, tokens: u32) -> bool;
-
-    fn get_limit(&self) -> u32;
-    fn get_remaining(&self) -> u32;
-    fn get_used(&self) -> u32;
-    fn get_reset(&self) -> u64;
+pub struct TokenBucket<C = SystemClock> {
+    pub(crate) algorithm: TokenBucketAlgorithm,
+    pub(crate) state: TokenBucketState,
+    epoch: Epoch,
+    pub(crate) clock: C,
+}
+
+impl TokenBucket {
+    pub fn new(capacity: u32, refill_rate: u32) -> Self {
+        Self::with_clock(capacity, refill_rate, SystemClock)
+    }
+
+    pub fn try_new(capacity: u32, refill_rate: u32) -> Result<Self, RateLimitError> {
+        Self::builder()
+            .capacity(capacity)
+            .refill_rate(refill_rate)
+            .build()
+    }
+
+    pub fn with_rate(capacity: u32, rate: Rate) -> Self {
+        Self::with_rate_and_clock(capacity, rate, SystemClock)
+    }
+
+    pub fn builder() -> TokenBucketBuilder {
+        TokenBucketBuilder::new()
+    }
+}
+
+impl<C: Clock> TokenBucket<C> {
+    pub fn with_clock(capacity: u32, refill_rate: u32, clock: C) -> Self {
+        Self::with_rate_and_clock(capacity, Rate::per_second(refill_rate), clock)
+    }
+
+    pub fn with_rate_and_clock(capacity: u32, rate: Rate, clock: C) -> Self {
+        let algorithm = TokenBucketAlgorithm::new(capacity, rate);
+        let epoch = Epoch::new(&clock);
+        Self {
+            algorithm,
+            state: algorithm.initial_state(epoch.now(&clock)),
+            epoch,
+            clock,
+        }
+    }
+
+    pub(crate) fn now(&self) -> Duration {
+        self.epoch.now(&self.clock)
+    }
+
+    /// Time until the bucket is full again.
+    fn reset_after(&self) -> Duration {
+        self.algorithm.reset_after(&self.state, self.now())
+    }
 }
 
-pub struct TokenBucket {
+// *** TOKEN BUCKET BUILDER ***
+pub struct TokenBucketBuilder<C = SystemClock> {
     capacity: u32,
-    tokens: u32,
-    refill_rate: u32,
-    last_refill: Instant,
+    rate: Rate,
+    initial_tokens: Option<u32>,
+    clock: C,
 }
 
-impl TokenBucket {
-    pub fn new(capacity: u32, refill_rate: u32) -> Self {
+impl TokenBucketBuilder {
+    pub fn new() -> Self {
         Self {
-            capacity,
-            tokens: capacity,
-            refill_rate,
-            last_refill: Instant::now(),
+            capacity: 0,
+            rate: Rate::per_second(0),
+            initial_tokens: None,
+            clock: SystemClock,
         }
     }
 }
 
-impl RateLimiter for TokenBucket {
-    fn refresh(&mut self) {
-        let now = Instant::now();
-        let elapsed = now.duration_since(self.last_refill);
-        let new_tokens = (elapsed.as_secs_f64() * self.refill_rate as f64).floor() as u32;
+impl Default for TokenBucketBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Clock> TokenBucketBuilder<C> {
+    pub fn capacity(mut self, capacity: u32) -> Self {
+        self.capacity = capacity;
+        self
+    }
 
-        if new_tokens > 0 {
-            self.tokens = std::cmp::min(self.capacity, self.tokens + new_tokens);
-            self.last_refill = now;
+    /// Same as `capacity`: the largest number of tokens that can be acquired at once.
+    pub fn burst(self, burst: u32) -> Self {
+        self.capacity(burst)
+    }
+
+    /// Tokens added per second, see `rate` for other periods.
+    pub fn refill_rate(self, refill_rate: u32) -> Self {
+        self.rate(Rate::per_second(refill_rate))
+    }
+
+    pub fn rate(mut self, rate: Rate) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    /// Tokens available right after creation. Defaults to a full bucket.
+    pub fn initial_tokens(mut self, tokens: u32) -> Self {
+        self.initial_tokens = Some(tokens);
+        self
+    }
+
+    pub fn clock<D: Clock>(self, clock: D) -> TokenBucketBuilder<D> {
+        TokenBucketBuilder {
+            capacity: self.capacity,
+            rate: self.rate,
+            initial_tokens: self.initial_tokens,
+            clock,
         }
     }
 
-    fn try_acquire(&mut self, tokens: u32) -> bool {
-        self.refresh();
-        if self.tokens >= tokens {
-            self.tokens -= tokens;
-            true
-        } else {
-            false
+    /// Checks the parameters and returns the initial number of tokens.
+    fn validate(&self) -> Result<u32, RateLimitError> {
+        if self.capacity == 0 {
+            return Err(RateLimitError::InvalidConfig(
+                "token bucket capacity must be greater than zero".to_string(),
+            ));
+        }
+        if !self.rate.is_valid() {
+            return Err(RateLimitError::InvalidConfig(format!(
+                "token bucket refill rate must be greater than zero, got {} per {:?}",
+                self.rate.tokens(),
+                self.rate.period()
+            )));
         }
+        let tokens = self.initial_tokens.unwrap_or(self.capacity);
+        if tokens > self.capacity {
+            return Err(RateLimitError::InvalidConfig(format!(
+                "token bucket initial tokens ({tokens}) exceed capacity ({})",
+                self.capacity
+            )));
+        }
+        Ok(tokens)
+    }
+
+    pub fn build(self) -> Result<TokenBucket<C>, RateLimitError> {
+        let tokens = self.validate()?;
+        let mut bucket = TokenBucket::with_rate_and_clock(self.capacity, self.rate, self.clock);
+        bucket.state.tokens = tokens;
+        Ok(bucket)
+    }
+
+    pub fn build_shared(self) -> Result<TokenBucketShared<C>, RateLimitError> {
+        self.build().map(TokenBucketShared::from)
+    }
+
+    pub fn build_atomic(self) -> Result<AtomicTokenBucket<C>, RateLimitError> {
+        let tokens = self.validate()?;
+        Ok(
+            AtomicTokenBucket::with_rate_and_clock(self.capacity, self.rate, self.clock)
+                .with_tokens(tokens),
+        )
+    }
+}
+
+impl<C: Clock> RateLimiter for TokenBucket<C> {
+    fn refresh(&mut self) {
+        let now = self.now();
+        self.algorithm.refresh(&mut self.state, now);
+    }
+
+    fn try_acquire(&mut self, tokens: u32) -> bool {
+        let now = self.now();
+        self.algorithm.try_acquire(&mut self.state, now, tokens)
     }
 
     fn get_limit(&self) -> u32 {
-        self.capacity
+        self.algorithm.limit()
     }
 
     fn get_remaining(&self) -> u32 {
-        self.tokens
+        self.algorithm.remaining(&self.state, self.now())
     }
 
     fn get_used(&self) -> u32 {
-        self.capacity - self.tokens
+        self.algorithm.used(&self.state, self.now())
     }
 
     fn get_reset(&self) -> u64 {
-        let now = std::time::SystemTime::now();
-        let refill_secs = (self.capacity - self.tokens) as f64 / self.refill_rate as f64;
-        let reset_time = now + std::time::Duration::from_secs_f64(refill_secs);
-        reset_time.duration_since(UNIX_EPOCH).unwrap().as_secs()
+        unix_time_after(self.clock.now_system(), self.reset_after()).as_secs()
     }
-}
 
-// *** TOKEN BUCKET SHARED ***
-pub trait RateLimiterShared {
-    fn refresh(&self);
-    fn try_acquire(&self, tokens: u32) -> bool;
-
-    fn get_limit(&self) -> u32;
-    fn get_remaining(&self) -> u32;
-    fn get_used(&self) -> u32;
-    fn get_reset(&self) -> u64;
+    fn get_reset_ms(&self) -> u64 {
+        as_millis(unix_time_after(self.clock.now_system(), self.reset_after()))
+    }
+
+    fn get_retry_after(&self, tokens: u32) -> Duration {
+        self.algorithm.retry_after(&self.state, self.now(), tokens)
+    }
 }
 
-pub struct TokenBucketShared {
-    inner: Arc<Mutex<TokenBucket>>,
+// *** TOKEN BUCKET SHARED ***
+pub struct TokenBucketShared<C = SystemClock> {
+    pub(crate) inner: Arc<Mutex<TokenBucket<C>>>,
 }
 
 impl TokenBucketShared {
     pub fn new(capacity: u32, refill_rate: u32) -> Self {
+        Self::with_clock(capacity, refill_rate, SystemClock)
+    }
+
+    pub fn try_new(capacity: u32, refill_rate: u32) -> Result<Self, RateLimitError> {
+        TokenBucket::try_new(capacity, refill_rate).map(Self::from)
+    }
+
+    pub fn with_rate(capacity: u32, rate: Rate) -> Self {
+        Self::from(TokenBucket::with_rate(capacity, rate))
+    }
+}
+
+impl<C: Clock> TokenBucketShared<C> {
+    pub fn with_clock(capacity: u32, refill_rate: u32, clock: C) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(TokenBucket::with_clock(
+                capacity,
+                refill_rate,
+                clock,
+            ))),
+        }
+    }
+}
+
+impl<C> From<TokenBucket<C>> for TokenBucketShared<C> {
+    fn from(bucket: TokenBucket<C>) -> Self {
         Self {
-            inner: Arc::new(Mutex::new(TokenBucket::new(capacity, refill_rate))),
+            inner: Arc::new(Mutex::new(bucket)),
         }
     }
 }
 
-impl RateLimiterShared for TokenBucketShared {
+impl<C: Clock> RateLimiterShared for TokenBucketShared<C> {
     fn refresh(&self) {
-        let mut bucket = self.inner.lock().unwrap();
+        let mut bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         bucket.refresh()
     }
 
     fn try_acquire(&self, tokens: u32) -> bool {
-        let mut bucket = self.inner.lock().unwrap();
+        let mut bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         bucket.try_acquire(tokens)
     }
 
+    fn try_acquire_decision(&self, tokens: u32) -> Decision {
+        let mut bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        bucket.try_acquire_decision(tokens)
+    }
+
+    fn try_acquire_checked(&self, tokens: u32) -> Result<Decision, RateLimitError> {
+        let mut bucket = self
+            .inner
+            .lock()
+            .map_err(|_| RateLimitError::PoisonedLock)?;
+        bucket.try_acquire_checked(tokens)
+    }
+
     fn get_limit(&self) -> u32 {
-        let bucket = self.inner.lock().unwrap();
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         bucket.get_limit()
     }
 
     fn get_remaining(&self) -> u32 {
-        let bucket = self.inner.lock().unwrap();
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         bucket.get_remaining()
     }
 
     fn get_used(&self) -> u32 {
-        let bucket = self.inner.lock().unwrap();
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         bucket.get_used()
     }
 
     fn get_reset(&self) -> u64 {
-        let bucket = self.inner.lock().unwrap();
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
         bucket.get_reset()
     }
+
+    fn get_reset_ms(&self) -> u64 {
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        bucket.get_reset_ms()
+    }
+
+    fn get_retry_after(&self, tokens: u32) -> Duration {
+        let bucket = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        bucket.get_retry_after(tokens)
+    }
 }
@@ -0,0 +1,148 @@
+use std::time::Duration;
+
+use crate::core::Rate;
+use crate::store::r#impl::{Algorithm, StateReader, StateWriter};
+
+const TAG: u8 = 1;
+
+// *** TOKEN BUCKET STATE ***
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct TokenBucketState {
+    pub tokens: u32,
+    pub last_refill: Duration,
+    /// Refill progress from before `last_refill`, in nanoseconds times `Rate::tokens`, so the
+    /// fraction of a nanosecond a token takes isn't rounded away between refreshes.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub carry: u32,
+}
+
+// *** TOKEN BUCKET ALGORITHM ***
+/// Token bucket transitions, see `TokenBucket` for a limiter holding its own state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenBucketAlgorithm {
+    capacity: u32,
+    rate: Rate,
+}
+
+impl TokenBucketAlgorithm {
+    pub fn new(capacity: u32, rate: Rate) -> Self {
+        Self { capacity, rate }
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    pub fn rate(&self) -> Rate {
+        self.rate
+    }
+
+    /// Progress towards the next tokens, in nanoseconds times `Rate::tokens`. A token takes
+    /// `Rate::period` in these units, so whole tokens are counted without rounding.
+    fn refill_progress(&self, state: &TokenBucketState, now: Duration) -> u128 {
+        let elapsed = now.saturating_sub(state.last_refill).as_nanos();
+        elapsed * self.rate.tokens() as u128 + state.carry as u128
+    }
+
+    /// Time left until `tokens` more have been produced.
+    fn time_until(&self, state: &TokenBucketState, now: Duration, tokens: u32) -> Duration {
+        if !self.rate.is_valid() {
+            return Duration::MAX;
+        }
+        let needed = tokens as u128 * self.rate.period().as_nanos();
+        let left = needed.saturating_sub(self.refill_progress(state, now));
+        let nanos = left.div_ceil(self.rate.tokens() as u128);
+        Duration::from_nanos(nanos.min(u64::MAX as u128) as u64)
+    }
+}
+
+impl Algorithm for TokenBucketAlgorithm {
+    type State = TokenBucketState;
+
+    fn limit(&self) -> u32 {
+        self.capacity
+    }
+
+    fn initial_state(&self, now: Duration) -> TokenBucketState {
+        TokenBucketState {
+            tokens: self.capacity,
+            last_refill: now,
+            carry: 0,
+        }
+    }
+
+    fn refresh(&self, state: &mut TokenBucketState, now: Duration) {
+        let period = self.rate.period().as_nanos();
+        let progress = self.refill_progress(state, now);
+        let new_tokens = match progress.checked_div(period) {
+            Some(tokens) => tokens.min(u32::MAX as u128) as u32,
+            None => u32::MAX,
+        };
+
+        if new_tokens >= self.capacity - state.tokens {
+            // A full bucket doesn't bank progress towards the next token
+            state.tokens = self.capacity;
+            state.last_refill = now;
+            state.carry = 0;
+        } else if new_tokens > 0 {
+            // Only consume the progress spent on whole tokens, carrying the exact remainder
+            let left = progress - new_tokens as u128 * period;
+            let rate_tokens = self.rate.tokens() as u128;
+            state.tokens += new_tokens;
+            state.last_refill = now - Duration::from_nanos((left / rate_tokens) as u64);
+            state.carry = (left % rate_tokens) as u32;
+        }
+    }
+
+    fn try_acquire(&self, state: &mut TokenBucketState, now: Duration, tokens: u32) -> bool {
+        self.refresh(state, now);
+        if state.tokens >= tokens {
+            state.tokens -= tokens;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn remaining(&self, state: &TokenBucketState, _now: Duration) -> u32 {
+        state.tokens
+    }
+
+    fn used(&self, state: &TokenBucketState, _now: Duration) -> u32 {
+        self.capacity - state.tokens
+    }
+
+    fn reset_after(&self, state: &TokenBucketState, now: Duration) -> Duration {
+        self.time_until(state, now, self.capacity - state.tokens)
+    }
+
+    fn retry_after(&self, state: &TokenBucketState, now: Duration, tokens: u32) -> Duration {
+        if tokens > self.capacity || !self.rate.is_valid() {
+            return Duration::MAX;
+        }
+        if state.tokens >= tokens {
+            return Duration::ZERO;
+        }
+
+        self.time_until(state, now, tokens - state.tokens)
+    }
+
+    fn encode(&self, state: &TokenBucketState) -> Vec<u8> {
+        StateWriter::new(TAG)
+            .u32(state.tokens)
+            .duration(state.last_refill)
+            .u32(state.carry)
+            .finish()
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Option<TokenBucketState> {
+        let mut reader = StateReader::new(bytes, TAG)?;
+        let state = TokenBucketState {
+            tokens: reader.u32()?.min(self.capacity),
+            last_refill: reader.duration()?,
+            carry: reader.u32()?,
+        };
+        reader.is_empty().then_some(state)
+    }
+}
@@ -0,0 +1,137 @@
+#[cfg(test)]
+mod sequential_tests {
+    use crate::clock::MockClock;
+    use crate::core::{Rate, RateLimitError, RateLimiterShared};
+    use crate::token_bucket::{AtomicTokenBucket, TokenBucket, TokenBucketShared};
+    use std::time::Duration;
+
+    #[test]
+    fn basic_test() {
+        let clock = MockClock::new();
+        let bucket = AtomicTokenBucket::with_clock(10, 1, clock.clone());
+        assert_eq!(bucket.get_limit(), 10);
+        assert_eq!(bucket.get_remaining(), 10);
+        assert_eq!(bucket.get_used(), 0);
+
+        assert!(bucket.try_acquire(5));
+        assert_eq!(bucket.get_remaining(), 5);
+        assert_eq!(bucket.get_used(), 5);
+
+        assert!(bucket.try_acquire(5));
+        assert_eq!(bucket.get_remaining(), 0);
+        assert!(!bucket.try_acquire(1));
+        assert_eq!(bucket.get_retry_after(1), Duration::from_secs(1));
+        assert_eq!(bucket.get_retry_after(3), Duration::from_secs(3));
+        assert_eq!(bucket.get_retry_after(11), Duration::MAX);
+
+        clock.advance(Duration::from_millis(1500));
+        assert_eq!(bucket.get_remaining(), 1);
+        assert_eq!(bucket.get_retry_after(2), Duration::from_millis(500));
+
+        let decision = bucket.try_acquire_decision(2);
+        assert!(!decision.allowed);
+        assert_eq!(decision.remaining, 1);
+        assert_eq!(decision.retry_after, Duration::from_millis(500));
+
+        clock.advance(Duration::from_secs(20));
+        assert_eq!(bucket.get_remaining(), 10);
+    }
+
+    #[test]
+    fn builder_test() {
+        let clock = MockClock::new();
+        let bucket = TokenBucket::builder()
+            .capacity(5)
+            .rate(Rate::per(5, Duration::from_secs(2)))
+            .initial_tokens(2)
+            .clock(clock.clone())
+            .build_atomic()
+            .unwrap();
+        assert_eq!(bucket.get_remaining(), 2);
+
+        let mut acquired = 0;
+        for _ in 0..40 {
+            clock.advance(Duration::from_millis(300));
+            while bucket.try_acquire(1) {
+                acquired += 1;
+            }
+        }
+        assert_eq!(acquired, 2 + 30);
+
+        assert!(matches!(
+            AtomicTokenBucket::try_new(10, 0),
+            Err(RateLimitError::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            bucket.try_acquire_checked(6),
+            Err(RateLimitError::InsufficientCapacity { .. })
+        ));
+    }
+
+    #[test]
+    fn matches_shared_test() {
+        for capacity in [3, 7] {
+            let clock = MockClock::new();
+            let atomic = AtomicTokenBucket::with_clock(capacity, capacity, clock.clone());
+            let shared = TokenBucketShared::with_clock(capacity, capacity, clock.clone());
+
+            for _ in 0..capacity {
+                assert!(atomic.try_acquire(1));
+                assert!(shared.try_acquire(1));
+                assert_eq!(atomic.get_remaining(), shared.get_remaining());
+            }
+            assert!(!atomic.try_acquire(1));
+            assert!(!shared.try_acquire(1));
+
+            for step in 0..200u64 {
+                clock.advance(Duration::from_millis(step * 53 % 170));
+                let tokens = 1 + (step % 3) as u32;
+                assert_eq!(
+                    atomic.try_acquire(tokens),
+                    shared.try_acquire(tokens),
+                    "capacity {capacity}, step {step}"
+                );
+                assert_eq!(atomic.get_remaining(), shared.get_remaining());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod parallel_tests {
+    use crate::core::RateLimiterShared;
+    use crate::token_bucket::AtomicTokenBucket;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+
+    #[test]
+    fn race_condition_test() {
+        let bucket = Arc::new(AtomicTokenBucket::new(10, 1));
+        let success_count = Arc::new(AtomicU32::new(0));
+        let barrier = Arc::new(Barrier::new(21));
+
+        let mut handles = vec![];
+        for _ in 0..20 {
+            let bucket_clone = Arc::clone(&bucket);
+            let success_count_clone = Arc::clone(&success_count);
+            let barrier_clone = Arc::clone(&barrier);
+
+            handles.push(thread::spawn(move || {
+                barrier_clone.wait();
+                if bucket_clone.try_acquire(1) {
+                    success_count_clone.fetch_add(1, Ordering::SeqCst);
+                }
+            }));
+        }
+
+        barrier.wait();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let result = success_count.load(Ordering::SeqCst);
+        assert_eq!(result, 10, "Race condition: {} tokens acquired!", result);
+        assert_eq!(bucket.get_remaining(), 0);
+    }
+}
@@ -1,2 +1,3 @@
+mod atomic_token_bucket_tests;
 mod token_bucket_shared_tests;
 mod token_bucket_tests;
@@ -1,7 +1,9 @@
 #[cfg(test)]
 mod sequential_tests {
-    use crate::token_bucket::r#impl::RateLimiterShared;
+    use crate::clock::MockClock;
+    use crate::core::{RateLimitError, RateLimiterShared};
     use crate::token_bucket::TokenBucketShared;
+    use std::sync::Arc;
     use std::thread;
     use std::time::Duration;
 
@@ -51,11 +53,47 @@ mod sequential_tests {
         assert_eq!(bucket.get_remaining(), 2);
         assert_eq!(bucket.get_used(), 8);
     }
+
+    #[test]
+    fn mock_clock_test() {
+        let clock = MockClock::new();
+        let bucket = TokenBucketShared::with_clock(10, 1, clock.clone());
+
+        assert!(bucket.try_acquire(10));
+        assert!(!bucket.try_acquire(1));
+
+        clock.advance(Duration::from_secs(2));
+        bucket.refresh();
+        assert_eq!(bucket.get_remaining(), 2);
+        assert_eq!(bucket.get_used(), 8);
+    }
+
+    #[test]
+    fn poisoned_lock_test() {
+        let bucket = TokenBucketShared::with_clock(3, 1, MockClock::new());
+        let inner = Arc::clone(&bucket.inner);
+        thread::spawn(move || {
+            let _guard = inner.lock().unwrap();
+            panic!("poison the lock");
+        })
+        .join()
+        .unwrap_err();
+
+        // Only the checked acquire reports the poisoned lock, the rest keep using the state
+        assert_eq!(
+            bucket.try_acquire_checked(1),
+            Err(RateLimitError::PoisonedLock)
+        );
+        assert!(bucket.try_acquire(2));
+        assert_eq!(bucket.get_remaining(), 1);
+        assert!(bucket.try_acquire_decision(1).allowed);
+        assert_eq!(bucket.get_retry_after(1), Duration::from_secs(1));
+    }
 }
 
 #[cfg(test)]
 mod parallel_tests {
-    use crate::token_bucket::r#impl::RateLimiterShared;
+    use crate::core::RateLimiterShared;
     use crate::token_bucket::TokenBucketShared;
     use std::sync::atomic::{AtomicU32, Ordering};
     use std::sync::{Arc, Barrier};
@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod sequential_tests {
-    use crate::token_bucket::r#impl::RateLimiter;
+    use crate::clock::MockClock;
+    use crate::core::{Rate, RateLimitError, RateLimiter};
     use crate::token_bucket::TokenBucket;
     use std::thread;
     use std::time::Duration;
@@ -51,4 +52,132 @@ mod sequential_tests {
         assert_eq!(bucket.get_remaining(), 2);
         assert_eq!(bucket.get_used(), 8);
     }
+
+    #[test]
+    fn decision_test() {
+        let mut bucket = TokenBucket::new(10, 2);
+
+        let decision = bucket.try_acquire_decision(8);
+        assert!(decision.allowed);
+        assert_eq!(decision.limit, 10);
+        assert_eq!(decision.remaining, 2);
+        assert_eq!(decision.retry_after, Duration::ZERO);
+
+        let decision = bucket.try_acquire_decision(4);
+        assert!(!decision.allowed);
+        assert_eq!(decision.remaining, 2);
+        assert!(decision.retry_after >= Duration::from_millis(900));
+        assert!(decision.retry_after <= Duration::from_secs(1));
+
+        let decision = bucket.try_acquire_decision(11);
+        assert!(!decision.allowed);
+        assert_eq!(decision.retry_after, Duration::MAX);
+    }
+
+    #[test]
+    fn mock_clock_test() {
+        let clock = MockClock::new();
+        let mut bucket = TokenBucket::with_clock(10, 2, clock.clone());
+
+        assert!(bucket.try_acquire(10));
+        assert!(!bucket.try_acquire(1));
+
+        clock.advance(Duration::from_millis(500));
+        assert!(bucket.try_acquire(1));
+        assert!(!bucket.try_acquire(1));
+
+        clock.advance(Duration::from_secs(10));
+        bucket.refresh();
+        assert_eq!(bucket.get_remaining(), 10);
+        assert_eq!(bucket.get_used(), 0);
+    }
+
+    #[test]
+    fn builder_test() {
+        assert!(matches!(
+            TokenBucket::try_new(10, 0),
+            Err(RateLimitError::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            TokenBucket::try_new(0, 1),
+            Err(RateLimitError::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            TokenBucket::builder()
+                .burst(10)
+                .refill_rate(1)
+                .initial_tokens(11)
+                .build(),
+            Err(RateLimitError::InvalidConfig(_))
+        ));
+
+        let mut bucket = TokenBucket::builder()
+            .burst(10)
+            .refill_rate(1)
+            .initial_tokens(3)
+            .build()
+            .unwrap();
+        assert_eq!(bucket.get_limit(), 10);
+        assert_eq!(bucket.get_remaining(), 3);
+        assert!(bucket.try_acquire(3));
+        assert!(!bucket.try_acquire(1));
+    }
+
+    #[test]
+    fn fractional_rate_test() {
+        let clock = MockClock::new();
+        let mut bucket = TokenBucket::builder()
+            .capacity(1)
+            .rate(Rate::per(1, Duration::from_secs(5)))
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+
+        assert!(bucket.try_acquire(1));
+        clock.advance(Duration::from_millis(4900));
+        assert!(!bucket.try_acquire(1));
+        assert_eq!(bucket.get_retry_after(1), Duration::from_millis(100));
+        clock.advance(Duration::from_millis(100));
+        assert!(bucket.try_acquire(1));
+
+        // 2.5 tokens per second, polled at intervals that never line up with a whole token
+        let mut bucket = TokenBucket::builder()
+            .capacity(100)
+            .rate(Rate::per(5, Duration::from_secs(2)))
+            .initial_tokens(0)
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+
+        let mut acquired = 0;
+        for _ in 0..40 {
+            clock.advance(Duration::from_millis(300));
+            while bucket.try_acquire(1) {
+                acquired += 1;
+            }
+        }
+        assert_eq!(acquired, 30);
+    }
+    #[test]
+    fn long_run_throughput_test() {
+        let clock = MockClock::new();
+        let mut bucket = TokenBucket::builder()
+            .capacity(100)
+            .rate(Rate::per(3, Duration::from_secs(1)))
+            .initial_tokens(0)
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+
+        // A token takes a third of a second, which no whole number of nanoseconds matches
+        let mut acquired = 0;
+        for _ in 0..2000 {
+            clock.advance(Duration::from_millis(500));
+            while bucket.try_acquire(1) {
+                acquired += 1;
+            }
+        }
+        assert_eq!(acquired, 3000);
+        assert_eq!(bucket.get_retry_after(1), Duration::from_nanos(333_333_334));
+    }
 }
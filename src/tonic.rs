@@ -0,0 +1,5 @@
+pub mod r#impl;
+pub mod tests;
+
+pub use crate::core::{Decision, KeyedRateLimiterShared, RateLimiterShared};
+pub use r#impl::{KeyExtractor, MetadataKey, RateLimitInfo, RateLimitLayer, RateLimitService};
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::future::{ready, Future};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use ::tonic::metadata::MetadataMap;
+use ::tonic::{Code, Status};
+use http::{HeaderMap, HeaderName, Request, Response};
+use tonic_types::{ErrorDetails, StatusExt};
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::core::{Decision, KeyedRateLimiterShared, RateLimiterShared};
+use crate::headers::RateLimitHeaders;
+
+type Check = Arc<dyn Fn(&HeaderMap) -> Decision + Send + Sync>;
+
+// *** KEY EXTRACTOR ***
+/// Picks the key a call is limited under from its metadata. Any `Fn(&HeaderMap) -> K` closure
+/// works, gRPC metadata travels as HTTP/2 headers.
+pub trait KeyExtractor: Send + Sync + 'static {
+    type Key;
+
+    fn extract(&self, metadata: &HeaderMap) -> Self::Key;
+}
+
+impl<F, K> KeyExtractor for F
+where
+    F: Fn(&HeaderMap) -> K + Send + Sync + 'static,
+{
+    type Key = K;
+
+    fn extract(&self, metadata: &HeaderMap) -> K {
+        self(metadata)
+    }
+}
+
+/// Keys by the value of an ASCII metadata entry, e.g. `authorization`. Calls without it share
+/// `None`.
+#[derive(Debug, Clone)]
+pub struct MetadataKey(pub HeaderName);
+
+impl MetadataKey {
+    pub fn new(name: HeaderName) -> Self {
+        Self(name)
+    }
+
+    /// Panics if `name` is not a valid lowercase metadata key.
+    pub fn from_static(name: &'static str) -> Self {
+        Self(HeaderName::from_static(name))
+    }
+}
+
+impl KeyExtractor for MetadataKey {
+    type Key = Option<String>;
+
+    fn extract(&self, metadata: &HeaderMap) -> Self::Key {
+        metadata
+            .get(&self.0)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned)
+    }
+}
+
+// *** RATE LIMIT INFO ***
+/// The most restrictive decision made for the current call, in the request extensions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitInfo(pub Decision);
+
+// *** RATE LIMIT LAYER ***
+/// Takes one token per call from every limiter configured for its method. A throttled call
+/// fails with `Status::resource_exhausted` carrying a `RetryInfo` detail and the `RateLimit-*`
+/// metadata, calls that pass get the same metadata in the response headers.
+///
+/// A path is either a full method `/package.Service/Method` or a service `/package.Service`
+/// covering all of its methods, method limits take precedence. Limits of a path are checked
+/// in the order they were added and tokens taken before a denial are not returned.
+#[derive(Clone, Default)]
+pub struct RateLimitLayer {
+    methods: Arc<HashMap<String, Vec<Check>>>,
+}
+
+impl RateLimitLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn method<L>(self, path: impl Into<String>, limiter: L) -> Self
+    where
+        L: RateLimiterShared + Send + Sync + 'static,
+    {
+        self.method_from_arc(path, Arc::new(limiter))
+    }
+
+    /// Shares `limiter` with other paths or code outside the service stack.
+    pub fn method_from_arc<L>(self, path: impl Into<String>, limiter: Arc<L>) -> Self
+    where
+        L: RateLimiterShared + Send + Sync + 'static,
+    {
+        self.with_check(
+            path.into(),
+            Arc::new(move |_| limiter.try_acquire_decision(1)),
+        )
+    }
+
+    /// Limits every caller of the path separately, e.g. by
+    /// `MetadataKey::from_static("authorization")`.
+    pub fn method_keyed<L, E>(self, path: impl Into<String>, limiter: L, extractor: E) -> Self
+    where
+        L: KeyedRateLimiterShared<E::Key> + Send + Sync + 'static,
+        E: KeyExtractor,
+    {
+        self.method_keyed_from_arc(path, Arc::new(limiter), extractor)
+    }
+
+    pub fn method_keyed_from_arc<L, E>(
+        self,
+        path: impl Into<String>,
+        limiter: Arc<L>,
+        extractor: E,
+    ) -> Self
+    where
+        L: KeyedRateLimiterShared<E::Key> + Send + Sync + 'static,
+        E: KeyExtractor,
+    {
+        self.with_check(
+            path.into(),
+            Arc::new(move |metadata| limiter.try_acquire_decision(&extractor.extract(metadata), 1)),
+        )
+    }
+
+    fn with_check(mut self, path: String, check: Check) -> Self {
+        Arc::make_mut(&mut self.methods)
+            .entry(path)
+            .or_default()
+            .push(check);
+        self
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            methods: Arc::clone(&self.methods),
+        }
+    }
+}
+
+// *** RATE LIMIT SERVICE ***
+#[derive(Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    methods: Arc<HashMap<String, Vec<Check>>>,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for RateLimitService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Error: Send + 'static,
+    S::Future: Send + 'static,
+    ResBody: Default + Send + 'static,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        let methods = Arc::clone(&self.methods);
+        let Some(checks) = checks(&methods, req.uri().path()) else {
+            return Box::pin(self.inner.call(req));
+        };
+
+        let mut strictest: Option<Decision> = None;
+        for check in checks {
+            let decision = check(req.headers());
+            if !decision.allowed {
+                return Box::pin(ready(Ok(resource_exhausted(&decision).into_http())));
+            }
+            if strictest.is_none_or(|strictest| decision.remaining < strictest.remaining) {
+                strictest = Some(decision);
+            }
+        }
+        let Some(decision) = strictest else {
+            return Box::pin(self.inner.call(req));
+        };
+
+        req.extensions_mut().insert(RateLimitInfo(decision));
+        let headers = RateLimitHeaders::from_decision(&decision);
+        let future = self.inner.call(req);
+        Box::pin(async move {
+            let mut response = future.await?;
+            headers.insert_into(response.headers_mut());
+            Ok(response)
+        })
+    }
+}
+
+fn checks<'a>(methods: &'a HashMap<String, Vec<Check>>, path: &str) -> Option<&'a [Check]> {
+    let service = path.rsplit_once('/').map_or(path, |(service, _)| service);
+    methods
+        .get(path)
+        .or_else(|| methods.get(service))
+        .map(Vec::as_slice)
+}
+
+fn resource_exhausted(decision: &Decision) -> Status {
+    let mut metadata = HeaderMap::new();
+    RateLimitHeaders::from_decision(decision).insert_into(&mut metadata);
+    let details = if decision.can_retry() {
+        ErrorDetails::with_retry_info(Some(decision.retry_after))
+    } else {
+        ErrorDetails::new()
+    };
+    Status::with_error_details_and_metadata(
+        Code::ResourceExhausted,
+        "rate limit exceeded",
+        details,
+        MetadataMap::from_headers(metadata),
+    )
+}
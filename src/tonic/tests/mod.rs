@@ -0,0 +1 @@
+mod tonic_tests;
@@ -0,0 +1,207 @@
+#[cfg(test)]
+mod sequential_tests {
+    use crate::clock::MockClock;
+    use crate::keyed::KeyedLimiter;
+    use crate::token_bucket::{TokenBucket, TokenBucketShared};
+    use crate::tonic::{MetadataKey, RateLimitInfo, RateLimitLayer};
+    use ::tonic::{Code, Status};
+    use ::tower::{service_fn, Layer, ServiceExt};
+    use http::{Request, Response};
+    use std::convert::Infallible;
+    use std::time::Duration;
+    use tonic_types::StatusExt;
+
+    const SAY_HELLO: &str = "/helloworld.Greeter/SayHello";
+    const SAY_BYE: &str = "/helloworld.Greeter/SayBye";
+
+    async fn handler(req: Request<()>) -> Result<Response<String>, Infallible> {
+        let body = match req.extensions().get::<RateLimitInfo>() {
+            Some(RateLimitInfo(decision)) => format!("remaining {}", decision.remaining),
+            None => "unlimited".to_owned(),
+        };
+        Ok(Response::new(body))
+    }
+
+    fn call(path: &str, caller: Option<&str>) -> Request<()> {
+        let mut builder = Request::builder().uri(path);
+        if let Some(caller) = caller {
+            builder = builder.header("authorization", caller);
+        }
+        builder.body(()).unwrap()
+    }
+
+    fn grpc_status(response: &Response<String>) -> Option<Status> {
+        Status::from_header_map(response.headers()).filter(|status| status.code() != Code::Ok)
+    }
+
+    #[tokio::test]
+    async fn method_limit_test() {
+        let clock = MockClock::new();
+        let bucket = TokenBucketShared::with_clock(2, 1, clock.clone());
+        let service = RateLimitLayer::new()
+            .method(SAY_HELLO, bucket)
+            .layer(service_fn(handler));
+
+        let response = service
+            .clone()
+            .oneshot(call(SAY_HELLO, None))
+            .await
+            .unwrap();
+        assert!(grpc_status(&response).is_none());
+        assert_eq!(response.headers()["ratelimit-remaining"], "1");
+        assert_eq!(response.body(), "remaining 1");
+        service
+            .clone()
+            .oneshot(call(SAY_HELLO, None))
+            .await
+            .unwrap();
+
+        let response = service
+            .clone()
+            .oneshot(call(SAY_HELLO, None))
+            .await
+            .unwrap();
+        let status = grpc_status(&response).unwrap();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+        let retry_info = status.get_details_retry_info().unwrap();
+        assert_eq!(retry_info.retry_delay, Some(Duration::from_secs(1)));
+        assert_eq!(status.metadata().get("retry-after").unwrap(), "1");
+        assert_eq!(status.metadata().get("ratelimit-remaining").unwrap(), "0");
+        assert!(status.metadata().get("ratelimit-reset").is_some());
+
+        // Other methods are not limited
+        let response = service.clone().oneshot(call(SAY_BYE, None)).await.unwrap();
+        assert_eq!(response.body(), "unlimited");
+
+        clock.advance(Duration::from_secs(1));
+        let response = service.oneshot(call(SAY_HELLO, None)).await.unwrap();
+        assert!(grpc_status(&response).is_none());
+    }
+
+    #[tokio::test]
+    async fn service_limit_test() {
+        let clock = MockClock::new();
+        let service_bucket = TokenBucketShared::with_clock(1, 1, clock.clone());
+        let method_bucket = TokenBucketShared::with_clock(3, 1, clock);
+        let service = RateLimitLayer::new()
+            .method("/helloworld.Greeter", service_bucket)
+            .method(SAY_HELLO, method_bucket)
+            .layer(service_fn(handler));
+
+        // The method limit takes precedence over the service one
+        for remaining in (0..3).rev() {
+            let response = service
+                .clone()
+                .oneshot(call(SAY_HELLO, None))
+                .await
+                .unwrap();
+            assert_eq!(response.body(), &format!("remaining {remaining}"));
+        }
+
+        let response = service.clone().oneshot(call(SAY_BYE, None)).await.unwrap();
+        assert_eq!(response.body(), "remaining 0");
+        let response = service.clone().oneshot(call(SAY_BYE, None)).await.unwrap();
+        assert_eq!(
+            grpc_status(&response).unwrap().code(),
+            Code::ResourceExhausted
+        );
+
+        let response = service
+            .oneshot(call("/other.Service/Call", None))
+            .await
+            .unwrap();
+        assert_eq!(response.body(), "unlimited");
+    }
+
+    #[tokio::test]
+    async fn keyed_by_metadata_test() {
+        let limiter = KeyedLimiter::new(|_: &Option<String>| TokenBucket::new(1, 1));
+        let service = RateLimitLayer::new()
+            .method_keyed(
+                SAY_HELLO,
+                limiter,
+                MetadataKey::from_static("authorization"),
+            )
+            .layer(service_fn(handler));
+
+        let response = service
+            .clone()
+            .oneshot(call(SAY_HELLO, Some("alice")))
+            .await
+            .unwrap();
+        assert!(grpc_status(&response).is_none());
+        let response = service
+            .clone()
+            .oneshot(call(SAY_HELLO, Some("alice")))
+            .await
+            .unwrap();
+        assert_eq!(
+            grpc_status(&response).unwrap().code(),
+            Code::ResourceExhausted
+        );
+
+        // Each caller has its own limit, anonymous calls share one
+        let response = service
+            .clone()
+            .oneshot(call(SAY_HELLO, Some("bob")))
+            .await
+            .unwrap();
+        assert!(grpc_status(&response).is_none());
+        let response = service
+            .clone()
+            .oneshot(call(SAY_HELLO, None))
+            .await
+            .unwrap();
+        assert!(grpc_status(&response).is_none());
+        let response = service.oneshot(call(SAY_HELLO, None)).await.unwrap();
+        assert_eq!(
+            grpc_status(&response).unwrap().code(),
+            Code::ResourceExhausted
+        );
+    }
+
+    #[tokio::test]
+    async fn method_and_caller_limits_test() {
+        let clock = MockClock::new();
+        let global = TokenBucketShared::with_clock(3, 1, clock.clone());
+        let limiter = KeyedLimiter::new(move |_: &Option<String>| {
+            TokenBucket::with_clock(5, 1, clock.clone())
+        });
+        let service = RateLimitLayer::new()
+            .method(SAY_HELLO, global)
+            .method_keyed(SAY_HELLO, limiter, |metadata: &http::HeaderMap| {
+                metadata
+                    .get("authorization")
+                    .map(|value| value.to_str().unwrap().to_owned())
+            })
+            .layer(service_fn(handler));
+
+        // The request carries the decision with the fewest tokens left
+        let response = service
+            .clone()
+            .oneshot(call(SAY_HELLO, Some("alice")))
+            .await
+            .unwrap();
+        assert_eq!(response.body(), "remaining 2");
+        assert_eq!(response.headers()["ratelimit-limit"], "3");
+
+        service
+            .clone()
+            .oneshot(call(SAY_HELLO, Some("bob")))
+            .await
+            .unwrap();
+        service
+            .clone()
+            .oneshot(call(SAY_HELLO, Some("carol")))
+            .await
+            .unwrap();
+        let response = service
+            .oneshot(call(SAY_HELLO, Some("dave")))
+            .await
+            .unwrap();
+        assert_eq!(
+            grpc_status(&response).unwrap().code(),
+            Code::ResourceExhausted
+        );
+    }
+}
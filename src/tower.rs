@@ -0,0 +1,7 @@
+pub mod r#impl;
+pub mod tests;
+
+pub use crate::core::{KeyedRateLimiterShared, RateLimitError, RateLimiterShared};
+pub use r#impl::{
+    BoxError, KeyedRateLimit, KeyedRateLimitLayer, RateLimit, RateLimitLayer, ResponseFuture,
+};
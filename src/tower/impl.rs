@@ -0,0 +1,269 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+
+use pin_project_lite::pin_project;
+use tokio::time::Sleep;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::core::{KeyedRateLimiterShared, RateLimitError, RateLimiterShared, MIN_WAIT};
+
+/// Error of the rate limiting services, a rejection downcasts to `RateLimitError`.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+// *** RATE LIMIT LAYER ***
+/// Takes one token per request from a shared limiter. By default a throttled request fails
+/// with `RateLimitError::Denied`, with `backpressure` the service is not ready until a token
+/// is available instead.
+pub struct RateLimitLayer<L> {
+    limiter: Arc<L>,
+    backpressure: bool,
+}
+
+impl<L: RateLimiterShared> RateLimitLayer<L> {
+    pub fn new(limiter: L) -> Self {
+        Self::from_arc(Arc::new(limiter))
+    }
+
+    pub fn backpressure(limiter: L) -> Self {
+        Self::from_arc(Arc::new(limiter)).with_backpressure(true)
+    }
+
+    /// Shares `limiter` with other layers or code outside the service stack.
+    pub fn from_arc(limiter: Arc<L>) -> Self {
+        Self {
+            limiter,
+            backpressure: false,
+        }
+    }
+
+    pub fn with_backpressure(mut self, backpressure: bool) -> Self {
+        self.backpressure = backpressure;
+        self
+    }
+}
+
+impl<L> Clone for RateLimitLayer<L> {
+    fn clone(&self) -> Self {
+        Self {
+            limiter: Arc::clone(&self.limiter),
+            backpressure: self.backpressure,
+        }
+    }
+}
+
+impl<S, L> Layer<S> for RateLimitLayer<L> {
+    type Service = RateLimit<S, L>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimit {
+            inner,
+            limiter: Arc::clone(&self.limiter),
+            backpressure: self.backpressure,
+            state: State::Idle,
+        }
+    }
+}
+
+// *** RATE LIMIT ***
+enum State {
+    Idle,
+    Waiting(Pin<Box<Sleep>>),
+    /// A token was taken in `poll_ready` for the next `call`.
+    Reserved,
+}
+
+pub struct RateLimit<S, L> {
+    inner: S,
+    limiter: Arc<L>,
+    backpressure: bool,
+    state: State,
+}
+
+impl<S: Clone, L> Clone for RateLimit<S, L> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            limiter: Arc::clone(&self.limiter),
+            backpressure: self.backpressure,
+            state: State::Idle,
+        }
+    }
+}
+
+impl<S, L, Req> Service<Req> for RateLimit<S, L>
+where
+    S: Service<Req>,
+    S::Error: Into<BoxError>,
+    L: RateLimiterShared,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        while self.backpressure {
+            match &mut self.state {
+                State::Reserved => break,
+                State::Waiting(sleep) => {
+                    ready!(sleep.as_mut().poll(cx));
+                    self.state = State::Idle;
+                }
+                State::Idle => match self.limiter.try_acquire_checked(1) {
+                    Ok(_) => self.state = State::Reserved,
+                    Err(RateLimitError::Denied(decision)) => {
+                        let wait = decision.retry_after.max(MIN_WAIT);
+                        self.state = State::Waiting(Box::pin(tokio::time::sleep(wait)));
+                    }
+                    Err(err) => return Poll::Ready(Err(err.into())),
+                },
+            }
+        }
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        // The token reserved by `poll_ready` pays for this request, without one it is taken
+        // here so skipping `poll_ready` can't bypass the limit
+        let state = std::mem::replace(&mut self.state, State::Idle);
+        if self.backpressure && matches!(state, State::Reserved) {
+            return ResponseFuture::inner(self.inner.call(req));
+        }
+
+        match self.limiter.try_acquire_checked(1) {
+            Ok(_) => ResponseFuture::inner(self.inner.call(req)),
+            Err(err) => ResponseFuture::rejected(err),
+        }
+    }
+}
+
+// *** KEYED RATE LIMIT LAYER ***
+/// Takes one token per request from the limiter of the key `extractor` returns for it.
+/// A throttled request fails with `RateLimitError::Denied`.
+pub struct KeyedRateLimitLayer<L, F> {
+    limiter: Arc<L>,
+    extractor: F,
+}
+
+impl<L, F> KeyedRateLimitLayer<L, F> {
+    pub fn new(limiter: L, extractor: F) -> Self {
+        Self::from_arc(Arc::new(limiter), extractor)
+    }
+
+    /// Shares `limiter` with other layers or code outside the service stack.
+    pub fn from_arc(limiter: Arc<L>, extractor: F) -> Self {
+        Self { limiter, extractor }
+    }
+}
+
+impl<L, F: Clone> Clone for KeyedRateLimitLayer<L, F> {
+    fn clone(&self) -> Self {
+        Self {
+            limiter: Arc::clone(&self.limiter),
+            extractor: self.extractor.clone(),
+        }
+    }
+}
+
+impl<S, L, F: Clone> Layer<S> for KeyedRateLimitLayer<L, F> {
+    type Service = KeyedRateLimit<S, L, F>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        KeyedRateLimit {
+            inner,
+            limiter: Arc::clone(&self.limiter),
+            extractor: self.extractor.clone(),
+        }
+    }
+}
+
+// *** KEYED RATE LIMIT ***
+pub struct KeyedRateLimit<S, L, F> {
+    inner: S,
+    limiter: Arc<L>,
+    extractor: F,
+}
+
+impl<S: Clone, L, F: Clone> Clone for KeyedRateLimit<S, L, F> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            limiter: Arc::clone(&self.limiter),
+            extractor: self.extractor.clone(),
+        }
+    }
+}
+
+impl<S, L, F, K, Req> Service<Req> for KeyedRateLimit<S, L, F>
+where
+    S: Service<Req>,
+    S::Error: Into<BoxError>,
+    L: KeyedRateLimiterShared<K>,
+    F: Fn(&Req) -> K,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        let key = (self.extractor)(&req);
+        match self.limiter.try_acquire_checked(&key, 1) {
+            Ok(_) => ResponseFuture::inner(self.inner.call(req)),
+            Err(err) => ResponseFuture::rejected(err),
+        }
+    }
+}
+
+// *** RESPONSE FUTURE ***
+pin_project! {
+    pub struct ResponseFuture<F> {
+        #[pin]
+        kind: Kind<F>,
+    }
+}
+
+pin_project! {
+    #[project = KindProj]
+    enum Kind<F> {
+        Inner { #[pin] future: F },
+        Rejected { error: Option<RateLimitError> },
+    }
+}
+
+impl<F> ResponseFuture<F> {
+    fn inner(future: F) -> Self {
+        Self {
+            kind: Kind::Inner { future },
+        }
+    }
+
+    fn rejected(error: RateLimitError) -> Self {
+        Self {
+            kind: Kind::Rejected { error: Some(error) },
+        }
+    }
+}
+
+impl<F, T, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<T, E>>,
+    E: Into<BoxError>,
+{
+    type Output = Result<T, BoxError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project().kind.project() {
+            KindProj::Inner { future } => future.poll(cx).map_err(Into::into),
+            KindProj::Rejected { error } => {
+                let error = error.take().expect("polled after completion");
+                Poll::Ready(Err(error.into()))
+            }
+        }
+    }
+}
@@ -0,0 +1 @@
+mod tower_tests;
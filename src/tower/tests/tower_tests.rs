@@ -0,0 +1,104 @@
+#[cfg(test)]
+mod sequential_tests {
+    use crate::clock::MockClock;
+    use crate::core::{RateLimitError, RateLimiterShared};
+    use crate::gcra::GcraShared;
+    use crate::keyed::KeyedLimiter;
+    use crate::token_bucket::{TokenBucket, TokenBucketShared};
+    use crate::tower::{BoxError, KeyedRateLimitLayer, RateLimitLayer};
+    use ::tower::{service_fn, Layer, Service, ServiceExt};
+    use std::convert::Infallible;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    async fn double(req: u32) -> Result<u32, Infallible> {
+        Ok(req * 2)
+    }
+
+    fn denied(err: BoxError) -> bool {
+        matches!(
+            err.downcast_ref::<RateLimitError>(),
+            Some(RateLimitError::Denied(_))
+        )
+    }
+
+    #[tokio::test]
+    async fn reject_test() {
+        let clock = MockClock::new();
+        let bucket = Arc::new(TokenBucketShared::with_clock(2, 1, clock.clone()));
+        let mut service = RateLimitLayer::from_arc(Arc::clone(&bucket)).layer(service_fn(double));
+
+        assert_eq!(service.ready().await.unwrap().call(1).await.unwrap(), 2);
+        assert_eq!(service.ready().await.unwrap().call(2).await.unwrap(), 4);
+        let err = service.ready().await.unwrap().call(3).await.unwrap_err();
+        assert!(denied(err));
+        assert_eq!(bucket.get_remaining(), 0);
+
+        clock.advance(Duration::from_secs(1));
+        // Clones share the limiter
+        let clone = service.clone();
+        assert_eq!(clone.oneshot(4).await.unwrap(), 8);
+        assert!(denied(service.oneshot(5).await.unwrap_err()));
+    }
+
+    #[tokio::test]
+    async fn backpressure_test() {
+        let limiter = GcraShared::new(Duration::from_millis(50), Duration::ZERO);
+        let mut service = RateLimitLayer::backpressure(limiter).layer(service_fn(double));
+        let start = Instant::now();
+
+        for req in 0..4 {
+            let response = service.ready().await.unwrap().call(req).await.unwrap();
+            assert_eq!(response, req * 2);
+        }
+
+        // Waited for each token instead of failing
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(150));
+        assert!(elapsed < Duration::from_millis(400));
+    }
+
+    #[tokio::test]
+    async fn backpressure_unreserved_call_test() {
+        let limiter = Arc::new(TokenBucketShared::new(2, 1));
+        let mut service = RateLimitLayer::from_arc(Arc::clone(&limiter))
+            .with_backpressure(true)
+            .layer(service_fn(double));
+
+        // The reservation pays for the first call, the next ones take their own token
+        assert_eq!(service.ready().await.unwrap().call(1).await.unwrap(), 2);
+        assert_eq!(service.call(2).await.unwrap(), 4);
+        assert_eq!(limiter.get_remaining(), 0);
+        assert!(denied(service.call(3).await.unwrap_err()));
+    }
+
+    #[tokio::test]
+    async fn keyed_test() {
+        let limiter = KeyedLimiter::new(|_: &String| TokenBucket::new(1, 1));
+        let layer = KeyedRateLimitLayer::new(limiter, |req: &(String, u32)| req.0.clone());
+        let service = layer.layer(service_fn(|(_, n): (String, u32)| double(n)));
+
+        let alice = || ("alice".to_string(), 1);
+        assert_eq!(service.clone().oneshot(alice()).await.unwrap(), 2);
+        assert!(denied(service.clone().oneshot(alice()).await.unwrap_err()));
+        assert_eq!(
+            service
+                .clone()
+                .oneshot(("bob".to_string(), 2))
+                .await
+                .unwrap(),
+            4
+        );
+    }
+
+    #[tokio::test]
+    async fn insufficient_capacity_test() {
+        let mut service =
+            RateLimitLayer::backpressure(TokenBucketShared::new(0, 1)).layer(service_fn(double));
+        let err = service.ready().await.err().unwrap();
+        assert!(matches!(
+            err.downcast_ref::<RateLimitError>(),
+            Some(RateLimitError::InsufficientCapacity { .. })
+        ));
+    }
+}